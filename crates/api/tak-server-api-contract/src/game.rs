@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use tak_core::{
-    TakAsyncTimeControl, TakBaseGameSettings, TakGameSettings, TakOpening, TakRealtimeTimeControl,
-    TakReserve, TakTimeSettings,
+    TakAsyncTimeControl, TakBaseGameSettings, TakFlatTiebreak, TakGameSettings, TakIncrementMode,
+    TakOpening, TakRealtimeTimeControl, TakRepetitionRule, TakReserve, TakReserveRunout, TakRules,
+    TakTimeSettings,
 };
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -51,6 +52,16 @@ pub enum JsonGameRequestType {
     MoreTime,
 }
 
+/// A conditional response: if the opponent plays `condition`, `response` is played.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonPremove {
+    pub condition: String,
+    pub response: String,
+    #[serde(default)]
+    pub next: Vec<JsonPremove>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 #[serde(
     rename_all = "camelCase",
@@ -64,6 +75,8 @@ pub enum GameStatusType {
     },
     Ended {
         result: String,
+        /// Squares of the winning road(s) in PTN notation, empty unless the game ended by road.
+        winning_road: Vec<String>,
     },
 }
 
@@ -116,7 +129,86 @@ pub struct JsonBaseGameSettings {
     pub half_komi: u32,
     pub pieces: u32,
     pub capstones: u32,
+    /// Black's reserve in a handicap game, `pieces`/`capstones` are then only white's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub black_pieces: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub black_capstones: Option<u32>,
     pub opening: JsonTakOpening,
+    #[serde(default)]
+    pub rules: JsonTakRules,
+}
+
+/// Rule variants, all fields default to the standard rules.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JsonTakRules {
+    pub repetition: JsonRepetitionRule,
+    pub flat_tiebreak: JsonFlatTiebreak,
+    pub reserve_runout: JsonReserveRunout,
+    pub no_standing_stones: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonRepetitionRule {
+    #[default]
+    ThreefoldDraw,
+    Allowed,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonFlatTiebreak {
+    #[default]
+    Draw,
+    Reserve,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonReserveRunout {
+    #[default]
+    EitherEmpty,
+    BothEmpty,
+}
+
+impl JsonTakRules {
+    pub fn from_rules(rules: &TakRules) -> Self {
+        JsonTakRules {
+            repetition: match rules.repetition {
+                TakRepetitionRule::ThreefoldDraw => JsonRepetitionRule::ThreefoldDraw,
+                TakRepetitionRule::Allowed => JsonRepetitionRule::Allowed,
+            },
+            flat_tiebreak: match rules.flat_tiebreak {
+                TakFlatTiebreak::Draw => JsonFlatTiebreak::Draw,
+                TakFlatTiebreak::Reserve => JsonFlatTiebreak::Reserve,
+            },
+            reserve_runout: match rules.reserve_runout {
+                TakReserveRunout::EitherEmpty => JsonReserveRunout::EitherEmpty,
+                TakReserveRunout::BothEmpty => JsonReserveRunout::BothEmpty,
+            },
+            no_standing_stones: rules.no_standing_stones,
+        }
+    }
+
+    pub fn to_rules(&self) -> TakRules {
+        TakRules {
+            repetition: match self.repetition {
+                JsonRepetitionRule::ThreefoldDraw => TakRepetitionRule::ThreefoldDraw,
+                JsonRepetitionRule::Allowed => TakRepetitionRule::Allowed,
+            },
+            flat_tiebreak: match self.flat_tiebreak {
+                JsonFlatTiebreak::Draw => TakFlatTiebreak::Draw,
+                JsonFlatTiebreak::Reserve => TakFlatTiebreak::Reserve,
+            },
+            reserve_runout: match self.reserve_runout {
+                JsonReserveRunout::EitherEmpty => TakReserveRunout::EitherEmpty,
+                JsonReserveRunout::BothEmpty => TakReserveRunout::BothEmpty,
+            },
+            no_standing_stones: self.no_standing_stones,
+        }
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -146,12 +238,82 @@ pub enum JsonTimeSettings {
         contingent_ms: u64,
         increment_ms: u64,
         extra: Option<ExtraTime>,
+        #[serde(default)]
+        increment_mode: JsonIncrementMode,
+    },
+    RealtimeOdds {
+        white: JsonRealtimeTimeControl,
+        black: JsonRealtimeTimeControl,
     },
     Async {
         contingent_ms: u64,
     },
 }
 
+/// The clock settings of one player in a [`JsonTimeSettings::RealtimeOdds`] game.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonRealtimeTimeControl {
+    pub contingent_ms: u64,
+    pub increment_ms: u64,
+    pub extra: Option<ExtraTime>,
+    #[serde(default)]
+    pub increment_mode: JsonIncrementMode,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonIncrementMode {
+    #[default]
+    Fischer,
+    Delay,
+    Bronstein,
+}
+
+impl JsonIncrementMode {
+    fn from_increment_mode(mode: TakIncrementMode) -> Self {
+        match mode {
+            TakIncrementMode::Fischer => JsonIncrementMode::Fischer,
+            TakIncrementMode::Delay => JsonIncrementMode::Delay,
+            TakIncrementMode::Bronstein => JsonIncrementMode::Bronstein,
+        }
+    }
+
+    fn to_increment_mode(self) -> TakIncrementMode {
+        match self {
+            JsonIncrementMode::Fischer => TakIncrementMode::Fischer,
+            JsonIncrementMode::Delay => TakIncrementMode::Delay,
+            JsonIncrementMode::Bronstein => TakIncrementMode::Bronstein,
+        }
+    }
+}
+
+impl JsonRealtimeTimeControl {
+    pub fn from_time_control(tc: &TakRealtimeTimeControl) -> Self {
+        JsonRealtimeTimeControl {
+            contingent_ms: tc.contingent.as_millis() as u64,
+            increment_ms: tc.increment.as_millis() as u64,
+            extra: tc.extra.map(|(on_move, extra_time)| ExtraTime {
+                on_move,
+                extra_ms: extra_time.as_millis() as u64,
+            }),
+            increment_mode: JsonIncrementMode::from_increment_mode(tc.increment_mode),
+        }
+    }
+
+    pub fn to_time_control(&self) -> TakRealtimeTimeControl {
+        TakRealtimeTimeControl {
+            contingent: Duration::from_millis(self.contingent_ms),
+            increment: Duration::from_millis(self.increment_ms),
+            extra: self
+                .extra
+                .as_ref()
+                .map(|extra| (extra.on_move, Duration::from_millis(extra.extra_ms))),
+            increment_mode: self.increment_mode.to_increment_mode(),
+        }
+    }
+}
+
 impl JsonBaseGameSettings {
     pub fn from_base_settings(settings: &TakBaseGameSettings) -> Self {
         JsonBaseGameSettings {
@@ -159,11 +321,14 @@ impl JsonBaseGameSettings {
             half_komi: settings.half_komi,
             pieces: settings.reserve.pieces,
             capstones: settings.reserve.capstones,
+            black_pieces: settings.black_reserve.as_ref().map(|r| r.pieces),
+            black_capstones: settings.black_reserve.as_ref().map(|r| r.capstones),
             opening: match settings.opening {
                 TakOpening::Swap => JsonTakOpening::Swap,
                 TakOpening::NoSwap => JsonTakOpening::NoSwap,
                 TakOpening::DoubleStack => JsonTakOpening::DoubleStack,
             },
+            rules: JsonTakRules::from_rules(&settings.rules),
         }
     }
 
@@ -175,11 +340,20 @@ impl JsonBaseGameSettings {
                 pieces: self.pieces,
                 capstones: self.capstones,
             },
+            black_reserve: if self.black_pieces.is_some() || self.black_capstones.is_some() {
+                Some(TakReserve {
+                    pieces: self.black_pieces.unwrap_or(self.pieces),
+                    capstones: self.black_capstones.unwrap_or(self.capstones),
+                })
+            } else {
+                None
+            },
             opening: match self.opening {
                 JsonTakOpening::Swap => TakOpening::Swap,
                 JsonTakOpening::NoSwap => TakOpening::NoSwap,
                 JsonTakOpening::DoubleStack => TakOpening::DoubleStack,
             },
+            rules: self.rules.to_rules(),
         }
     }
 }
@@ -189,13 +363,18 @@ impl JsonGameSettings {
         JsonGameSettings {
             base: JsonBaseGameSettings::from_base_settings(&settings.base),
             time_settings: match &settings.time_settings {
-                TakTimeSettings::Realtime(tc) => JsonTimeSettings::Realtime {
-                    contingent_ms: tc.contingent.as_millis() as u64,
-                    increment_ms: tc.increment.as_millis() as u64,
-                    extra: tc.extra.map(|(on_move, extra_time)| ExtraTime {
-                        on_move,
-                        extra_ms: extra_time.as_millis() as u64,
-                    }),
+                TakTimeSettings::Realtime(tc) => {
+                    let tc = JsonRealtimeTimeControl::from_time_control(tc);
+                    JsonTimeSettings::Realtime {
+                        contingent_ms: tc.contingent_ms,
+                        increment_ms: tc.increment_ms,
+                        extra: tc.extra,
+                        increment_mode: tc.increment_mode,
+                    }
+                }
+                TakTimeSettings::RealtimeOdds(white, black) => JsonTimeSettings::RealtimeOdds {
+                    white: JsonRealtimeTimeControl::from_time_control(white),
+                    black: JsonRealtimeTimeControl::from_time_control(black),
                 },
                 TakTimeSettings::Async(tc) => JsonTimeSettings::Async {
                     contingent_ms: tc.contingent.as_millis() as u64,
@@ -212,13 +391,19 @@ impl JsonGameSettings {
                     contingent_ms,
                     increment_ms,
                     extra,
-                } => TakTimeSettings::Realtime(TakRealtimeTimeControl {
-                    contingent: Duration::from_millis(*contingent_ms),
-                    increment: Duration::from_millis(*increment_ms),
-                    extra: extra
-                        .as_ref()
-                        .map(|extra| (extra.on_move, Duration::from_millis(extra.extra_ms))),
-                }),
+                    increment_mode,
+                } => TakTimeSettings::Realtime(
+                    JsonRealtimeTimeControl {
+                        contingent_ms: *contingent_ms,
+                        increment_ms: *increment_ms,
+                        extra: extra.clone(),
+                        increment_mode: *increment_mode,
+                    }
+                    .to_time_control(),
+                ),
+                JsonTimeSettings::RealtimeOdds { white, black } => {
+                    TakTimeSettings::RealtimeOdds(white.to_time_control(), black.to_time_control())
+                }
                 JsonTimeSettings::Async {
                     contingent_ms: increment_ms,
                 } => TakTimeSettings::Async(TakAsyncTimeControl {
@@ -20,6 +20,9 @@ pub enum ClientMessage {
         game_id: String,
         action: String,
     },
+    AbortGame {
+        game_id: String,
+    },
     ChatMessage {
         message: String,
         conversation: JsonChatConversation,
@@ -55,6 +58,9 @@ pub enum ServerMessage {
     Error {
         message: String,
         code: u16,
+        /// Identifies why an action was rejected, e.g. `square_occupied`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error_code: Option<String>,
         response_id: Uuid,
     },
     SeekCreated {
@@ -87,6 +93,28 @@ pub enum ServerMessage {
     AccountsOnline {
         account_ids: Vec<String>,
     },
+    ServerAlert {
+        #[serde(flatten)]
+        alert_type: ServerAlertType,
+    },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(
+    tag = "alertType",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ServerAlertType {
+    /// No new games can be started, the server goes down at `deadline`.
+    Shutdown {
+        #[serde(with = "chrono::serde::ts_milliseconds")]
+        deadline: DateTime<Utc>,
+    },
+    ShutdownCancelled,
+    Custom {
+        message: String,
+    },
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -125,6 +153,8 @@ pub enum ServerGameEventType {
     },
     GameEnded {
         result: String,
+        /// Squares of the winning road(s) in PTN notation, empty unless the game ended by road.
+        winning_road: Vec<String>,
     },
     GameRequestChanged {
         player_id: String,
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use axum::{Json, extract::State};
+use chrono::{DateTime, Utc};
+
+use crate::{AppState, ServiceError, auth::Auth};
+
+pub fn register_routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/drain", axum::routing::get(get_drain_status))
+        .route("/drain", axum::routing::post(start_draining))
+        .route("/drain", axum::routing::delete(cancel_draining))
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartDrainingRequest {
+    /// Time until the server shuts down.
+    pub duration_secs: u64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonDrainStatus {
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+fn require_admin(auth: &Auth) -> Result<(), ServiceError> {
+    if !auth.account.is_admin() {
+        return Err(ServiceError::Unauthorized(
+            "Only admins can manage server draining".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_drain_status(
+    auth: Auth,
+    State(app): State<AppState>,
+) -> Result<Json<JsonDrainStatus>, ServiceError> {
+    require_admin(&auth)?;
+    Ok(Json(JsonDrainStatus {
+        deadline: app.app.shutdown_workflow.get_drain_deadline(),
+    }))
+}
+
+pub async fn start_draining(
+    auth: Auth,
+    State(app): State<AppState>,
+    Json(payload): Json<StartDrainingRequest>,
+) -> Result<Json<JsonDrainStatus>, ServiceError> {
+    require_admin(&auth)?;
+    let deadline = chrono::TimeDelta::from_std(Duration::from_secs(payload.duration_secs))
+        .ok()
+        .and_then(|duration| Utc::now().checked_add_signed(duration))
+        .ok_or_else(|| ServiceError::BadRequest("Invalid drain duration".to_string()))?;
+    app.app.shutdown_workflow.start_draining(deadline);
+    Ok(Json(JsonDrainStatus {
+        deadline: Some(deadline),
+    }))
+}
+
+pub async fn cancel_draining(auth: Auth, State(app): State<AppState>) -> Result<(), ServiceError> {
+    require_admin(&auth)?;
+    if !app.app.shutdown_workflow.cancel_draining() {
+        return Err(ServiceError::NotFound(
+            "The server is not draining".to_string(),
+        ));
+    }
+    Ok(())
+}
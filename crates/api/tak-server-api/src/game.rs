@@ -3,24 +3,29 @@ use std::time::{Duration, Instant};
 use axum::{
     Json,
     extract::{Path, State},
-    routing::{get, post},
+    routing::{get, post, put},
 };
-use tak_core::ptn::{action_to_ptn, game_result_to_string};
+use tak_core::ptn::{action_from_ptn, action_to_ptn, game_result_to_string, pos_to_string};
 use tak_server_api_contract::game::{
     ForPlayer, GameStatusType, JsonEndedGameInfo, JsonGameMetadata, JsonGameRatingInfo,
     JsonGameRequest, JsonGameRequestType, JsonGameRequests, JsonGameSettings, JsonGameStatus,
-    JsonPlayerSnapshot,
+    JsonPlayerSnapshot, JsonPremove,
 };
 use tak_server_app::{
     domain::{
         GameId,
-        game::request::{GameRequest, GameRequestType},
+        game::{
+            premove::{PremoveBranch, PremoveTree},
+            request::{GameRequest, GameRequestType},
+        },
     },
     services::player_resolver::ResolveError,
     workflow::{
         gameplay::{
             GameMetadataView,
-            do_action::{ActionResult, HandleRequestError, PlayerActionError},
+            do_action::{
+                AbortError, ActionResult, HandleRequestError, PlayerActionError, SetPremovesError,
+            },
         },
         history::{GameRecordView, query::GameQueryError},
     },
@@ -32,9 +37,13 @@ pub fn register_routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/", get(get_games))
         .route("/{game_id}", get(get_game_status))
+        .route("/{game_id}/ptn", get(get_game_ptn))
         .route("/{game_id}/resign", post(resign_game))
+        .route("/{game_id}/abort", post(abort_game))
         .route("/{game_id}/request", post(set_request))
         .route("/{game_id}/request/accept", post(accept_request))
+        .route("/{game_id}/premoves", get(get_premoves))
+        .route("/{game_id}/premoves", put(set_premoves))
 }
 
 pub async fn get_games(State(app): State<AppState>) -> Json<Vec<JsonGameMetadata>> {
@@ -103,6 +112,11 @@ pub async fn get_game_status(
             let status = if let Some(result) = &ended_game.result {
                 GameStatusType::Ended {
                     result: game_result_to_string(&result),
+                    winning_road: ended_game
+                        .reconstruct_winning_road()
+                        .iter()
+                        .map(pos_to_string)
+                        .collect(),
                 }
             } else {
                 tracing::warn!(
@@ -147,6 +161,23 @@ pub async fn get_game_status(
     }
 }
 
+pub async fn get_game_ptn(
+    State(app): State<AppState>,
+    Path(game_id): Path<i64>,
+) -> Result<String, ServiceError> {
+    let game_id = GameId(game_id);
+    match app.app.game_history_query_use_case.get_game(game_id).await {
+        Ok(Some(ended_game)) => Ok(ended_game.to_ptn().to_string()),
+        Ok(None) => Err(ServiceError::NotFound(format!(
+            "Game with id {} not found",
+            game_id
+        ))),
+        Err(GameQueryError::RepositoryError) => Err(ServiceError::Internal(
+            "Failed to retrieve game record".to_string(),
+        )),
+    }
+}
+
 pub async fn resign_game(
     auth: Auth,
     State(app): State<AppState>,
@@ -178,6 +209,45 @@ pub async fn resign_game(
         })
 }
 
+pub async fn abort_game(
+    auth: Auth,
+    State(app): State<AppState>,
+    Path(game_id): Path<i64>,
+) -> Result<(), ServiceError> {
+    let game_id = GameId(game_id);
+    let player_id = app
+        .app
+        .player_resolver_service
+        .resolve_player_id_by_account_id(&auth.account.account_id)
+        .await
+        .map_err(|ResolveError::Internal| {
+            ServiceError::Internal(format!(
+                "Failed to resolve player id for account {}",
+                auth.account.account_id
+            ))
+        })?;
+    match app
+        .app
+        .game_do_action_use_case
+        .abort(game_id, player_id)
+        .await
+    {
+        ActionResult::Success => Ok(()),
+        ActionResult::NotPossible(e) => match e {
+            PlayerActionError::GameNotFound => Err(ServiceError::NotFound(format!(
+                "Game with id {} not found",
+                game_id
+            ))),
+            PlayerActionError::NotAPlayerInGame => Err(ServiceError::Forbidden(
+                "You are not a player in this game".to_string(),
+            )),
+        },
+        ActionResult::ActionError(e @ AbortError::GameAlreadyStarted) => {
+            Err(ServiceError::NotPossible(e.to_string()))
+        }
+    }
+}
+
 pub async fn set_request(
     auth: Auth,
     State(app): State<AppState>,
@@ -275,6 +345,111 @@ pub async fn accept_request(
     }
 }
 
+pub async fn get_premoves(
+    auth: Auth,
+    State(app): State<AppState>,
+    Path(game_id): Path<i64>,
+) -> Result<Json<Vec<JsonPremove>>, ServiceError> {
+    let game_id = GameId(game_id);
+    let player_id = app
+        .app
+        .player_resolver_service
+        .resolve_player_id_by_account_id(&auth.account.account_id)
+        .await
+        .map_err(|ResolveError::Internal| {
+            ServiceError::Internal(format!(
+                "Failed to resolve player id for account {}",
+                auth.account.account_id
+            ))
+        })?;
+    match app
+        .app
+        .game_do_action_use_case
+        .get_premoves(game_id, player_id)
+    {
+        Ok(premoves) => Ok(Json(premoves_to_json(&premoves))),
+        Err(PlayerActionError::GameNotFound) => Err(ServiceError::NotFound(format!(
+            "Game with id {} not found",
+            game_id
+        ))),
+        Err(PlayerActionError::NotAPlayerInGame) => Err(ServiceError::Forbidden(
+            "You are not a player in this game".to_string(),
+        )),
+    }
+}
+
+pub async fn set_premoves(
+    auth: Auth,
+    State(app): State<AppState>,
+    Path(game_id): Path<i64>,
+    Json(premoves): Json<Vec<JsonPremove>>,
+) -> Result<(), ServiceError> {
+    let game_id = GameId(game_id);
+    let player_id = app
+        .app
+        .player_resolver_service
+        .resolve_player_id_by_account_id(&auth.account.account_id)
+        .await
+        .map_err(|ResolveError::Internal| {
+            ServiceError::Internal(format!(
+                "Failed to resolve player id for account {}",
+                auth.account.account_id
+            ))
+        })?;
+    let premoves = premoves_from_json(&premoves)?;
+    match app
+        .app
+        .game_do_action_use_case
+        .set_premoves(game_id, player_id, premoves)
+        .await
+    {
+        ActionResult::Success => Ok(()),
+        ActionResult::NotPossible(e) => match e {
+            PlayerActionError::GameNotFound => Err(ServiceError::NotFound(format!(
+                "Game with id {} not found",
+                game_id
+            ))),
+            PlayerActionError::NotAPlayerInGame => Err(ServiceError::Forbidden(
+                "You are not a player in this game".to_string(),
+            )),
+        },
+        ActionResult::ActionError(e @ SetPremovesError::InvalidPremoves(_)) => {
+            Err(ServiceError::BadRequest(e.to_string()))
+        }
+        ActionResult::ActionError(e) => Err(ServiceError::NotPossible(e.to_string())),
+    }
+}
+
+fn premoves_to_json(premoves: &PremoveTree) -> Vec<JsonPremove> {
+    premoves
+        .branches
+        .iter()
+        .map(|branch| JsonPremove {
+            condition: action_to_ptn(&branch.condition),
+            response: action_to_ptn(&branch.response),
+            next: premoves_to_json(&branch.next),
+        })
+        .collect()
+}
+
+fn premoves_from_json(premoves: &[JsonPremove]) -> Result<PremoveTree, ServiceError> {
+    let parse = |ptn: &str| {
+        action_from_ptn(ptn)
+            .ok_or_else(|| ServiceError::BadRequest(format!("Invalid action: {}", ptn)))
+    };
+    let branches = premoves
+        .iter()
+        .map(|premove| {
+            Ok(PremoveBranch {
+                condition: parse(&premove.condition)?,
+                response: parse(&premove.response)?,
+                next: premoves_from_json(&premove.next)?,
+            })
+        })
+        .collect::<Result<Vec<_>, ServiceError>>()?;
+    Ok(PremoveTree { branches })
+}
+
 pub fn from_metadata_view(game_id: GameId, view: &GameMetadataView) -> JsonGameMetadata {
     JsonGameMetadata {
         id: game_id.to_string(),
@@ -3,6 +3,7 @@ use axum::{
     extract::{Query, State},
     routing::get,
 };
+use tak_core::ptn::game_result_from_string;
 use tak_server_api_contract::game::JsonEndedGameInfo;
 use tak_server_app::{
     domain::{
@@ -18,13 +19,35 @@ pub fn register_routes() -> axum::Router<AppState> {
     axum::Router::new().route("/", get(query_game_history))
 }
 
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameHistoryFilterQuery {
+    /// Comma separated PTN results, e.g. `0-T,T-0` for all games won on time.
+    results: Option<String>,
+}
+
 pub async fn query_game_history(
     State(app): State<AppState>,
     Query(pagination): Query<PaginationQuery>,
+    Query(filter_query): Query<GameHistoryFilterQuery>,
 ) -> Result<Json<PaginatedResponse<JsonEndedGameInfo>>, ServiceError> {
+    let game_results = filter_query
+        .results
+        .map(|results| {
+            results
+                .split(',')
+                .map(|result| {
+                    game_result_from_string(result.trim()).ok_or_else(|| {
+                        ServiceError::BadRequest(format!("Invalid game result: {}", result))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
     let filter = GameQuery {
         pagination: Pagination::new(pagination.page.saturating_sub(1), pagination.page_size),
         sort: Some((SortOrder::Descending, GameSortBy::Date)),
+        game_results,
         ..Default::default()
     };
     let history = app
@@ -8,6 +8,7 @@ pub use auth::ApiAuthPort;
 pub use ws::WsService;
 
 pub mod account;
+pub mod admin;
 mod auth;
 pub mod chat;
 pub mod game;
@@ -54,7 +55,8 @@ pub async fn serve(
         .nest("/accounts", account::register_routes())
         .nest("/players", player::register_routes())
         .nest("/profiles", profile::register_routes())
-        .nest("/history", history::register_routes());
+        .nest("/history", history::register_routes())
+        .nest("/admin", admin::register_routes());
 
     let port = std::env::var("TAK_HTTP_API_PORT")
         .expect("TAK_HTTP_API_PORT must be set")
@@ -96,6 +98,11 @@ pub enum ServiceError {
     NotPossible(String),
     Internal(String),
     Forbidden(String),
+    /// A bad request with a stable error code clients can match on.
+    Rejected {
+        code: &'static str,
+        message: String,
+    },
 }
 
 impl ServiceError {
@@ -106,7 +113,15 @@ impl ServiceError {
             | ServiceError::BadRequest(msg)
             | ServiceError::NotPossible(msg)
             | ServiceError::Internal(msg)
-            | ServiceError::Forbidden(msg) => msg,
+            | ServiceError::Forbidden(msg)
+            | ServiceError::Rejected { message: msg, .. } => msg,
+        }
+    }
+
+    pub fn error_code(&self) -> Option<&'static str> {
+        match self {
+            ServiceError::Rejected { code, .. } => Some(code),
+            _ => None,
         }
     }
 
@@ -118,6 +133,7 @@ impl ServiceError {
             ServiceError::NotPossible(_) => axum::http::StatusCode::BAD_REQUEST,
             ServiceError::Internal(_) => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
             ServiceError::Forbidden(_) => axum::http::StatusCode::FORBIDDEN,
+            ServiceError::Rejected { .. } => axum::http::StatusCode::BAD_REQUEST,
         }
     }
 }
@@ -131,6 +147,7 @@ impl std::fmt::Display for ServiceError {
             ServiceError::NotPossible(msg) => write!(f, "Not possible: {}", msg),
             ServiceError::Internal(msg) => write!(f, "Internal error: {}", msg),
             ServiceError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
+            ServiceError::Rejected { message, .. } => write!(f, "Bad request: {}", message),
         }
     }
 }
@@ -139,7 +156,10 @@ impl IntoResponse for ServiceError {
     fn into_response(self) -> axum::http::Response<axum::body::Body> {
         let status = self.status_code();
         let msg = self.message().to_string();
-        let body = serde_json::json!({ "error": msg });
+        let body = match self.error_code() {
+            Some(code) => serde_json::json!({ "error": msg, "code": code }),
+            None => serde_json::json!({ "error": msg }),
+        };
         (status, axum::Json(body)).into_response()
     }
 }
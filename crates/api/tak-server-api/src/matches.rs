@@ -200,6 +200,9 @@ pub async fn set_player_ready(
             MatchReadinessError::MatchNotFound => {
                 Err(ServiceError::NotFound("Match not found".into()))
             }
+            MatchReadinessError::ServerDraining => Err(ServiceError::NotPossible(
+                "The server is shutting down, no new games can be started".into(),
+            )),
         }
     } else {
         Ok(Json(()))
@@ -240,6 +243,9 @@ pub async fn set_player_not_ready(
             MatchReadinessError::MatchNotFound => {
                 Err(ServiceError::NotFound("Match not found".into()))
             }
+            MatchReadinessError::ServerDraining => Err(ServiceError::NotPossible(
+                "The server is shutting down, no new games can be started".into(),
+            )),
         }
     } else {
         Ok(Json(()))
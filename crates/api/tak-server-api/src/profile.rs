@@ -6,9 +6,16 @@ use axum::{
     extract::{Multipart, Path, Query, State},
     http::header,
     response::Response,
-    routing::{get, post},
+    routing::{get, post, put},
+};
+use tak_server_app::{
+    domain::{
+        AccountId,
+        correspondence::{CorrespondenceSettings, NotificationPreferences, VacationPeriod},
+        profile::ProfilePictureFileType,
+    },
+    workflow::account::update_profile::UpdateProfileError,
 };
-use tak_server_app::domain::{AccountId, profile::ProfilePictureFileType};
 
 use crate::{
     AppState, ServiceError,
@@ -22,6 +29,111 @@ pub fn register_routes() -> axum::Router<AppState> {
         .route("/{account_id}", post(update_account_profile))
         .route("/{account_id}/picture", get(get_profile_picture))
         .route("/{account_id}/picture", post(set_profile_picture))
+        .route(
+            "/{account_id}/correspondence",
+            get(get_correspondence_settings),
+        )
+        .route(
+            "/{account_id}/correspondence",
+            put(update_correspondence_settings),
+        )
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonCorrespondenceSettings {
+    pub turn_emails: bool,
+    pub timeout_warning_emails: bool,
+    pub vacations: Vec<JsonVacationPeriod>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonVacationPeriod {
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub start: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub end: chrono::DateTime<chrono::Utc>,
+}
+
+fn own_account_id(auth: &Auth, account_id: String) -> Result<AccountId, ServiceError> {
+    let Ok(account_id) = AccountId::try_from(account_id) else {
+        return Err(ServiceError::BadRequest(
+            "Invalid account ID format".to_string(),
+        ));
+    };
+    if account_id != auth.account.account_id {
+        return Err(ServiceError::Forbidden(
+            "You can only access your own settings".to_string(),
+        ));
+    }
+    Ok(account_id)
+}
+
+pub async fn get_correspondence_settings(
+    auth: Auth,
+    State(app): State<AppState>,
+    Path(account_id): Path<String>,
+) -> Result<Json<JsonCorrespondenceSettings>, ServiceError> {
+    let account_id = own_account_id(&auth, account_id)?;
+    let settings = app
+        .app
+        .get_profile_use_case
+        .get_correspondence_settings(&account_id)
+        .await
+        .map_err(|_| {
+            ServiceError::Internal("Failed to retrieve correspondence settings".to_string())
+        })?;
+    Ok(Json(JsonCorrespondenceSettings {
+        turn_emails: settings.notifications.turn_emails,
+        timeout_warning_emails: settings.notifications.timeout_warning_emails,
+        vacations: settings
+            .vacations
+            .into_iter()
+            .map(|period| JsonVacationPeriod {
+                start: period.start,
+                end: period.end,
+            })
+            .collect(),
+    }))
+}
+
+pub async fn update_correspondence_settings(
+    auth: Auth,
+    State(app): State<AppState>,
+    Path(account_id): Path<String>,
+    Json(payload): Json<JsonCorrespondenceSettings>,
+) -> Result<(), ServiceError> {
+    let account_id = own_account_id(&auth, account_id)?;
+    let settings = CorrespondenceSettings {
+        notifications: NotificationPreferences {
+            turn_emails: payload.turn_emails,
+            timeout_warning_emails: payload.timeout_warning_emails,
+        },
+        vacations: payload
+            .vacations
+            .into_iter()
+            .map(|period| VacationPeriod {
+                start: period.start,
+                end: period.end,
+            })
+            .collect(),
+    };
+    app.app
+        .update_profile_use_case
+        .update_correspondence_settings(&account_id, settings)
+        .await
+        .map_err(|e| match e {
+            UpdateProfileError::InvalidVacation(reason) => {
+                ServiceError::BadRequest(reason.to_string())
+            }
+            UpdateProfileError::AccountNotFound => {
+                ServiceError::NotFound("Account not found".to_string())
+            }
+            UpdateProfileError::RepositoryError => {
+                ServiceError::Internal("Failed to update correspondence settings".to_string())
+            }
+        })
 }
 
 pub async fn get_account_profile(
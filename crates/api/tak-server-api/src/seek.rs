@@ -69,6 +69,9 @@ pub async fn create_seek(
         Err(CreateSeekError::InvalidGameSettings) => Err(ServiceError::BadRequest(
             "Invalid game settings".to_string(),
         )),
+        Err(CreateSeekError::ServerDraining) => Err(ServiceError::NotPossible(
+            "The server is shutting down, no new seeks can be created".to_string(),
+        )),
     }
 }
 
@@ -131,6 +134,9 @@ pub async fn accept_seek(
         Err(AcceptSeekError::FailedToCreateGame) => {
             Err(ServiceError::Internal("Failed to accept seek".to_string()))
         }
+        Err(AcceptSeekError::ServerDraining) => Err(ServiceError::NotPossible(
+            "The server is shutting down, no new games can be started".to_string(),
+        )),
     }
 }
 
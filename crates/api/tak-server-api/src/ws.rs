@@ -12,22 +12,24 @@ use futures::{
     SinkExt, StreamExt,
     stream::{SplitSink, SplitStream},
 };
-use tak_core::ptn::{action_from_ptn, action_to_ptn, game_result_to_string};
+use tak_core::ptn::{action_from_ptn, action_to_ptn, game_result_to_string, pos_to_string};
 use tak_player_connection::{ConnectionId, PlayerSimpleConnectionPort};
 use tak_server_api_contract::{
     game::{ForPlayer, JsonGameRequest},
     ws::{
         ClientMessage, ClientMessageWrapper, JsonChatConversation, JsonChatMessage,
-        ServerGameEventType, ServerMatchEventType, ServerMessage,
+        ServerAlertType, ServerGameEventType, ServerMatchEventType, ServerMessage,
     },
 };
 use tak_server_app::{
     domain::{AccountId, GameId, PlayerId, chat::ChatConversation, game::request::GameRequest},
-    ports::notification::{ListenerGameMessageType, ListenerMatchEventType, ListenerMessage},
+    ports::notification::{
+        ListenerGameMessageType, ListenerMatchEventType, ListenerMessage, ServerAlertMessage,
+    },
     workflow::{
         chat::message::ChatSendMessageError,
         gameplay::{
-            do_action::{ActionResult, DoActionError, PlayerActionError},
+            do_action::{ActionResult, PlayerActionError},
             observe::ObserveGameError,
         },
     },
@@ -102,6 +104,7 @@ async fn receive_ws(
                             ServerMessage::Error {
                                 message: e.to_string(),
                                 code: e.status_code().as_u16(),
+                                error_code: e.error_code().map(str::to_string),
                                 response_id: msg.response_id,
                             }
                         } else {
@@ -116,6 +119,7 @@ async fn receive_ws(
                         let _ = sender.send(ServerMessage::Error {
                             message: "Invalid message format".to_string(),
                             code: 400,
+                            error_code: None,
                             response_id: Uuid::new_v4(),
                         });
                     }
@@ -230,15 +234,35 @@ async fn handle_authenticated_client_message(
                 .await
             {
                 ActionResult::Success => Ok(()),
-                ActionResult::ActionError(e) => match e {
-                    DoActionError::InvalidAction(reason) => Err(ServiceError::BadRequest(format!(
-                        "Invalid action: {:?}",
-                        reason
-                    ))),
-                    DoActionError::NotPlayersTurn => {
-                        Err(ServiceError::BadRequest("Not player's turn".to_string()))
+                ActionResult::ActionError(e) => Err(ServiceError::Rejected {
+                    code: e.code(),
+                    message: e.to_string(),
+                }),
+                ActionResult::NotPossible(e) => match e {
+                    PlayerActionError::GameNotFound => {
+                        Err(ServiceError::BadRequest("Game not found".to_string()))
+                    }
+                    PlayerActionError::NotAPlayerInGame => {
+                        Err(ServiceError::BadRequest("Not a player in game".to_string()))
                     }
                 },
+            }
+        }
+        ClientMessage::AbortGame { game_id } => {
+            let Ok(game_id) = GameId::try_from(game_id) else {
+                return Err(ServiceError::BadRequest(
+                    "Invalid game ID format".to_string(),
+                ));
+            };
+            tracing::info!("Received AbortGame for game {}", game_id);
+            match app
+                .app
+                .game_do_action_use_case
+                .abort(game_id, player_id)
+                .await
+            {
+                ActionResult::Success => Ok(()),
+                ActionResult::ActionError(e) => Err(ServiceError::NotPossible(e.to_string())),
                 ActionResult::NotPossible(e) => match e {
                     PlayerActionError::GameNotFound => {
                         Err(ServiceError::BadRequest("Game not found".to_string()))
@@ -379,8 +403,12 @@ impl PlayerSimpleConnectionPort for WsService {
 
 fn from_listener_game_event_type(event_type: ListenerGameMessageType) -> ServerGameEventType {
     match event_type {
-        ListenerGameMessageType::GameOver { game_result } => ServerGameEventType::GameEnded {
+        ListenerGameMessageType::GameOver {
+            game_result,
+            winning_road,
+        } => ServerGameEventType::GameEnded {
             result: game_result_to_string(&game_result),
+            winning_road: winning_road.iter().map(pos_to_string).collect(),
         },
         ListenerGameMessageType::GameAction {
             player_id: _,
@@ -477,6 +505,12 @@ fn from_listener_message(message: ListenerMessage) -> Option<ServerMessage> {
         ListenerMessage::AccountsOnline { accounts } => Some(ServerMessage::AccountsOnline {
             account_ids: accounts.into_iter().map(|a| a.to_string()).collect(),
         }),
-        ListenerMessage::ServerAlert { .. } => None,
+        ListenerMessage::ServerAlert { message } => Some(ServerMessage::ServerAlert {
+            alert_type: match message {
+                ServerAlertMessage::Shutdown { deadline } => ServerAlertType::Shutdown { deadline },
+                ServerAlertMessage::ShutdownCancelled => ServerAlertType::ShutdownCancelled,
+                ServerAlertMessage::Custom(message) => ServerAlertType::Custom { message },
+            },
+        }),
     }
 }
@@ -25,8 +25,15 @@ pub struct Model {
     pub half_komi: i32,
     pub pieces: i32,
     pub capstones: i32,
+    /// Only set for handicap games, where `pieces`/`capstones` are white's reserve.
+    pub black_pieces: Option<i32>,
+    pub black_capstones: Option<i32>,
     pub opening: String,
+    /// Rule variants, only set when they deviate from the standard rules.
+    pub rules: Option<serde_json::Value>,
     pub game_settings: serde_json::Value,
+    /// Clocks and requests of a game that hasn't finished yet, used to resume it after a restart.
+    pub ongoing_state: Option<serde_json::Value>,
 
     #[sea_orm(indexed)]
     pub match_id: Option<i64>,
@@ -7,6 +7,9 @@ pub struct Model {
     pub account_id: Uuid,
     pub country: Option<String>,
     pub profile_picture_version: Option<u64>,
+    pub turn_emails: Option<bool>,
+    pub timeout_warning_emails: Option<bool>,
+    pub vacations: Option<serde_json::Value>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
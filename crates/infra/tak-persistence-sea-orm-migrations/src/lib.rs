@@ -1,10 +1,14 @@
 use sea_orm_migration::{MigrationTrait, MigratorTrait};
 
+mod m20261016_000001_game_result_win_reasons;
+
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![]
+        vec![Box::new(
+            m20261016_000001_game_result_win_reasons::Migration,
+        )]
     }
 }
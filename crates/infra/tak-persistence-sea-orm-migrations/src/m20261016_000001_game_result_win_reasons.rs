@@ -0,0 +1,87 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{
+        ActiveModelTrait, ColumnTrait, EntityTrait, JsonValue, QueryFilter, QueryOrder,
+        QuerySelect, Set,
+    },
+};
+use tak_persistence_sea_orm_entities::game;
+
+/// Games that were won on time, by resignation or by abandonment used to be stored as a plain
+/// `1-0`/`0-1`. This recovers the reason from the recorded game over event.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const BATCH_SIZE: u64 = 500;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let mut last_id = 0;
+        loop {
+            let games = game::Entity::find()
+                .filter(game::Column::Id.gt(last_id))
+                .filter(game::Column::Result.is_in(["1-0", "0-1"]))
+                .order_by_asc(game::Column::Id)
+                .limit(BATCH_SIZE)
+                .all(db)
+                .await?;
+            let Some(last) = games.last() else {
+                break;
+            };
+            last_id = last.id;
+            for game in games {
+                let Some(letter) = win_reason_letter(&game.events) else {
+                    continue;
+                };
+                let result = match game.result.as_deref() {
+                    Some("1-0") => format!("{letter}-0"),
+                    _ => format!("0-{letter}"),
+                };
+                game::ActiveModel {
+                    id: Set(game.id),
+                    result: Set(Some(result)),
+                    ..Default::default()
+                }
+                .update(db)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for letter in ["T", "S", "A", "X"] {
+            game::Entity::update_many()
+                .col_expr(game::Column::Result, Expr::value("1-0"))
+                .filter(game::Column::Result.eq(format!("{letter}-0")))
+                .exec(db)
+                .await?;
+            game::Entity::update_many()
+                .col_expr(game::Column::Result, Expr::value("0-1"))
+                .filter(game::Column::Result.eq(format!("0-{letter}")))
+                .exec(db)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The result letter matching the last game over event, if it carries a win reason.
+fn win_reason_letter(events: &JsonValue) -> Option<&'static str> {
+    let game_over_type = events
+        .as_array()?
+        .iter()
+        .rev()
+        .map(|record| &record["event"])
+        .find(|event| event["type"] == "gameOver")?["gameOverType"]
+        .as_str()?;
+    match game_over_type {
+        "timeout" => Some("T"),
+        "resignation" => Some("S"),
+        "abandonment" => Some("A"),
+        _ => None,
+    }
+}
@@ -6,13 +6,18 @@ use sea_orm::{
 };
 use serde::Deserialize;
 use tak_core::{
-    TakAction, TakBaseGameSettings, TakGameSettings, TakPlayer, TakReserve, TakTimeInfo,
+    TakAction, TakBaseGameSettings, TakGameSettings, TakPlayer, TakPlyTime, TakReserve, TakRules,
+    TakTimeInfo,
     ptn::{action_from_ptn, action_to_ptn, game_result_from_string, game_result_to_string},
 };
 use tak_persistence_sea_orm_entities::game;
 use tak_server_app::domain::{
     GameId, MatchId, PaginatedResponse, PlayerId, RepoError, RepoRetrieveError, SortOrder,
-    game::{GameEvent, GameEventType, GameMetadata, GameOverEventType, request::GameRequest},
+    game::{
+        GameEvent, GameEventType, GameMetadata, GameOverEventType, OngoingGameSnapshot,
+        premove::{GamePremoves, PremoveBranch, PremoveTree},
+        request::{GameRequest, GameRequestSystem, GameRequests},
+    },
     game_history::{
         DateSelector, GameFinishedUpdate, GameIdSelector, GamePlayerFilter, GameQuery,
         GameRatingInfo, GameRecord, GameRepository, GameSortBy, PlayerSnapshot,
@@ -20,8 +25,8 @@ use tak_server_app::domain::{
 };
 
 use crate::{
-    JsonTimeSettings, create_db_pool, db_error_to_repo_retrieve_error, tak_opening_from_string,
-    tak_opening_to_string,
+    JsonTakRules, JsonTimeSettings, create_db_pool, db_error_to_repo_retrieve_error,
+    tak_opening_from_string, tak_opening_to_string,
 };
 
 pub struct GameRepositoryImpl {
@@ -50,6 +55,10 @@ enum JsonEventRecordType {
             deserialize_with = "deserialize_action"
         )]
         action: TakAction,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        think_time_ms: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        time_gained_ms: Option<u64>,
     },
     RequestSet {
         request_type: JsonRequest,
@@ -98,10 +107,187 @@ impl JsonTimeInfo {
     }
 }
 
+impl JsonEventRecord {
+    fn from_game_event(event: &GameEvent) -> Self {
+        Self {
+            timestamp: event.date,
+            event: JsonEventRecordType::from_game_event(event.event_type.clone()),
+            time_info: JsonTimeInfo::from_time_info(&event.time_info),
+        }
+    }
+
+    fn to_game_event(&self) -> GameEvent {
+        GameEvent {
+            date: self.timestamp,
+            event_type: self.event.to_game_event(),
+            time_info: self.time_info.to_time_info(),
+        }
+    }
+}
+
+/// Stored in the `ongoing_state` column until the game is finished.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonOngoingState {
+    actions: Vec<String>,
+    ply_times: Vec<JsonPlyTime>,
+    time_info: JsonTimeInfo,
+    white_requests: JsonGameRequests,
+    black_requests: JsonGameRequests,
+    #[serde(default)]
+    white_premoves: Vec<JsonPremove>,
+    #[serde(default)]
+    black_premoves: Vec<JsonPremove>,
+    #[serde(default)]
+    white_clock_paused: bool,
+    #[serde(default)]
+    black_clock_paused: bool,
+    #[serde(default)]
+    turn_think_time_ms: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonPremove {
+    #[serde(
+        serialize_with = "serialize_action",
+        deserialize_with = "deserialize_action"
+    )]
+    condition: TakAction,
+    #[serde(
+        serialize_with = "serialize_action",
+        deserialize_with = "deserialize_action"
+    )]
+    response: TakAction,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    next: Vec<JsonPremove>,
+}
+
+impl JsonPremove {
+    fn from_tree(tree: &PremoveTree) -> Vec<Self> {
+        tree.branches
+            .iter()
+            .map(|branch| JsonPremove {
+                condition: branch.condition.clone(),
+                response: branch.response.clone(),
+                next: Self::from_tree(&branch.next),
+            })
+            .collect()
+    }
+
+    fn to_tree(premoves: &[Self]) -> PremoveTree {
+        PremoveTree {
+            branches: premoves
+                .iter()
+                .map(|premove| PremoveBranch {
+                    condition: premove.condition.clone(),
+                    response: premove.response.clone(),
+                    next: Self::to_tree(&premove.next),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonPlyTime {
+    think_time_ms: u64,
+    time_gained_ms: u64,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonGameRequests {
+    draw_offered: bool,
+    undo_requested: bool,
+    more_time_offered_ms: Option<u64>,
+}
+
+impl JsonGameRequests {
+    fn from_requests(requests: &GameRequests) -> Self {
+        Self {
+            draw_offered: requests.draw_offered,
+            undo_requested: requests.undo_requested,
+            more_time_offered_ms: requests.more_time_offered.map(|d| d.as_millis() as u64),
+        }
+    }
+
+    fn to_requests(&self) -> GameRequests {
+        GameRequests {
+            draw_offered: self.draw_offered,
+            undo_requested: self.undo_requested,
+            more_time_offered: self.more_time_offered_ms.map(Duration::from_millis),
+        }
+    }
+}
+
+impl JsonOngoingState {
+    fn from_snapshot(snapshot: &OngoingGameSnapshot) -> Self {
+        Self {
+            actions: snapshot.actions.iter().map(action_to_ptn).collect(),
+            ply_times: snapshot
+                .ply_times
+                .iter()
+                .map(|ply_time| JsonPlyTime {
+                    think_time_ms: ply_time.think_time.as_millis() as u64,
+                    time_gained_ms: ply_time.time_gained.as_millis() as u64,
+                })
+                .collect(),
+            time_info: JsonTimeInfo::from_time_info(&snapshot.time_info),
+            white_requests: JsonGameRequests::from_requests(&snapshot.requests.white_requests),
+            black_requests: JsonGameRequests::from_requests(&snapshot.requests.black_requests),
+            white_premoves: JsonPremove::from_tree(&snapshot.premoves.white_premoves),
+            black_premoves: JsonPremove::from_tree(&snapshot.premoves.black_premoves),
+            white_clock_paused: snapshot.clock_paused.0,
+            black_clock_paused: snapshot.clock_paused.1,
+            turn_think_time_ms: snapshot.turn_think_time.as_millis() as u64,
+        }
+    }
+
+    fn to_snapshot(&self, events: Vec<GameEvent>) -> Result<OngoingGameSnapshot, String> {
+        let actions = self
+            .actions
+            .iter()
+            .map(|ptn| {
+                action_from_ptn(ptn)
+                    .ok_or_else(|| format!("Invalid action in ongoing game state: {}", ptn))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(OngoingGameSnapshot {
+            actions,
+            ply_times: self
+                .ply_times
+                .iter()
+                .map(|ply_time| TakPlyTime {
+                    think_time: Duration::from_millis(ply_time.think_time_ms),
+                    time_gained: Duration::from_millis(ply_time.time_gained_ms),
+                })
+                .collect(),
+            time_info: self.time_info.to_time_info(),
+            clock_paused: (self.white_clock_paused, self.black_clock_paused),
+            turn_think_time: Duration::from_millis(self.turn_think_time_ms),
+            requests: GameRequestSystem {
+                white_requests: self.white_requests.to_requests(),
+                black_requests: self.black_requests.to_requests(),
+            },
+            premoves: GamePremoves {
+                white_premoves: JsonPremove::to_tree(&self.white_premoves),
+                black_premoves: JsonPremove::to_tree(&self.black_premoves),
+            },
+            events,
+        })
+    }
+}
+
 impl JsonEventRecordType {
     fn from_game_event(event: GameEventType) -> Self {
         match event {
-            GameEventType::Action { action } => JsonEventRecordType::Action { action },
+            GameEventType::Action { action, ply_time } => JsonEventRecordType::Action {
+                action,
+                think_time_ms: ply_time.as_ref().map(|t| t.think_time.as_millis() as u64),
+                time_gained_ms: ply_time.as_ref().map(|t| t.time_gained.as_millis() as u64),
+            },
             GameEventType::RequestSet { request, player } => JsonEventRecordType::RequestSet {
                 request_type: match request {
                     GameRequest::Draw(offer) => JsonRequest::Draw { offer },
@@ -132,8 +318,16 @@ impl JsonEventRecordType {
 
     fn to_game_event(&self) -> GameEventType {
         match self {
-            JsonEventRecordType::Action { action } => GameEventType::Action {
+            JsonEventRecordType::Action {
+                action,
+                think_time_ms,
+                time_gained_ms,
+            } => GameEventType::Action {
                 action: action.clone(),
+                ply_time: think_time_ms.map(|think_time_ms| TakPlyTime {
+                    think_time: Duration::from_millis(think_time_ms),
+                    time_gained: Duration::from_millis(time_gained_ms.unwrap_or(0)),
+                }),
             },
             JsonEventRecordType::RequestSet {
                 request_type,
@@ -223,6 +417,14 @@ impl GameRepositoryImpl {
         Self { db }
     }
 
+    fn events_to_value(events: &[GameEvent]) -> Result<serde_json::Value, serde_json::Error> {
+        let events = events
+            .iter()
+            .map(JsonEventRecord::from_game_event)
+            .collect::<Vec<_>>();
+        serde_json::to_value(&events)
+    }
+
     fn model_to_game(model: game::Model) -> Result<GameRecord, String> {
         let rating_info = if let Some(rating_change_white) = model.rating_change_white
             && let Some(rating_change_black) = model.rating_change_black
@@ -239,8 +441,20 @@ impl GameRepositoryImpl {
             board_size: model.size as u32,
             half_komi: model.half_komi as u32,
             reserve: TakReserve::new(model.pieces as u32, model.capstones as u32),
+            black_reserve: match (model.black_pieces, model.black_capstones) {
+                (Some(pieces), Some(capstones)) => {
+                    Some(TakReserve::new(pieces as u32, capstones as u32))
+                }
+                _ => None,
+            },
             opening: tak_opening_from_string(&model.opening)
                 .ok_or_else(|| format!("Invalid opening string in database: {}", model.opening))?,
+            rules: match model.rules {
+                Some(rules) => serde_json::from_value::<JsonTakRules>(rules)
+                    .map_err(|e| format!("Failed to deserialize rules from database: {}", e))?
+                    .to_rules(),
+                None => TakRules::default(),
+            },
         };
 
         let time_settings =
@@ -285,14 +499,7 @@ impl GameRepositoryImpl {
             metadata,
             white: white_snapshot,
             black: black_snapshot,
-            events: json_events
-                .into_iter()
-                .map(|jm| GameEvent {
-                    date: jm.timestamp,
-                    event_type: jm.event.to_game_event(),
-                    time_info: jm.time_info.to_time_info(),
-                })
-                .collect(),
+            events: json_events.iter().map(|jm| jm.to_game_event()).collect(),
             rating_info,
             result: model
                 .result
@@ -325,13 +532,31 @@ impl GameRepository for GameRepositoryImpl {
             half_komi: Set(base_settings.half_komi as i32),
             pieces: Set(base_settings.reserve.pieces as i32),
             capstones: Set(base_settings.reserve.capstones as i32),
+            black_pieces: Set(base_settings
+                .black_reserve
+                .as_ref()
+                .map(|r| r.pieces as i32)),
+            black_capstones: Set(base_settings
+                .black_reserve
+                .as_ref()
+                .map(|r| r.capstones as i32)),
             opening: Set(tak_opening_to_string(&base_settings.opening)),
+            rules: Set(if base_settings.rules.is_standard() {
+                None
+            } else {
+                Some(
+                    serde_json::to_value(JsonTakRules::from_rules(&base_settings.rules)).map_err(
+                        |e| RepoError::StorageError(format!("Failed to serialize rules: {}", e)),
+                    )?,
+                )
+            }),
             rating_change_white: Set(None),
             rating_change_black: Set(None),
             game_settings: Set(serde_json::to_value(&time_settings).map_err(|e| {
                 RepoError::StorageError(format!("Failed to serialize game settings: {}", e))
             })?),
             match_id: Set(game.metadata.match_id.map(|id| id.0)),
+            ongoing_state: Set(None),
         };
 
         let result = new_game
@@ -347,16 +572,7 @@ impl GameRepository for GameRepositoryImpl {
         game_id: GameId,
         update: GameFinishedUpdate,
     ) -> Result<(), RepoRetrieveError> {
-        let events = update
-            .events
-            .iter()
-            .map(|event| JsonEventRecord {
-                timestamp: event.date,
-                event: JsonEventRecordType::from_game_event(event.event_type.clone()),
-                time_info: JsonTimeInfo::from_time_info(&event.time_info),
-            })
-            .collect::<Vec<_>>();
-        let events = serde_json::to_value(&events)
+        let events = Self::events_to_value(&update.events)
             .map_err(|e| RepoRetrieveError::StorageError(e.to_string()))?;
 
         let result_val = game_result_to_string(&update.result);
@@ -373,6 +589,7 @@ impl GameRepository for GameRepositoryImpl {
                 .rating_info
                 .as_ref()
                 .map(|info| info.rating_change_black)),
+            ongoing_state: Set(None),
 
             ..Default::default()
         };
@@ -385,6 +602,60 @@ impl GameRepository for GameRepositoryImpl {
         Ok(())
     }
 
+    async fn update_ongoing_game(
+        &self,
+        game_id: GameId,
+        snapshot: OngoingGameSnapshot,
+    ) -> Result<(), RepoRetrieveError> {
+        let events = Self::events_to_value(&snapshot.events)
+            .map_err(|e| RepoRetrieveError::StorageError(e.to_string()))?;
+        let ongoing_state = serde_json::to_value(JsonOngoingState::from_snapshot(&snapshot))
+            .map_err(|e| RepoRetrieveError::StorageError(e.to_string()))?;
+
+        game::Entity::update_many()
+            .set(game::ActiveModel {
+                events: Set(events),
+                ongoing_state: Set(Some(ongoing_state)),
+                ..Default::default()
+            })
+            .filter(game::Column::Id.eq(game_id.0))
+            .filter(game::Column::Result.is_null())
+            .exec(&self.db)
+            .await
+            .map_err(db_error_to_repo_retrieve_error)?;
+
+        Ok(())
+    }
+
+    async fn get_ongoing_games(
+        &self,
+    ) -> Result<Vec<(GameId, GameMetadata, OngoingGameSnapshot)>, RepoError> {
+        let models = game::Entity::find()
+            .filter(game::Column::Result.is_null())
+            .filter(game::Column::OngoingState.is_not_null())
+            .all(&self.db)
+            .await
+            .map_err(|e| RepoError::StorageError(e.to_string()))?;
+
+        let mut results = Vec::new();
+        for model in models {
+            let game_id = GameId(model.id);
+            let ongoing_state = model.ongoing_state.clone().unwrap_or_default();
+            let game_record = Self::model_to_game(model).and_then(|record| {
+                serde_json::from_value::<JsonOngoingState>(ongoing_state)
+                    .map_err(|e| format!("Failed to deserialize ongoing game state: {}", e))?
+                    .to_snapshot(record.events)
+                    .map(|snapshot| (record.metadata, snapshot))
+            });
+            match game_record {
+                Ok((metadata, snapshot)) => results.push((game_id, metadata, snapshot)),
+                Err(e) => tracing::error!("Skipping ongoing game {}: {}", game_id, e),
+            }
+        }
+
+        Ok(results)
+    }
+
     async fn get_game_record(&self, id: GameId) -> Result<GameRecord, RepoRetrieveError> {
         let model = game::Entity::find_by_id(id.0)
             .one(&self.db)
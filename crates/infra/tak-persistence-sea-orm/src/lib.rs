@@ -4,8 +4,9 @@ use async_lock::OnceCell;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 use sea_orm_migration::MigratorTrait;
 use tak_core::{
-    TakAsyncTimeControl, TakBaseGameSettings, TakGameSettings, TakOpening, TakRealtimeTimeControl,
-    TakReserve, TakTimeSettings,
+    TakAsyncTimeControl, TakBaseGameSettings, TakFlatTiebreak, TakGameSettings, TakIncrementMode,
+    TakOpening, TakRealtimeTimeControl, TakRepetitionRule, TakReserve, TakReserveRunout, TakRules,
+    TakTimeSettings,
 };
 use tak_persistence_sea_orm_migrations::Migrator;
 use tak_server_app::domain::RepoRetrieveError;
@@ -107,7 +108,11 @@ struct JsonGameSettings {
     half_komi: u32,
     pieces: u32,
     capstones: u32,
+    #[serde(default)]
+    black_reserve: Option<(u32, u32)>,
     opening: JsonTakOpening,
+    #[serde(default)]
+    rules: JsonTakRules,
     time_settings: JsonTimeSettings,
 }
 
@@ -118,7 +123,13 @@ impl JsonGameSettings {
             half_komi: game_settings.base.half_komi,
             pieces: game_settings.base.reserve.pieces,
             capstones: game_settings.base.reserve.capstones,
+            black_reserve: game_settings
+                .base
+                .black_reserve
+                .as_ref()
+                .map(|r| (r.pieces, r.capstones)),
             opening: JsonTakOpening::from_tak_opening(&game_settings.base.opening),
+            rules: JsonTakRules::from_rules(&game_settings.base.rules),
             time_settings: JsonTimeSettings::from_time_settings(&game_settings.time_settings),
         }
     }
@@ -132,7 +143,11 @@ impl JsonGameSettings {
                     pieces: self.pieces,
                     capstones: self.capstones,
                 },
+                black_reserve: self
+                    .black_reserve
+                    .map(|(pieces, capstones)| TakReserve { pieces, capstones }),
                 opening: self.opening.to_tak_opening(),
+                rules: self.rules.to_rules(),
             },
             time_settings: self.time_settings.to_time_settings(),
         }
@@ -173,6 +188,10 @@ impl JsonTakOpening {
 )]
 enum JsonTimeSettings {
     Realtime(JsonRealtimeTimeSettings),
+    RealtimeOdds {
+        white: JsonRealtimeTimeSettings,
+        black: JsonRealtimeTimeSettings,
+    },
     Async(JsonAsyncTimeSettings),
 }
 
@@ -180,18 +199,12 @@ impl JsonTimeSettings {
     fn from_time_settings(time_settings: &TakTimeSettings) -> Self {
         match time_settings {
             TakTimeSettings::Realtime(settings) => {
-                JsonTimeSettings::Realtime(JsonRealtimeTimeSettings {
-                    contingent_ms: settings.contingent.as_millis() as u64,
-                    increment_ms: settings.increment.as_millis() as u64,
-                    extra: settings.extra.as_ref().map(|(trigger_move, extra_time)| {
-                        JsonRealtimeTimeExtra {
-                            extra_time_ms: extra_time.as_millis() as u64,
-                            extra_time_move: *trigger_move,
-                        }
-                    }),
-                })
+                JsonTimeSettings::Realtime(JsonRealtimeTimeSettings::from_time_control(settings))
             }
-
+            TakTimeSettings::RealtimeOdds(white, black) => JsonTimeSettings::RealtimeOdds {
+                white: JsonRealtimeTimeSettings::from_time_control(white),
+                black: JsonRealtimeTimeSettings::from_time_control(black),
+            },
             TakTimeSettings::Async(settings) => JsonTimeSettings::Async(JsonAsyncTimeSettings {
                 increment_ms: settings.contingent.as_millis() as u64,
             }),
@@ -200,18 +213,10 @@ impl JsonTimeSettings {
     fn to_time_settings(&self) -> TakTimeSettings {
         match self {
             JsonTimeSettings::Realtime(json_settings) => {
-                TakTimeSettings::Realtime(TakRealtimeTimeControl {
-                    contingent: Duration::from_millis(json_settings.contingent_ms),
-                    increment: Duration::from_millis(json_settings.increment_ms),
-                    extra: if let Some(extra) = &json_settings.extra {
-                        Some((
-                            extra.extra_time_move,
-                            Duration::from_millis(extra.extra_time_ms),
-                        ))
-                    } else {
-                        None
-                    },
-                })
+                TakTimeSettings::Realtime(json_settings.to_time_control())
+            }
+            JsonTimeSettings::RealtimeOdds { white, black } => {
+                TakTimeSettings::RealtimeOdds(white.to_time_control(), black.to_time_control())
             }
             JsonTimeSettings::Async(json_settings) => TakTimeSettings::Async(TakAsyncTimeControl {
                 contingent: Duration::from_millis(json_settings.increment_ms as u64),
@@ -226,6 +231,55 @@ struct JsonRealtimeTimeSettings {
     contingent_ms: u64,
     increment_ms: u64,
     extra: Option<JsonRealtimeTimeExtra>,
+    #[serde(default)]
+    increment_mode: JsonIncrementMode,
+}
+
+impl JsonRealtimeTimeSettings {
+    fn from_time_control(settings: &TakRealtimeTimeControl) -> Self {
+        JsonRealtimeTimeSettings {
+            contingent_ms: settings.contingent.as_millis() as u64,
+            increment_ms: settings.increment.as_millis() as u64,
+            extra: settings.extra.as_ref().map(|(trigger_move, extra_time)| {
+                JsonRealtimeTimeExtra {
+                    extra_time_ms: extra_time.as_millis() as u64,
+                    extra_time_move: *trigger_move,
+                }
+            }),
+            increment_mode: match settings.increment_mode {
+                TakIncrementMode::Fischer => JsonIncrementMode::Fischer,
+                TakIncrementMode::Delay => JsonIncrementMode::Delay,
+                TakIncrementMode::Bronstein => JsonIncrementMode::Bronstein,
+            },
+        }
+    }
+
+    fn to_time_control(&self) -> TakRealtimeTimeControl {
+        TakRealtimeTimeControl {
+            contingent: Duration::from_millis(self.contingent_ms),
+            increment: Duration::from_millis(self.increment_ms),
+            extra: self.extra.as_ref().map(|extra| {
+                (
+                    extra.extra_time_move,
+                    Duration::from_millis(extra.extra_time_ms),
+                )
+            }),
+            increment_mode: match self.increment_mode {
+                JsonIncrementMode::Fischer => TakIncrementMode::Fischer,
+                JsonIncrementMode::Delay => TakIncrementMode::Delay,
+                JsonIncrementMode::Bronstein => TakIncrementMode::Bronstein,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsonIncrementMode {
+    #[default]
+    Fischer,
+    Delay,
+    Bronstein,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -241,6 +295,77 @@ struct JsonAsyncTimeSettings {
     increment_ms: u64,
 }
 
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct JsonTakRules {
+    repetition: JsonRepetitionRule,
+    flat_tiebreak: JsonFlatTiebreak,
+    reserve_runout: JsonReserveRunout,
+    no_standing_stones: bool,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsonRepetitionRule {
+    #[default]
+    ThreefoldDraw,
+    Allowed,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsonFlatTiebreak {
+    #[default]
+    Draw,
+    Reserve,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum JsonReserveRunout {
+    #[default]
+    EitherEmpty,
+    BothEmpty,
+}
+
+impl JsonTakRules {
+    fn from_rules(rules: &TakRules) -> Self {
+        JsonTakRules {
+            repetition: match rules.repetition {
+                TakRepetitionRule::ThreefoldDraw => JsonRepetitionRule::ThreefoldDraw,
+                TakRepetitionRule::Allowed => JsonRepetitionRule::Allowed,
+            },
+            flat_tiebreak: match rules.flat_tiebreak {
+                TakFlatTiebreak::Draw => JsonFlatTiebreak::Draw,
+                TakFlatTiebreak::Reserve => JsonFlatTiebreak::Reserve,
+            },
+            reserve_runout: match rules.reserve_runout {
+                TakReserveRunout::EitherEmpty => JsonReserveRunout::EitherEmpty,
+                TakReserveRunout::BothEmpty => JsonReserveRunout::BothEmpty,
+            },
+            no_standing_stones: rules.no_standing_stones,
+        }
+    }
+
+    fn to_rules(&self) -> TakRules {
+        TakRules {
+            repetition: match self.repetition {
+                JsonRepetitionRule::ThreefoldDraw => TakRepetitionRule::ThreefoldDraw,
+                JsonRepetitionRule::Allowed => TakRepetitionRule::Allowed,
+            },
+            flat_tiebreak: match self.flat_tiebreak {
+                JsonFlatTiebreak::Draw => TakFlatTiebreak::Draw,
+                JsonFlatTiebreak::Reserve => TakFlatTiebreak::Reserve,
+            },
+            reserve_runout: match self.reserve_runout {
+                JsonReserveRunout::EitherEmpty => TakReserveRunout::EitherEmpty,
+                JsonReserveRunout::BothEmpty => TakReserveRunout::BothEmpty,
+            },
+            no_standing_stones: self.no_standing_stones,
+        }
+    }
+}
+
 fn tak_opening_to_string(opening: &TakOpening) -> String {
     match opening {
         TakOpening::Swap => "swap".to_string(),
@@ -6,9 +6,19 @@ use sea_orm::{DatabaseConnection, EntityTrait, Iterable, sea_query::OnConflict};
 use tak_persistence_sea_orm_entities::profile;
 use tak_server_app::domain::{
     AccountId, RepoError, RepoRetrieveError,
+    correspondence::{CorrespondenceSettings, NotificationPreferences, VacationPeriod},
     profile::{AccountProfile, AccountProfileRepository, ProfilePictureVersion},
 };
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonVacationPeriod {
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    start: chrono::DateTime<chrono::Utc>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    end: chrono::DateTime<chrono::Utc>,
+}
+
 pub struct ProfileRepositoryImpl {
     db: DatabaseConnection,
     profile_cache: Arc<moka::sync::Cache<AccountId, AccountProfile>>,
@@ -25,6 +35,41 @@ impl ProfileRepositoryImpl {
         );
         Self { db, profile_cache }
     }
+
+    fn model_to_correspondence_settings(model: &profile::Model) -> CorrespondenceSettings {
+        let defaults = NotificationPreferences::default();
+        let vacations = match model
+            .vacations
+            .clone()
+            .map(serde_json::from_value::<Vec<JsonVacationPeriod>>)
+            .transpose()
+        {
+            Ok(vacations) => vacations.unwrap_or_default(),
+            Err(e) => {
+                tracing::error!(
+                    "Invalid vacations stored for account {}: {}",
+                    model.account_id,
+                    e
+                );
+                Vec::new()
+            }
+        };
+        CorrespondenceSettings {
+            notifications: NotificationPreferences {
+                turn_emails: model.turn_emails.unwrap_or(defaults.turn_emails),
+                timeout_warning_emails: model
+                    .timeout_warning_emails
+                    .unwrap_or(defaults.timeout_warning_emails),
+            },
+            vacations: vacations
+                .into_iter()
+                .map(|period| VacationPeriod {
+                    start: period.start,
+                    end: period.end,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -34,12 +79,28 @@ impl AccountProfileRepository for ProfileRepositoryImpl {
         account_id: &AccountId,
         profile_information: AccountProfile,
     ) -> Result<(), RepoError> {
+        let correspondence = &profile_information.correspondence;
+        let vacations = correspondence
+            .vacations
+            .iter()
+            .map(|period| JsonVacationPeriod {
+                start: period.start,
+                end: period.end,
+            })
+            .collect::<Vec<_>>();
+        let vacations =
+            serde_json::to_value(vacations).map_err(|e| RepoError::StorageError(e.to_string()))?;
         let active_model = profile::ActiveModel {
             account_id: sea_orm::ActiveValue::Set(account_id.0),
             country: sea_orm::ActiveValue::Set(profile_information.country.map(|x| x.to_string())),
             profile_picture_version: sea_orm::ActiveValue::Set(
                 profile_information.profile_picture_version.map(|x| x.0),
             ),
+            turn_emails: sea_orm::ActiveValue::Set(Some(correspondence.notifications.turn_emails)),
+            timeout_warning_emails: sea_orm::ActiveValue::Set(Some(
+                correspondence.notifications.timeout_warning_emails,
+            )),
+            vacations: sea_orm::ActiveValue::Set(Some(vacations)),
         };
         profile::Entity::insert(active_model)
             .on_conflict(
@@ -80,6 +141,7 @@ impl AccountProfileRepository for ProfileRepositoryImpl {
                     .as_deref()
                     .and_then(|c| CountryCode::from_str(c).ok()),
                 profile_picture_version: model.profile_picture_version.map(ProfilePictureVersion),
+                correspondence: Self::model_to_correspondence_settings(&model),
             };
             self.profile_cache
                 .insert(account_id.clone(), profile_information.clone());
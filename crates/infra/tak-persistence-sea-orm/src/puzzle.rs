@@ -7,7 +7,7 @@ use sea_orm::{
     sea_query::{Func, OnConflict, Query},
 };
 use tak_core::{
-    TakBaseGameSettings, TakOpening, TakReserve,
+    TakBaseGameSettings, TakOpening, TakReserve, TakRules,
     ptn::{action_from_ptn, action_to_ptn},
 };
 use tak_persistence_sea_orm_entities::puzzle;
@@ -99,12 +99,14 @@ impl PuzzleRepositoryImpl {
             board_size: model.size as u32,
             half_komi: model.half_komi as u32,
             reserve: TakReserve::new(model.pieces as u32, model.capstones as u32),
+            black_reserve: None,
             opening: tak_opening_from_string(&model.opening).ok_or_else(|| {
                 RepoRetrieveError::StorageError(format!(
                     "Invalid opening in puzzle: {}",
                     model.opening
                 ))
             })?,
+            rules: TakRules::default(),
         };
         let position = serde_json::from_value::<Vec<String>>(model.position)
             .map_err(|e| {
@@ -247,7 +249,9 @@ fn test_puzzle() -> Puzzle {
         board_size: 5,
         half_komi: 2,
         reserve: TakReserve::from_size(5).unwrap(),
+        black_reserve: None,
         opening: TakOpening::Swap,
+        rules: TakRules::default(),
     };
     let position = vec![
         action_from_ptn("a5").unwrap(),
@@ -274,7 +278,9 @@ fn test_puzzle2() -> Puzzle {
         board_size: 6,
         half_komi: 2,
         reserve: TakReserve::from_size(6).unwrap(),
+        black_reserve: None,
         opening: TakOpening::Swap,
+        rules: TakRules::default(),
     };
     let position = vec![
         action_from_ptn("a5").unwrap(),
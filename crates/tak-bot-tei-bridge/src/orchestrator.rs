@@ -5,8 +5,8 @@ use tak_core::{TakPlayer, TakReserve};
 use tak_server_api_contract::{
     auth::IdentityInfo,
     game::{
-        JsonBaseGameSettings, JsonGameMetadata, JsonGameSettings, JsonGameStatus, JsonTakOpening,
-        JsonTimeSettings,
+        JsonBaseGameSettings, JsonGameMetadata, JsonGameSettings, JsonGameStatus,
+        JsonIncrementMode, JsonTakOpening, JsonTakRules, JsonTimeSettings,
     },
     seek::{CreateSeekPayload, JsonSeek},
     ws::{ClientMessage, ServerGameEventType, ServerMessage},
@@ -45,12 +45,16 @@ fn get_seek_payload() -> CreateSeekPayload {
                 half_komi: 4,
                 pieces: reserve.pieces,
                 capstones: reserve.capstones,
+                black_pieces: None,
+                black_capstones: None,
                 opening: JsonTakOpening::Swap,
+                rules: JsonTakRules::default(),
             },
             time_settings: JsonTimeSettings::Realtime {
                 increment_ms: 5_000,
                 contingent_ms: 300_000,
                 extra: None,
+                increment_mode: JsonIncrementMode::Fischer,
             },
         },
     }
@@ -187,6 +191,10 @@ impl Orchestrator {
                     {
                         let time_remaining = match game.game_settings.time_settings {
                             JsonTimeSettings::Realtime { contingent_ms, .. } => contingent_ms,
+                            JsonTimeSettings::RealtimeOdds { white, black } => match player {
+                                TakPlayer::White => white.contingent_ms,
+                                TakPlayer::Black => black.contingent_ms,
+                            },
                             JsonTimeSettings::Async { contingent_ms } => contingent_ms,
                         };
 
@@ -200,6 +208,7 @@ impl Orchestrator {
                 ServerMessage::ChatMessage { .. } => {}
                 ServerMessage::MatchEvent { .. } => {}
                 ServerMessage::AccountsOnline { .. } => {}
+                ServerMessage::ServerAlert { .. } => {}
             }
         }
     }
@@ -81,9 +81,14 @@ impl ServerApiImpl {
             ServerMessage::Error {
                 message,
                 code,
+                error_code,
                 response_id,
             } => {
                 if let Some(tx) = response_map.lock().unwrap().remove(&response_id) {
+                    let code = match error_code {
+                        Some(error_code) => format!("{} ({})", code, error_code),
+                        None => code.to_string(),
+                    };
                     let _ = tx.send(Err(format!("Error {}: {}", code, message)));
                 }
             }
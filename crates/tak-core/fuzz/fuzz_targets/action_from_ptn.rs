@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tak_core::ptn::{action_from_ptn, action_to_ptn};
+
+fuzz_target!(|data: &str| {
+    if let Some(action) = action_from_ptn(data) {
+        assert_eq!(action_from_ptn(&action_to_ptn(&action)), Some(action));
+    }
+});
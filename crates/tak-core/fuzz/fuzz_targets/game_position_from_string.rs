@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tak_core::ptn::{game_position_from_string, game_position_to_string};
+
+fuzz_target!(|data: &str| {
+    if let Some(position) = game_position_from_string(data) {
+        let tps = game_position_to_string(&position);
+        let reparsed = game_position_from_string(&tps).expect("written TPS should parse");
+        assert_eq!(reparsed.board.stacks(), position.board.stacks());
+        assert_eq!(reparsed.ply_index, position.ply_index);
+    }
+});
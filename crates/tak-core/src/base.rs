@@ -1,21 +1,34 @@
 use std::collections::HashMap;
 
 use crate::{
-    InvalidActionReason, InvalidPlaceReason, TakAction, TakBaseGameSettings, TakGameResult,
-    TakOpening, TakPlayer, TakReserve, TakVariant, TakWinReason, board::TakBoard,
+    InvalidActionReason, InvalidMoveReason, InvalidPlaceReason, InvalidPositionReason, TakAction,
+    TakBaseGameSettings, TakFlatTiebreak, TakGameResult, TakOpening, TakPlayer, TakPos,
+    TakRepetitionRule, TakReserve, TakReserveRunout, TakVariant, TakWinReason,
+    board::{TakBoard, zobrist},
+    ptn::TakGamePosition,
 };
 
 #[derive(Clone, Debug)]
 pub struct TakFinishedBaseGame {
     pub game_result: TakGameResult,
     pub action_history: Vec<TakAction>,
+    /// The squares of the winner's road(s), empty unless the game was won by road.
+    pub winning_road: Vec<TakPos>,
 }
 
 impl TakFinishedBaseGame {
     pub fn new(ended_game: &TakOngoingBaseGame, game_result: TakGameResult) -> Self {
+        let winning_road = match &game_result {
+            TakGameResult::Win {
+                winner,
+                reason: TakWinReason::Road,
+            } => ended_game.board.find_roads(*winner),
+            _ => Vec::new(),
+        };
         TakFinishedBaseGame {
             game_result,
             action_history: ended_game.action_history.clone(),
+            winning_road,
         }
     }
 }
@@ -26,27 +39,157 @@ pub struct TakOngoingBaseGame {
     pub board: TakBoard,
     pub current_player: TakPlayer,
     pub reserves: (TakReserve, TakReserve),
-    pub board_hash_history: HashMap<String, u32>,
+    pub board_hash_history: HashMap<u64, u32>,
     pub action_history: Vec<TakAction>,
+    pub initial_position: TakGamePosition,
+    undo_history: Vec<TakUndoRecord>,
+}
+
+#[derive(Clone, Debug)]
+enum TakUndoRecord {
+    Place {
+        previous_reserves: (TakReserve, TakReserve),
+        previous_hash: u64,
+    },
+    Move {
+        source_variant: TakVariant,
+        end_variant: Option<TakVariant>,
+        previous_hash: u64,
+    },
 }
 
 impl TakOngoingBaseGame {
     pub fn new(settings: TakBaseGameSettings) -> Self {
         let board = TakBoard::new(settings.board_size);
-        let reserves = (settings.reserve.clone(), settings.reserve.clone());
+        let reserves = settings.reserves();
         TakOngoingBaseGame {
             settings,
+            initial_position: TakGamePosition {
+                board: board.clone(),
+                ply_index: 0,
+            },
             board,
             current_player: TakPlayer::White,
             reserves,
             board_hash_history: HashMap::new(),
             action_history: Vec::new(),
+            undo_history: Vec::new(),
+        }
+    }
+
+    /// Starts a game from an arbitrary position, e.g. one parsed from TPS.
+    /// The remaining reserves are derived from the stones on the board.
+    pub fn from_position(
+        settings: TakBaseGameSettings,
+        position: TakGamePosition,
+    ) -> Result<Self, InvalidPositionReason> {
+        if position.board.size != settings.board_size {
+            return Err(InvalidPositionReason::BoardSizeMismatch);
+        }
+        let stacks = position.board.stacks().iter().flatten().collect::<Vec<_>>();
+        let mut used = (TakReserve::new(0, 0), TakReserve::new(0, 0));
+        for stack in &stacks {
+            let Some(top) = stack.composition.last() else {
+                return Err(InvalidPositionReason::EmptyStack);
+            };
+            for player in &stack.composition {
+                match player {
+                    TakPlayer::White => used.0.pieces += 1,
+                    TakPlayer::Black => used.1.pieces += 1,
+                }
+            }
+            if stack.variant == TakVariant::Capstone {
+                let top_used = match top {
+                    TakPlayer::White => &mut used.0,
+                    TakPlayer::Black => &mut used.1,
+                };
+                top_used.pieces -= 1;
+                top_used.capstones += 1;
+            }
+        }
+        let initial_reserves = settings.reserves();
+        let remaining = |player: TakPlayer, used: &TakReserve| {
+            let initial = match player {
+                TakPlayer::White => &initial_reserves.0,
+                TakPlayer::Black => &initial_reserves.1,
+            };
+            let pieces = initial.pieces.checked_sub(used.pieces);
+            let capstones = initial.capstones.checked_sub(used.capstones);
+            match (pieces, capstones) {
+                (Some(pieces), Some(capstones)) => Ok(TakReserve::new(pieces, capstones)),
+                (None, _) => Err(InvalidPositionReason::NotEnoughPieces(player)),
+                (_, None) => Err(InvalidPositionReason::NotEnoughCapstones(player)),
+            }
+        };
+        let reserves = (
+            remaining(TakPlayer::White, &used.0)?,
+            remaining(TakPlayer::Black, &used.1)?,
+        );
+
+        let is_valid_opening = match position.ply_index {
+            0 => stacks.is_empty(),
+            1 => {
+                let expected_composition = match settings.opening {
+                    TakOpening::Swap => vec![TakPlayer::Black],
+                    TakOpening::NoSwap => vec![TakPlayer::White],
+                    TakOpening::DoubleStack => vec![TakPlayer::Black; 2],
+                };
+                matches!(stacks.as_slice(), [stack] if stack.variant == TakVariant::Flat
+                    && stack.composition == expected_composition)
+            }
+            _ => true,
+        };
+        if !is_valid_opening {
+            return Err(InvalidPositionReason::OpeningViolation);
+        }
+
+        let game = TakOngoingBaseGame {
+            board: position.board.clone(),
+            current_player: if position.ply_index.is_multiple_of(2) {
+                TakPlayer::White
+            } else {
+                TakPlayer::Black
+            },
+            reserves,
+            board_hash_history: HashMap::new(),
+            action_history: Vec::new(),
+            undo_history: Vec::new(),
+            settings,
+            initial_position: position,
+        };
+        if game.board.check_for_road(TakPlayer::White)
+            || game.board.check_for_road(TakPlayer::Black)
+            || game.board.is_full()
+            || game.is_out_of_pieces()
+        {
+            return Err(InvalidPositionReason::GameAlreadyOver);
+        }
+        Ok(game)
+    }
+
+    /// Index of the next ply, counted from the start of the game rather than the initial position.
+    pub fn ply_index(&self) -> usize {
+        self.initial_position.ply_index + self.action_history.len()
+    }
+
+    /// Zobrist hash of the board and the side to move.
+    pub fn position_hash(&self) -> u64 {
+        match self.current_player {
+            TakPlayer::White => self.board.hash(),
+            TakPlayer::Black => self.board.hash() ^ zobrist::black_to_move_key(),
+        }
+    }
+
+    pub fn position(&self) -> TakGamePosition {
+        TakGamePosition {
+            board: self.board.clone(),
+            ply_index: self.ply_index(),
         }
     }
 
     pub fn can_do_action(&self, action: &TakAction) -> Result<(), InvalidActionReason> {
-        let is_opening_action = self.action_history.len() < 2;
-        let is_first_move = self.action_history.is_empty();
+        let is_opening_action = self.ply_index() < 2;
+        let is_first_move = self.ply_index() == 0;
         if is_opening_action {
             match action {
                 TakAction::Place { pos, variant } => {
@@ -64,7 +207,7 @@ impl TakOngoingBaseGame {
                             .has(TakVariant::Flat, if is_first_move { 2 } else { 1 }),
                     } {
                         return Err(InvalidActionReason::InvalidPlace(
-                            InvalidPlaceReason::NoPiecesRemaining,
+                            InvalidPlaceReason::NoPiecesRemaining(TakVariant::Flat),
                         ));
                     }
                     self.board
@@ -76,39 +219,92 @@ impl TakOngoingBaseGame {
         } else {
             match action {
                 TakAction::Place { pos, variant } => {
+                    if *variant == TakVariant::Standing && self.settings.rules.no_standing_stones {
+                        return Err(InvalidActionReason::InvalidPlace(
+                            InvalidPlaceReason::VariantNotAllowed,
+                        ));
+                    }
                     let reserve = match self.current_player {
                         TakPlayer::White => &self.reserves.0,
                         TakPlayer::Black => &self.reserves.1,
                     };
                     if !reserve.has(*variant, 1) {
                         return Err(InvalidActionReason::InvalidPlace(
-                            InvalidPlaceReason::NoPiecesRemaining,
+                            InvalidPlaceReason::NoPiecesRemaining(*variant),
                         ));
                     }
                     self.board
                         .can_do_place(pos)
                         .map_err(|e| InvalidActionReason::InvalidPlace(e))
                 }
-                TakAction::Move { pos, dir, drops } => self
-                    .board
-                    .can_do_move(pos, *dir, drops)
-                    .map_err(|e| InvalidActionReason::InvalidMove(e)),
+                TakAction::Move { pos, dir, drops } => {
+                    self.board
+                        .can_do_move(pos, *dir, drops)
+                        .map_err(InvalidActionReason::InvalidMove)?;
+                    if self.board.top_player(pos) != Some(self.current_player) {
+                        return Err(InvalidActionReason::InvalidMove(
+                            InvalidMoveReason::NotPlayersStack(pos.clone()),
+                        ));
+                    }
+                    Ok(())
+                }
             }
         }
     }
 
+    /// Enumerates every action the current player may perform, in no particular order.
+    pub fn legal_actions(&self) -> Vec<TakAction> {
+        let mut actions = Vec::new();
+        let is_opening_action = self.ply_index() < 2;
+        let variants: &[TakVariant] = if is_opening_action {
+            &[TakVariant::Flat]
+        } else {
+            &TakVariant::ALL
+        };
+        for y in 0..self.settings.board_size as i32 {
+            for x in 0..self.settings.board_size as i32 {
+                let pos = TakPos::new(x, y);
+                for variant in variants {
+                    let action = TakAction::Place {
+                        pos: pos.clone(),
+                        variant: *variant,
+                    };
+                    if self.can_do_action(&action).is_ok() {
+                        actions.push(action);
+                    }
+                }
+            }
+        }
+        if !is_opening_action {
+            self.board.push_moves(self.current_player, &mut actions);
+        }
+        actions
+    }
+
     pub fn do_action(
         &mut self,
         action: TakAction,
     ) -> Result<Option<TakFinishedBaseGame>, InvalidActionReason> {
-        if let Err(e) = self.can_do_action(&action) {
-            return Err(e);
-        }
-        let is_opening_action = self.action_history.len() < 2;
+        Ok(self
+            .make_action(action)?
+            .map(|game_result| TakFinishedBaseGame::new(self, game_result)))
+    }
+
+    /// Performs an action and returns the game result if it ended the game.
+    /// Unlike [`Self::do_action`], the action can always be reverted with [`Self::unmake_action`],
+    /// even after the game ended, which makes this the primitive for search and analysis.
+    pub fn make_action(
+        &mut self,
+        action: TakAction,
+    ) -> Result<Option<TakGameResult>, InvalidActionReason> {
+        self.can_do_action(&action)?;
+        let is_opening_action = self.ply_index() < 2;
         let moved_player = self.current_player;
-        let is_first_move = self.action_history.is_empty();
-        match &action {
+        let is_first_move = self.ply_index() == 0;
+        let previous_hash = self.position_hash();
+        let undo = match &action {
             TakAction::Place { pos, variant } => {
+                let previous_reserves = self.reserves.clone();
                 let (reserve, opponent_reserve) = match self.current_player {
                     TakPlayer::White => (&mut self.reserves.0, &mut self.reserves.1),
                     TakPlayer::Black => (&mut self.reserves.1, &mut self.reserves.0),
@@ -143,62 +339,119 @@ impl TakOngoingBaseGame {
                 self.board
                     .do_place(pos, *variant, placing_composition)
                     .expect("can_do_action should have prevented invalid place due to board state");
+                TakUndoRecord::Place {
+                    previous_reserves,
+                    previous_hash,
+                }
             }
             TakAction::Move { pos, dir, drops } => {
+                let source_variant = self
+                    .board
+                    .variant_at(pos)
+                    .expect("can_do_action should have prevented moving an empty stack");
+                let end_pos = pos.offset(*dir, drops.len() as i32);
+                let end_variant = self.board.variant_at(&end_pos);
                 self.board
                     .do_move(pos, *dir, drops)
                     .expect("can_do_action should have prevented invalid move due to board state");
+                TakUndoRecord::Move {
+                    source_variant,
+                    end_variant,
+                    previous_hash,
+                }
             }
-        }
+        };
         self.action_history.push(action);
+        self.undo_history.push(undo);
         self.current_player = self.current_player.opponent();
 
-        let board_hash = self.board.compute_hash_string();
+        let board_hash = self.position_hash();
         self.board_hash_history
-            .entry(board_hash.clone())
+            .entry(board_hash)
             .and_modify(|e| *e += 1)
             .or_insert(1);
 
         Ok(self.check_game_over(board_hash, moved_player))
     }
 
-    pub fn undo_action(&mut self) -> bool {
-        if self.action_history.pop().is_none() {
-            return false;
-        };
-        let mut game_clone = TakOngoingBaseGame::new(self.settings.clone());
-        for record in &self.action_history {
-            match game_clone.do_action(record.clone()) {
-                Ok(None) => {}
-                Ok(Some(_)) => {
-                    //This should never happen, and the module is closed to preserve invariants, so we panic here
-                    panic!(
-                        "Finished game encountered when replaying action during undo: {:?}",
-                        record
-                    );
-                }
-                Err(e) => {
-                    //This should never happen, and the module is closed to preserve invariants, so we panic here
-                    panic!(
-                        "Failed to replay action during undo: {:?}, error: {:?}",
-                        record, e
-                    );
-                }
+    /// Reverts the last action in constant time and returns it.
+    /// Actions that were part of the initial position cannot be reverted.
+    pub fn unmake_action(&mut self) -> Option<TakAction> {
+        let undo = self.undo_history.pop()?;
+        let action = self
+            .action_history
+            .pop()
+            .expect("undo history and action history should have the same length");
+
+        let board_hash = self.position_hash();
+        if let Some(count) = self.board_hash_history.get_mut(&board_hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.board_hash_history.remove(&board_hash);
             }
         }
-        *self = game_clone;
-        true
+        self.current_player = self.current_player.opponent();
+
+        let previous_hash = match (&action, undo) {
+            (
+                TakAction::Place { pos, .. },
+                TakUndoRecord::Place {
+                    previous_reserves,
+                    previous_hash,
+                },
+            ) => {
+                self.board.undo_place(pos);
+                self.reserves = previous_reserves;
+                previous_hash
+            }
+            (
+                TakAction::Move { pos, dir, drops },
+                TakUndoRecord::Move {
+                    source_variant,
+                    end_variant,
+                    previous_hash,
+                },
+            ) => {
+                self.board
+                    .undo_move(pos, *dir, drops, source_variant, end_variant);
+                previous_hash
+            }
+            (action, undo) => {
+                //This should never happen, and the module is closed to preserve invariants, so we panic here
+                panic!(
+                    "Undo record does not match action: {:?}, record: {:?}",
+                    action, undo
+                );
+            }
+        };
+        debug_assert_eq!(self.position_hash(), previous_hash);
+        Some(action)
+    }
+
+    pub fn undo_action(&mut self) -> bool {
+        self.unmake_action().is_some()
     }
 
-    fn check_game_over(
-        &self,
-        board_hash: String,
-        moved_player: TakPlayer,
-    ) -> Option<TakFinishedBaseGame> {
-        let white_reserve_empty = self.reserves.0.pieces == 0 && self.reserves.0.capstones == 0;
-        let black_reserve_empty = self.reserves.1.pieces == 0 && self.reserves.1.capstones == 0;
+    /// Whether running out of pieces ends the game, depending on the reserve runout rule.
+    fn is_out_of_pieces(&self) -> bool {
+        let is_empty = |reserve: &TakReserve| reserve.pieces == 0 && reserve.capstones == 0;
+        let white_empty = is_empty(&self.reserves.0);
+        let black_empty = is_empty(&self.reserves.1);
+        match self.settings.rules.reserve_runout {
+            TakReserveRunout::EitherEmpty => white_empty || black_empty,
+            TakReserveRunout::BothEmpty => {
+                let to_move_empty = match self.current_player {
+                    TakPlayer::White => white_empty,
+                    TakPlayer::Black => black_empty,
+                };
+                (white_empty && black_empty)
+                    || (to_move_empty && !self.board.controls_stack(self.current_player))
+            }
+        }
+    }
 
-        let game_result = if self.board.check_for_road(moved_player) {
+    fn check_game_over(&self, board_hash: u64, moved_player: TakPlayer) -> Option<TakGameResult> {
+        if self.board.check_for_road(moved_player) {
             Some(TakGameResult::Win {
                 winner: moved_player,
                 reason: TakWinReason::Road,
@@ -208,11 +461,18 @@ impl TakOngoingBaseGame {
                 winner: moved_player.opponent(),
                 reason: TakWinReason::Road,
             })
-        } else if self.board.is_full() || white_reserve_empty || black_reserve_empty {
+        } else if self.board.is_full() || self.is_out_of_pieces() {
             let (white_flats, black_flats) = self.board.count_flats();
             let (white_score, black_score) =
                 (white_flats * 2, black_flats * 2 + self.settings.half_komi);
-            Some(match white_score.cmp(&black_score) {
+            let tiebreak = match self.settings.rules.flat_tiebreak {
+                TakFlatTiebreak::Draw => std::cmp::Ordering::Equal,
+                TakFlatTiebreak::Reserve => {
+                    let remaining = |reserve: &TakReserve| reserve.pieces + reserve.capstones;
+                    remaining(&self.reserves.0).cmp(&remaining(&self.reserves.1))
+                }
+            };
+            Some(match white_score.cmp(&black_score).then(tiebreak) {
                 std::cmp::Ordering::Greater => TakGameResult::Win {
                     winner: TakPlayer::White,
                     reason: TakWinReason::Flats,
@@ -223,13 +483,518 @@ impl TakOngoingBaseGame {
                 },
                 std::cmp::Ordering::Equal => TakGameResult::Draw,
             })
-        } else if let Some(repeat_count) = self.board_hash_history.get(&board_hash)
+        } else if self.settings.rules.repetition == TakRepetitionRule::ThreefoldDraw
+            && let Some(repeat_count) = self.board_hash_history.get(&board_hash)
             && *repeat_count >= 3
         {
             Some(TakGameResult::Draw)
         } else {
             None
-        }?;
-        Some(TakFinishedBaseGame::new(self, game_result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::{TakDir, TakRules, ptn::game_position_from_string};
+
+    fn settings(board_size: u32, opening: TakOpening) -> TakBaseGameSettings {
+        TakBaseGameSettings {
+            board_size,
+            half_komi: 0,
+            reserve: TakReserve::from_size(board_size).unwrap(),
+            black_reserve: None,
+            opening,
+            rules: TakRules::default(),
+        }
+    }
+
+    fn all_drop_sequences(total: u32, max_len: usize) -> Vec<Vec<u32>> {
+        if total == 0 {
+            return vec![vec![]];
+        }
+        (1..=total)
+            .flat_map(|first| {
+                all_drop_sequences(total - first, max_len.saturating_sub(1))
+                    .into_iter()
+                    .filter(move |rest| rest.len() < max_len)
+                    .map(move |rest| [vec![first], rest].concat())
+            })
+            .collect()
+    }
+
+    fn brute_force_actions(game: &TakOngoingBaseGame) -> HashSet<TakAction> {
+        let size = game.settings.board_size;
+        let mut actions = HashSet::new();
+        for y in 0..size as i32 {
+            for x in 0..size as i32 {
+                let pos = TakPos::new(x, y);
+                for variant in TakVariant::ALL {
+                    actions.insert(TakAction::Place {
+                        pos: pos.clone(),
+                        variant,
+                    });
+                }
+                for dir in TakDir::ALL {
+                    for total in 1..=size {
+                        for drops in all_drop_sequences(total, size as usize) {
+                            actions.insert(TakAction::Move {
+                                pos: pos.clone(),
+                                dir,
+                                drops,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        actions.retain(|action| game.can_do_action(action).is_ok());
+        actions
+    }
+
+    #[test]
+    fn test_legal_actions_match_can_do_action() {
+        for tps in [
+            "x5/x5/x5/x5/x5 1 1",
+            "x5/x5/x5/x5/2,x4 2 1",
+            "x3,12,2S/x,22S,22C,11,21/121,212,12,1121C,1212S/21S,1,21,211S,12S/x,21S,2,x2 1 26",
+            "2,x4/x,2,x3/1,2S,1C,x2/x,1,x3/1,x4 2 5",
+        ] {
+            let position = game_position_from_string(tps).unwrap();
+            let game =
+                TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+            let legal = game.legal_actions();
+            let legal_set = legal.iter().cloned().collect::<HashSet<_>>();
+            assert_eq!(
+                legal.len(),
+                legal_set.len(),
+                "duplicate actions for {}",
+                tps
+            );
+            assert_eq!(legal_set, brute_force_actions(&game), "tps: {}", tps);
+        }
+    }
+
+    #[test]
+    fn test_legal_actions_opening() {
+        let game = TakOngoingBaseGame::new(settings(4, TakOpening::NoSwap));
+        assert_eq!(game.legal_actions().len(), 16);
+
+        let mut game = TakOngoingBaseGame::new(TakBaseGameSettings {
+            reserve: TakReserve::new(2, 0),
+            ..settings(4, TakOpening::DoubleStack)
+        });
+        assert_eq!(game.legal_actions().len(), 16);
+        game.do_action(TakAction::Place {
+            pos: TakPos::new(0, 0),
+            variant: TakVariant::Flat,
+        })
+        .unwrap();
+        // black's reserve is used up by the double stack, white still has both pieces
+        assert_eq!(game.legal_actions().len(), 15);
+    }
+
+    #[test]
+    fn test_legal_actions_capstone_flattening() {
+        let position = game_position_from_string("x5/x5/x5/x5/21C,x,1S,2S,x 1 4").unwrap();
+        let game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        let moves = game
+            .legal_actions()
+            .into_iter()
+            .filter(|a| matches!(a, TakAction::Move { .. }))
+            .collect::<HashSet<_>>();
+        let expected = [
+            (TakDir::Up, vec![1]),
+            (TakDir::Up, vec![2]),
+            (TakDir::Up, vec![1, 1]),
+            (TakDir::Right, vec![1]),
+            (TakDir::Right, vec![2]),
+            (TakDir::Right, vec![1, 1]),
+        ]
+        .into_iter()
+        .map(|(dir, drops)| TakAction::Move {
+            pos: TakPos::new(0, 0),
+            dir,
+            drops,
+        })
+        .chain([TakAction::Move {
+            pos: TakPos::new(2, 0),
+            dir: TakDir::Up,
+            drops: vec![1],
+        }])
+        .chain([TakAction::Move {
+            pos: TakPos::new(2, 0),
+            dir: TakDir::Left,
+            drops: vec![1],
+        }])
+        .collect::<HashSet<_>>();
+        assert_eq!(moves, expected);
+    }
+
+    #[test]
+    fn test_invalid_action_reasons() {
+        let position = game_position_from_string("x5/x5/x2,1S,x2/x,2,2C,x2/1,x4 1 4").unwrap();
+        let game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        let reject = |ptn: &str| {
+            let reason = game
+                .can_do_action(&crate::ptn::action_from_ptn(ptn).unwrap())
+                .unwrap_err();
+            (reason.code(), reason.to_string())
+        };
+        assert_eq!(
+            reject("b2"),
+            ("square_occupied", "b2 is already occupied".to_string())
+        );
+        assert_eq!(
+            reject("f1"),
+            ("place_out_of_bounds", "f1 is not on the board".to_string())
+        );
+        assert_eq!(
+            reject("b2<"),
+            (
+                "not_players_stack",
+                "the stack on b2 belongs to the opponent".to_string()
+            )
+        );
+        assert_eq!(
+            reject("2a1+"),
+            (
+                "not_enough_pieces",
+                "the stack on a1 only has 1 pieces".to_string()
+            )
+        );
+        assert_eq!(
+            reject("6a1+"),
+            (
+                "exceeds_carry_limit",
+                "at most 5 pieces can be carried".to_string()
+            )
+        );
+        assert_eq!(
+            reject("a1<"),
+            (
+                "move_out_of_bounds",
+                "the move from a1 leaves the board".to_string()
+            )
+        );
+        assert_eq!(reject("a2+").0, "square_empty");
+        assert_eq!(reject("c3-").0, "blocked_by_capstone");
+
+        let position = game_position_from_string("x5/x5/x2,1S,x2/x2,1,x2/x5 1 4").unwrap();
+        let game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        let reason = game
+            .can_do_action(&crate::ptn::action_from_ptn("c2+").unwrap())
+            .unwrap_err();
+        assert_eq!(reason.code(), "blocked_by_standing_stone");
+        assert!(reason.to_string().contains("c3"));
+    }
+
+    #[test]
+    fn test_move_opponents_stack() {
+        // the stack on a1 is topped by black, the one on b1 by white
+        let position = game_position_from_string("x5/x5/x5/x5/12,21,x3 1 3").unwrap();
+        let mut game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        for ptn in ["a1+", "2a1+", "a1>"] {
+            let action = crate::ptn::action_from_ptn(ptn).unwrap();
+            assert!(matches!(
+                game.do_action(action),
+                Err(InvalidActionReason::InvalidMove(
+                    InvalidMoveReason::NotPlayersStack(pos)
+                )) if pos == TakPos::new(0, 0)
+            ));
+        }
+        assert_eq!(game.current_player, TakPlayer::White);
+        assert_eq!(
+            game.board.stack_at(&TakPos::new(0, 0)).unwrap().composition,
+            vec![TakPlayer::White, TakPlayer::Black]
+        );
+
+        // controlling the top is enough, even with the opponent's pieces below
+        let action = crate::ptn::action_from_ptn("2b1+").unwrap();
+        assert!(matches!(game.do_action(action), Ok(None)));
+    }
+
+    #[test]
+    fn test_threefold_repetition() {
+        let position = game_position_from_string("x5/x5/x5/x5/1,2,x3 1 2").unwrap();
+        let mut game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        for ptn in ["a1+", "b1+", "a2-", "b2-", "a1+", "b1+", "a2-", "b2-"] {
+            let action = crate::ptn::action_from_ptn(ptn).unwrap();
+            assert!(game.do_action(action).unwrap().is_none());
+        }
+        // white to move with the same board for the third time after a1+
+        let finished = game
+            .do_action(crate::ptn::action_from_ptn("a1+").unwrap())
+            .unwrap()
+            .expect("threefold repetition should end the game");
+        assert_eq!(finished.game_result, TakGameResult::Draw);
+    }
+
+    #[test]
+    fn test_rule_variants() {
+        let with_rules = |rules: TakRules| TakBaseGameSettings {
+            rules,
+            ..settings(3, TakOpening::Swap)
+        };
+
+        let position = game_position_from_string("x3/x3/1,2,x 1 2").unwrap();
+        let game = TakOngoingBaseGame::from_position(
+            with_rules(TakRules {
+                no_standing_stones: true,
+                ..TakRules::default()
+            }),
+            position,
+        )
+        .unwrap();
+        assert!(matches!(
+            game.can_do_action(&crate::ptn::action_from_ptn("Sc3").unwrap()),
+            Err(InvalidActionReason::InvalidPlace(
+                InvalidPlaceReason::VariantNotAllowed
+            ))
+        ));
+        assert!(game.legal_actions().iter().all(|action| !matches!(
+            action,
+            TakAction::Place {
+                variant: TakVariant::Standing,
+                ..
+            }
+        )));
+
+        // equal flats on a full board, black has more pieces left in reserve
+        let flat_count = |rules| {
+            let position = game_position_from_string("1,2,1/2,1,2/1S,1,x 2 5").unwrap();
+            let mut game = TakOngoingBaseGame::from_position(with_rules(rules), position).unwrap();
+            game.do_action(crate::ptn::action_from_ptn("c1").unwrap())
+                .unwrap()
+                .map(|finished| finished.game_result)
+        };
+        assert_eq!(flat_count(TakRules::default()), Some(TakGameResult::Draw));
+        assert_eq!(
+            flat_count(TakRules {
+                flat_tiebreak: TakFlatTiebreak::Reserve,
+                ..TakRules::default()
+            }),
+            Some(TakGameResult::Win {
+                winner: TakPlayer::Black,
+                reason: TakWinReason::Flats,
+            })
+        );
+
+        let position = game_position_from_string("x3/x3/1,2,x 1 2").unwrap();
+        let mut game = TakOngoingBaseGame::from_position(
+            with_rules(TakRules {
+                repetition: TakRepetitionRule::Allowed,
+                ..TakRules::default()
+            }),
+            position,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            for ptn in ["a1+", "b1+", "a2-", "b2-"] {
+                let action = crate::ptn::action_from_ptn(ptn).unwrap();
+                assert!(game.do_action(action).unwrap().is_none());
+            }
+        }
+
+        // both players have one piece left
+        let both_reserves = TakRules {
+            reserve_runout: TakReserveRunout::BothEmpty,
+            ..TakRules::default()
+        };
+        let with_reserves = |rules, white, black| TakBaseGameSettings {
+            reserve: TakReserve::new(white, 0),
+            black_reserve: Some(TakReserve::new(black, 0)),
+            ..with_rules(rules)
+        };
+        let position = game_position_from_string("x3/x3/1,2,x 1 2").unwrap();
+        let mut game = TakOngoingBaseGame::from_position(
+            with_reserves(TakRules::default(), 2, 2),
+            position.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            game.do_action(crate::ptn::action_from_ptn("a3").unwrap())
+                .unwrap()
+                .map(|finished| finished.game_result),
+            Some(TakGameResult::Win {
+                winner: TakPlayer::White,
+                reason: TakWinReason::Flats,
+            })
+        );
+        let mut game =
+            TakOngoingBaseGame::from_position(with_reserves(both_reserves.clone(), 2, 2), position)
+                .unwrap();
+        assert!(
+            game.do_action(crate::ptn::action_from_ptn("a3").unwrap())
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            game.do_action(crate::ptn::action_from_ptn("c3").unwrap())
+                .unwrap()
+                .map(|finished| finished.game_result),
+            Some(TakGameResult::Draw)
+        );
+
+        // white is out of pieces and can only go on while controlling a stack
+        let position = game_position_from_string("x3/x3/21,x2 1 2").unwrap();
+        assert!(
+            TakOngoingBaseGame::from_position(with_reserves(both_reserves.clone(), 1, 3), position)
+                .is_ok()
+        );
+        let position = game_position_from_string("x3/x3/12,x2 1 2").unwrap();
+        assert!(matches!(
+            TakOngoingBaseGame::from_position(with_reserves(both_reserves, 1, 3), position),
+            Err(InvalidPositionReason::GameAlreadyOver)
+        ));
+    }
+
+    #[test]
+    fn test_winning_road() {
+        let position = game_position_from_string("x5/x5/1,x4/1,1,1,1,x/1,2,2,2,2 1 6").unwrap();
+        let mut game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        let finished = game
+            .do_action(crate::ptn::action_from_ptn("e2").unwrap())
+            .unwrap()
+            .expect("e2 should complete the road");
+        // the branches on a1 and a3 are not part of the road
+        assert_eq!(
+            finished.winning_road,
+            (0..5).map(|x| TakPos::new(x, 1)).collect::<Vec<_>>()
+        );
+
+        let mut game = TakOngoingBaseGame::new(settings(5, TakOpening::Swap));
+        game.do_action(crate::ptn::action_from_ptn("a1").unwrap())
+            .unwrap();
+        game.do_action(crate::ptn::action_from_ptn("e5").unwrap())
+            .unwrap();
+        let finished = TakFinishedBaseGame::new(&game, TakGameResult::Draw);
+        assert!(finished.winning_road.is_empty());
+    }
+
+    #[test]
+    fn test_handicap_reserves() {
+        let handicap = TakBaseGameSettings {
+            black_reserve: Some(TakReserve::new(2, 0)),
+            ..settings(3, TakOpening::Swap)
+        };
+        assert!(handicap.is_handicap());
+
+        let mut game = TakOngoingBaseGame::new(handicap.clone());
+        assert_eq!(
+            game.reserves,
+            (TakReserve::new(10, 0), TakReserve::new(2, 0))
+        );
+        for ptn in ["a1", "c3", "b2"] {
+            assert!(
+                game.do_action(crate::ptn::action_from_ptn(ptn).unwrap())
+                    .unwrap()
+                    .is_none()
+            );
+        }
+        assert_eq!(
+            game.reserves,
+            (TakReserve::new(8, 0), TakReserve::new(1, 0))
+        );
+        let finished = game
+            .do_action(crate::ptn::action_from_ptn("a3").unwrap())
+            .unwrap()
+            .expect("black should have run out of pieces");
+        assert_eq!(finished.game_result, TakGameResult::Draw);
+
+        let position = game_position_from_string("2,2,2/1,x2/1,1,x 1 4").unwrap();
+        assert!(matches!(
+            TakOngoingBaseGame::from_position(handicap, position),
+            Err(InvalidPositionReason::NotEnoughPieces(TakPlayer::Black))
+        ));
+    }
+
+    #[test]
+    fn test_position_hash_includes_side_to_move() {
+        let white = TakOngoingBaseGame::from_position(
+            settings(5, TakOpening::Swap),
+            game_position_from_string("x5/x5/x5/x5/1,2,x3 1 2").unwrap(),
+        )
+        .unwrap();
+        let black = TakOngoingBaseGame::from_position(
+            settings(5, TakOpening::Swap),
+            game_position_from_string("x5/x5/x5/x5/1,2,x3 2 2").unwrap(),
+        )
+        .unwrap();
+        assert_eq!(white.board.hash(), black.board.hash());
+        assert_ne!(white.position_hash(), black.position_hash());
+    }
+
+    #[test]
+    fn test_unmake_action_restores_state() {
+        type Snapshot = (
+            Vec<Option<crate::board::TakStack>>,
+            u64,
+            (TakReserve, TakReserve),
+            TakPlayer,
+            HashMap<u64, u32>,
+        );
+        fn snapshot(game: &TakOngoingBaseGame) -> Snapshot {
+            (
+                game.board.stacks().to_vec(),
+                game.position_hash(),
+                game.reserves.clone(),
+                game.current_player,
+                game.board_hash_history.clone(),
+            )
+        }
+
+        for (seed, opening) in [
+            (1u64, TakOpening::Swap),
+            (7, TakOpening::NoSwap),
+            (42, TakOpening::DoubleStack),
+        ] {
+            let mut rng = seed;
+            let mut game = TakOngoingBaseGame::new(settings(5, opening));
+            let mut snapshots = Vec::new();
+            for _ in 0..120 {
+                let actions = game.legal_actions();
+                rng = rng
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let action = actions[(rng >> 33) as usize % actions.len()].clone();
+                snapshots.push(snapshot(&game));
+                let result = game.make_action(action).unwrap();
+                assert_eq!(game.board.hash(), game.board.compute_hash());
+                if result.is_some() {
+                    break;
+                }
+            }
+            while let Some(expected) = snapshots.pop() {
+                assert!(game.unmake_action().is_some());
+                assert!(snapshot(&game) == expected, "seed {}", seed);
+            }
+            assert!(game.unmake_action().is_none());
+            assert!(game.action_history.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_undo_stops_at_initial_position() {
+        let position = game_position_from_string("x5/x5/x5/x5/1,2,x3 1 2").unwrap();
+        let mut game =
+            TakOngoingBaseGame::from_position(settings(5, TakOpening::Swap), position).unwrap();
+        game.do_action(crate::ptn::action_from_ptn("a1>").unwrap())
+            .unwrap();
+        assert_eq!(game.ply_index(), 3);
+        assert!(game.undo_action());
+        assert_eq!(game.ply_index(), 2);
+        assert_eq!(game.current_player, TakPlayer::White);
+        assert_eq!(game.reserves.0, TakReserve::new(20, 1));
+        assert!(!game.undo_action());
     }
 }
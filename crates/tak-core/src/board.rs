@@ -1,35 +1,139 @@
-use std::collections::VecDeque;
-
-use crate::{InvalidMoveReason, InvalidPlaceReason, TakDir, TakPlayer, TakPos, TakVariant};
+use crate::{
+    InvalidMoveReason, InvalidPlaceReason, TakAction, TakDir, TakPlayer, TakPos, TakVariant,
+};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TakStack {
     pub variant: TakVariant,
     pub composition: Vec<TakPlayer>,
 }
 
+/// Board state kept as one stack per square plus one bitboard per top-stone property,
+/// with bit `y * size + x` standing for the square at `(x, y)`.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "TakBoardStacks", into = "TakBoardStacks")
+)]
 pub struct TakBoard {
     pub size: u32,
-    pub stacks: Vec<Option<TakStack>>,
+    stacks: Vec<Option<TakStack>>,
+    white_tops: u64,
+    black_tops: u64,
+    standing: u64,
+    capstones: u64,
+    hash: u64,
+}
+
+/// The serialized form of a board, square `y * size + x` being the one at `(x, y)`.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TakBoardStacks {
+    size: u32,
+    stacks: Vec<Option<TakStack>>,
+}
+
+#[cfg(feature = "serde")]
+impl From<TakBoard> for TakBoardStacks {
+    fn from(board: TakBoard) -> Self {
+        TakBoardStacks {
+            size: board.size,
+            stacks: board.stacks,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<TakBoardStacks> for TakBoard {
+    type Error = String;
+
+    fn try_from(board: TakBoardStacks) -> Result<Self, Self::Error> {
+        if board.size > TakBoard::MAX_SIZE {
+            return Err(format!("board size {} is too large", board.size));
+        }
+        if board.stacks.len() != (board.size * board.size) as usize {
+            return Err(format!(
+                "expected {} squares, got {}",
+                board.size * board.size,
+                board.stacks.len()
+            ));
+        }
+        Ok(TakBoard::from_stacks(board.size, board.stacks))
+    }
 }
 
 impl TakBoard {
+    /// Largest board that fits into the 64 bit masks.
+    pub const MAX_SIZE: u32 = 8;
+
     pub fn new(size: u32) -> Self {
+        assert!(
+            size <= Self::MAX_SIZE,
+            "board size {size} exceeds the maximum of {}",
+            Self::MAX_SIZE
+        );
         let board_area = size * size;
         TakBoard {
             size,
             stacks: vec![None; board_area as usize],
+            white_tops: 0,
+            black_tops: 0,
+            standing: 0,
+            capstones: 0,
+            hash: 0,
+        }
+    }
+
+    pub fn from_stacks(size: u32, stacks: Vec<Option<TakStack>>) -> Self {
+        let mut board = TakBoard::new(size);
+        for (index, stack) in stacks.into_iter().enumerate() {
+            let Some(stack) = stack else {
+                continue;
+            };
+            if stack.composition.is_empty() {
+                continue;
+            }
+            for player in stack.composition {
+                board.push_piece(index, player);
+            }
+            board.set_variant(index, stack.variant);
         }
+        board
+    }
+
+    /// Zobrist hash of the board, kept up to date by `do_place` and `do_move`.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Recomputes the Zobrist hash from scratch.
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (index, stack) in self.stacks.iter().enumerate() {
+            let Some(stack) = stack else {
+                continue;
+            };
+            for (height, player) in stack.composition.iter().enumerate() {
+                hash ^= zobrist::piece_key(index, height, *player);
+            }
+            hash ^= zobrist::variant_key(index, stack.variant);
+        }
+        hash
+    }
+
+    /// Every square, in the same order as `from_stacks` expects them.
+    pub fn stacks(&self) -> &[Option<TakStack>] {
+        &self.stacks
     }
 
     pub fn can_do_place(&self, pos: &TakPos) -> Result<(), InvalidPlaceReason> {
         if !pos.is_valid(self.size) {
-            return Err(InvalidPlaceReason::OutOfBounds);
+            return Err(InvalidPlaceReason::OutOfBounds(pos.clone()));
         }
-        let index = (pos.y * self.size as i32 + pos.x) as usize;
-        if self.stacks[index].is_some() {
-            return Err(InvalidPlaceReason::PositionOccupied);
+        if self.occupied() & self.square(pos) != 0 {
+            return Err(InvalidPlaceReason::PositionOccupied(pos.clone()));
         }
         Ok(())
     }
@@ -41,11 +145,11 @@ impl TakBoard {
         composition: Vec<TakPlayer>,
     ) -> Result<(), InvalidPlaceReason> {
         self.can_do_place(&pos)?;
-        let index = (pos.y * self.size as i32 + pos.x) as usize;
-        self.stacks[index] = Some(TakStack {
-            variant: variant.clone(),
-            composition,
-        });
+        let index = self.index(pos);
+        for player in composition {
+            self.push_piece(index, player);
+        }
+        self.set_variant(index, variant);
         Ok(())
     }
 
@@ -56,47 +160,46 @@ impl TakBoard {
         drops: &[u32],
     ) -> Result<(), InvalidMoveReason> {
         if !pos.is_valid(self.size) {
-            return Err(InvalidMoveReason::OutOfBounds);
+            return Err(InvalidMoveReason::OutOfBounds(pos.clone()));
         }
-        let index = (pos.y * self.size as i32 + pos.x) as usize;
-        let stack = self.stacks[index]
-            .as_ref()
-            .ok_or_else(|| InvalidMoveReason::PositionEmpty)?;
-        let total_pieces: u32 = stack.composition.len() as u32;
+        let index = self.index(pos);
+        let variant = self
+            .square_variant(index)
+            .ok_or_else(|| InvalidMoveReason::PositionEmpty(pos.clone()))?;
+        let total_pieces = self.stack_height(index);
         let drops_sum: u32 = drops.iter().sum();
-        if drops_sum == 0 || drops_sum > self.size {
+        if drops_sum == 0 {
             return Err(InvalidMoveReason::InvalidDropDistribution);
         }
+        if drops_sum > self.size {
+            return Err(InvalidMoveReason::ExceedsCarryLimit {
+                carry_limit: self.size,
+            });
+        }
         if drops_sum > total_pieces {
-            return Err(InvalidMoveReason::InvalidNumberOfPieces);
+            return Err(InvalidMoveReason::InvalidNumberOfPieces {
+                pos: pos.clone(),
+                available: total_pieces,
+            });
         }
         let drops_len = drops.len();
         let end_pos = pos.offset(dir, drops_len as i32);
         if !end_pos.is_valid(self.size) {
-            return Err(InvalidMoveReason::OutOfBounds);
+            return Err(InvalidMoveReason::OutOfBounds(pos.clone()));
         }
         for i in 0..drops_len {
             if drops[i] == 0 {
                 return Err(InvalidMoveReason::InvalidDropDistribution);
             }
             let cur_pos = pos.offset(dir, i as i32 + 1);
-            let cur_index = (cur_pos.y * self.size as i32 + cur_pos.x) as usize;
-
-            match &self.stacks[cur_index] {
-                Some(TakStack {
-                    variant: TakVariant::Standing,
-                    composition: _,
-                }) if stack.variant != TakVariant::Capstone
-                    || i < drops_len - 1
-                    || drops[i] != 1 =>
+            match self.square_variant(self.index(&cur_pos)) {
+                Some(TakVariant::Standing)
+                    if variant != TakVariant::Capstone || i < drops_len - 1 || drops[i] != 1 =>
                 {
-                    return Err(InvalidMoveReason::CannotMoveOverStandingPieces);
+                    return Err(InvalidMoveReason::CannotMoveOverStandingPieces(cur_pos));
                 }
-                Some(TakStack {
-                    variant: TakVariant::Capstone,
-                    composition: _,
-                }) => {
-                    return Err(InvalidMoveReason::CannotMoveOverCapstonePieces);
+                Some(TakVariant::Capstone) => {
+                    return Err(InvalidMoveReason::CannotMoveOverCapstonePieces(cur_pos));
                 }
                 _ => {}
             }
@@ -111,151 +214,423 @@ impl TakBoard {
         drops: &[u32],
     ) -> Result<(), InvalidMoveReason> {
         self.can_do_move(pos, dir, drops)?;
-        let index = (pos.y * self.size as i32 + pos.x) as usize;
-        let stack = self.stacks[index].as_mut().unwrap();
-        let total_pieces: u32 = stack.composition.len() as u32;
+        let index = self.index(pos);
+        let variant = self
+            .square_variant(index)
+            .expect("can_do_move should have rejected an empty square");
         let drops_sum: u32 = drops.iter().sum();
-        let variant = stack.variant.clone();
-        let mut moving_pieces = if drops_sum == total_pieces {
-            self.stacks[index].take().unwrap().composition
-        } else {
-            stack.variant = TakVariant::Flat;
-            stack
-                .composition
-                .split_off((total_pieces - drops_sum) as usize)
-        };
-        moving_pieces.reverse();
+        self.set_variant(index, TakVariant::Flat);
+        // bit k is set if the k-th carried piece from the bottom is black
+        let mut carried = 0u32;
+        for k in (0..drops_sum).rev() {
+            if self.pop_piece(index) == TakPlayer::Black {
+                carried |= 1 << k;
+            }
+        }
 
-        let drops_len = drops.len();
-        for i in 0..drops_len {
-            let current_pos = pos.offset(dir, i as i32 + 1);
-            let cur_index = (current_pos.y * self.size as i32 + current_pos.x) as usize;
-            let cur_stack = self.stacks[cur_index].get_or_insert(TakStack {
-                variant: TakVariant::Flat,
-                composition: vec![],
-            });
-            let to_drop = moving_pieces
-                .drain(moving_pieces.len() - drops[i] as usize..)
-                .rev();
-            cur_stack.composition.extend(to_drop);
-            if i == drops_len - 1 {
-                cur_stack.variant = variant.clone();
+        let mut k = 0;
+        for (i, drop) in drops.iter().enumerate() {
+            let cur_index = self.index(&pos.offset(dir, i as i32 + 1));
+            for _ in 0..*drop {
+                self.push_piece(cur_index, carried_player(carried, k));
+                k += 1;
             }
         }
+        let end_index = self.index(&pos.offset(dir, drops.len() as i32));
+        self.set_variant(end_index, variant);
         Ok(())
     }
 
-    pub fn is_full(&self) -> bool {
-        self.stacks.iter().all(|s| s.is_some())
+    pub fn stack_at(&self, pos: &TakPos) -> Option<TakStack> {
+        if !pos.is_valid(self.size) {
+            return None;
+        }
+        self.stacks[self.index(pos)].clone()
     }
 
-    pub fn count_flats(&self) -> (u32, u32) {
-        let mut white_flats = 0;
-        let mut black_flats = 0;
-        for stack in &self.stacks {
-            if let Some(s) = stack {
-                if let Some(top_player) = s.composition.last() {
-                    match s.variant {
-                        TakVariant::Flat => match top_player {
-                            TakPlayer::White => white_flats += 1,
-                            TakPlayer::Black => black_flats += 1,
-                        },
-                        _ => {}
-                    }
+    pub fn variant_at(&self, pos: &TakPos) -> Option<TakVariant> {
+        if !pos.is_valid(self.size) {
+            return None;
+        }
+        self.square_variant(self.index(pos))
+    }
+
+    /// Reverts a `do_place` at `pos`, returning the removed stack.
+    pub fn undo_place(&mut self, pos: &TakPos) -> Option<TakStack> {
+        let stack = self.stack_at(pos)?;
+        let index = self.index(pos);
+        self.set_variant(index, TakVariant::Flat);
+        for _ in 0..stack.composition.len() {
+            self.pop_piece(index);
+        }
+        Some(stack)
+    }
+
+    /// Reverts a `do_move`. `source_variant` is the variant of the moved stack before the move and
+    /// `end_variant` the variant of the stack on the last square, if that square was occupied.
+    pub fn undo_move(
+        &mut self,
+        pos: &TakPos,
+        dir: TakDir,
+        drops: &[u32],
+        source_variant: TakVariant,
+        end_variant: Option<TakVariant>,
+    ) {
+        let index = self.index(pos);
+        let drops_sum: u32 = drops.iter().sum();
+        let mut carried = 0u32;
+        let mut k = drops_sum;
+        for (i, drop) in drops.iter().enumerate().rev() {
+            let cur_index = self.index(&pos.offset(dir, i as i32 + 1));
+            if i == drops.len() - 1 {
+                self.set_variant(cur_index, end_variant.unwrap_or(TakVariant::Flat));
+            }
+            for _ in 0..*drop {
+                k -= 1;
+                if self.pop_piece(cur_index) == TakPlayer::Black {
+                    carried |= 1 << k;
                 }
             }
         }
-        (white_flats, black_flats)
+        for k in 0..drops_sum {
+            self.push_piece(index, carried_player(carried, k));
+        }
+        self.set_variant(index, source_variant);
     }
 
-    fn is_road_square(&self, pos: &TakPos, player: TakPlayer) -> bool {
+    pub fn top_player(&self, pos: &TakPos) -> Option<TakPlayer> {
         if !pos.is_valid(self.size) {
-            return false;
+            return None;
+        }
+        let square = self.square(pos);
+        if self.white_tops & square != 0 {
+            Some(TakPlayer::White)
+        } else if self.black_tops & square != 0 {
+            Some(TakPlayer::Black)
+        } else {
+            None
         }
-        let index = (pos.y * self.size as i32 + pos.x) as usize;
-        if let Some(stack) = &self.stacks[index] {
-            if let Some(top_player) = stack.composition.last() {
-                return stack.variant != TakVariant::Standing && *top_player == player;
+    }
+
+    /// Appends every legal spread of a stack controlled by `player`, including capstone flattening.
+    pub fn push_moves(&self, player: TakPlayer, actions: &mut Vec<TakAction>) {
+        let controlled = self.tops(player);
+        for y in 0..self.size as i32 {
+            for x in 0..self.size as i32 {
+                let pos = TakPos::new(x, y);
+                let index = self.index(&pos);
+                if controlled & 1 << index == 0 {
+                    continue;
+                }
+                let is_capstone = self.capstones & 1 << index != 0;
+                let carry_limit = self.stack_height(index).min(self.size);
+                for dir in TakDir::ALL {
+                    let mut reach = 0;
+                    let mut can_flatten = false;
+                    loop {
+                        let next = pos.offset(dir, reach as i32 + 1);
+                        if !next.is_valid(self.size) {
+                            break;
+                        }
+                        match self.square_variant(self.index(&next)) {
+                            None | Some(TakVariant::Flat) => reach += 1,
+                            Some(TakVariant::Standing) => {
+                                can_flatten = is_capstone;
+                                break;
+                            }
+                            Some(TakVariant::Capstone) => break,
+                        }
+                    }
+                    for carry in 1..=carry_limit {
+                        for drops in drop_sequences(carry, reach) {
+                            actions.push(TakAction::Move {
+                                pos: pos.clone(),
+                                dir,
+                                drops,
+                            });
+                        }
+                        if can_flatten {
+                            // the capstone has to drop alone onto the standing stone
+                            for mut drops in drop_sequences(carry - 1, reach) {
+                                if drops.len() == reach {
+                                    drops.push(1);
+                                    actions.push(TakAction::Move {
+                                        pos: pos.clone(),
+                                        dir,
+                                        drops,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
             }
         }
-        false
+    }
+
+    /// Whether `player` has a stone on top of any stack.
+    pub fn controls_stack(&self, player: TakPlayer) -> bool {
+        self.tops(player) != 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.occupied() == self.board_mask()
+    }
+
+    pub fn count_flats(&self) -> (u32, u32) {
+        let walls_and_caps = self.standing | self.capstones;
+        (
+            (self.white_tops & !walls_and_caps).count_ones(),
+            (self.black_tops & !walls_and_caps).count_ones(),
+        )
+    }
+
+    /// Squares that can be part of a road for `player`.
+    fn road_mask(&self, player: TakPlayer) -> u64 {
+        self.tops(player) & !self.standing
     }
 
     pub fn check_for_road(&self, player: TakPlayer) -> bool {
-        self.find_road(true, player) || self.find_road(false, player)
+        self.find_road(true, player).is_some() || self.find_road(false, player).is_some()
     }
 
-    fn find_road(&self, horizontal: bool, player: TakPlayer) -> bool {
-        let mut visited = vec![false; (self.size * self.size) as usize];
-        let mut queue = VecDeque::new();
+    /// The squares of a shortest road for `player` in each direction that has one,
+    /// ordered by index. Empty if `player` has no road.
+    pub fn find_roads(&self, player: TakPlayer) -> Vec<TakPos> {
+        let roads =
+            self.find_road(true, player).unwrap_or(0) | self.find_road(false, player).unwrap_or(0);
+        (0..self.stacks.len())
+            .filter(|index| roads & 1 << index != 0)
+            .map(|index| {
+                TakPos::new(
+                    (index as u32 % self.size) as i32,
+                    (index as u32 / self.size) as i32,
+                )
+            })
+            .collect()
+    }
 
-        for i in 0..self.size as i32 {
-            let start_pos = if horizontal {
-                TakPos { x: 0, y: i }
-            } else {
-                TakPos { x: i, y: 0 }
-            };
-            if self.is_road_square(&start_pos, player) {
-                queue.push_back(start_pos.clone());
-                let index = (start_pos.y * self.size as i32 + start_pos.x) as usize;
-                visited[index] = true;
+    /// Flood fills the road squares connected to one edge layer by layer until the other edge
+    /// is reached, then walks back through the layers to pick out a shortest road.
+    fn find_road(&self, horizontal: bool, player: TakPlayer) -> Option<u64> {
+        let last = self.size - 1;
+        let (start, end) = if horizontal {
+            (self.column_mask(0), self.column_mask(last))
+        } else {
+            (self.row_mask(0), self.row_mask(last))
+        };
+        let road = self.road_mask(player);
+        let mut reached = road & start;
+        let mut layers = vec![reached];
+        while reached & end == 0 {
+            let next = self.neighbors(reached) & road;
+            if next == reached {
+                return None;
             }
+            layers.push(next & !reached);
+            reached = next;
         }
 
-        while let Some(current_pos) = queue.pop_front() {
-            let is_end = if horizontal {
-                current_pos.x == self.size as i32 - 1
-            } else {
-                current_pos.y == self.size as i32 - 1
-            };
-            if is_end {
-                return true;
-            }
+        let mut current = lowest_square(reached & end & layers.pop()?);
+        let mut path = current;
+        for layer in layers.into_iter().rev() {
+            current = lowest_square(self.neighbors(current) & layer);
+            path |= current;
+        }
+        Some(path)
+    }
 
-            for dir in TakDir::ALL {
-                let neighbor = current_pos.offset(dir, 1);
-                if self.is_road_square(&neighbor, player) {
-                    let index = (neighbor.y * self.size as i32 + neighbor.x) as usize;
-                    if !visited[index] {
-                        queue.push_back(neighbor);
-                        visited[index] = true;
-                    }
-                }
-            }
+    /// `squares` together with all orthogonally adjacent squares.
+    fn neighbors(&self, squares: u64) -> u64 {
+        let left_edge = self.column_mask(0);
+        let right_edge = self.column_mask(self.size - 1);
+        (squares
+            | (squares & !right_edge) << 1
+            | (squares & !left_edge) >> 1
+            | squares << self.size
+            | squares >> self.size)
+            & self.board_mask()
+    }
+
+    fn board_mask(&self) -> u64 {
+        let area = self.size * self.size;
+        if area == 64 {
+            u64::MAX
+        } else {
+            (1 << area) - 1
         }
+    }
 
-        false
-    }
-
-    pub fn compute_hash_string(&self) -> String {
-        let mut hash = Vec::new();
-        for stack in &self.stacks {
-            match stack {
-                Some(s) => {
-                    let variant_char = match s.variant {
-                        TakVariant::Flat => 'F',
-                        TakVariant::Standing => 'S',
-                        TakVariant::Capstone => 'C',
-                    };
-                    let composition_str: String = s
-                        .composition
-                        .iter()
-                        .map(|p| match p {
-                            TakPlayer::White => 'W',
-                            TakPlayer::Black => 'B',
-                        })
-                        .collect();
-                    hash.push(format!("{}{}", variant_char, composition_str));
-                }
-                None => {
-                    hash.push("N".to_string());
-                }
-            }
+    fn row_mask(&self, y: u32) -> u64 {
+        ((1 << self.size) - 1) << (y * self.size)
+    }
+
+    fn column_mask(&self, x: u32) -> u64 {
+        (0..self.size).fold(0, |mask, y| mask | 1 << (y * self.size + x))
+    }
+
+    fn index(&self, pos: &TakPos) -> usize {
+        (pos.y * self.size as i32 + pos.x) as usize
+    }
+
+    fn square(&self, pos: &TakPos) -> u64 {
+        1 << self.index(pos)
+    }
+
+    fn occupied(&self) -> u64 {
+        self.white_tops | self.black_tops
+    }
+
+    fn tops(&self, player: TakPlayer) -> u64 {
+        match player {
+            TakPlayer::White => self.white_tops,
+            TakPlayer::Black => self.black_tops,
+        }
+    }
+
+    fn square_variant(&self, index: usize) -> Option<TakVariant> {
+        let square = 1 << index;
+        if self.occupied() & square == 0 {
+            None
+        } else if self.standing & square != 0 {
+            Some(TakVariant::Standing)
+        } else if self.capstones & square != 0 {
+            Some(TakVariant::Capstone)
+        } else {
+            Some(TakVariant::Flat)
         }
-        hash.join(",")
+    }
+
+    fn stack_height(&self, index: usize) -> u32 {
+        self.stacks[index]
+            .as_ref()
+            .map_or(0, |stack| stack.composition.len() as u32)
+    }
+
+    /// Changes the variant of the top stone; empty squares count as flat.
+    fn set_variant(&mut self, index: usize, variant: TakVariant) {
+        let previous = self.square_variant(index).unwrap_or(TakVariant::Flat);
+        self.hash ^= zobrist::variant_key(index, previous) ^ zobrist::variant_key(index, variant);
+        let square = 1 << index;
+        self.standing &= !square;
+        self.capstones &= !square;
+        match variant {
+            TakVariant::Flat => {}
+            TakVariant::Standing => self.standing |= square,
+            TakVariant::Capstone => self.capstones |= square,
+        }
+        if let Some(stack) = &mut self.stacks[index] {
+            stack.variant = variant;
+        }
+    }
+
+    fn push_piece(&mut self, index: usize, player: TakPlayer) {
+        let stack = self.stacks[index].get_or_insert_with(|| TakStack {
+            variant: TakVariant::Flat,
+            composition: Vec::new(),
+        });
+        self.hash ^= zobrist::piece_key(index, stack.composition.len(), player);
+        stack.composition.push(player);
+        self.update_top(index);
+    }
+
+    fn pop_piece(&mut self, index: usize) -> TakPlayer {
+        //This should never happen, callers only pop pieces they have checked are there
+        let stack = self.stacks[index]
+            .as_mut()
+            .expect("popped piece from an empty square");
+        let player = stack
+            .composition
+            .pop()
+            .expect("popped piece from an empty square");
+        self.hash ^= zobrist::piece_key(index, stack.composition.len(), player);
+        if stack.composition.is_empty() {
+            self.stacks[index] = None;
+        }
+        self.update_top(index);
+        player
+    }
+
+    fn update_top(&mut self, index: usize) {
+        let square = 1 << index;
+        self.white_tops &= !square;
+        self.black_tops &= !square;
+        match self.stacks[index]
+            .as_ref()
+            .and_then(|stack| stack.composition.last())
+        {
+            Some(TakPlayer::White) => self.white_tops |= square,
+            Some(TakPlayer::Black) => self.black_tops |= square,
+            None => {}
+        }
+    }
+}
+
+fn lowest_square(squares: u64) -> u64 {
+    squares & squares.wrapping_neg()
+}
+
+fn carried_player(carried: u32, k: u32) -> TakPlayer {
+    if carried >> k & 1 == 1 {
+        TakPlayer::Black
+    } else {
+        TakPlayer::White
     }
 }
 
+/// Zobrist keys are derived on demand by mixing a feature index with splitmix64 instead of
+/// being stored in a table, so stacks of any height can be hashed.
+pub(crate) mod zobrist {
+    use crate::{TakPlayer, TakVariant};
+
+    fn key(feature: u64) -> u64 {
+        let mut z = feature.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn piece_key(index: usize, height: usize, player: TakPlayer) -> u64 {
+        let player_bit = match player {
+            TakPlayer::White => 0,
+            TakPlayer::Black => 1,
+        };
+        key((((height as u64) << 8 | index as u64) << 1 | player_bit) << 2)
+    }
+
+    pub fn variant_key(index: usize, variant: TakVariant) -> u64 {
+        match variant {
+            TakVariant::Flat => 0,
+            TakVariant::Standing => key((index as u64) << 2 | 1),
+            TakVariant::Capstone => key((index as u64) << 2 | 2),
+        }
+    }
+
+    pub fn black_to_move_key() -> u64 {
+        key(3)
+    }
+}
+
+/// All ways to drop `total` pieces onto at most `max_len` consecutive squares, one or more per square.
+fn drop_sequences(total: u32, max_len: usize) -> Vec<Vec<u32>> {
+    if total == 0 {
+        return vec![vec![]];
+    }
+    let mut sequences = Vec::new();
+    if max_len == 0 {
+        return sequences;
+    }
+    for first in 1..=total {
+        for rest in drop_sequences(total - first, max_len - 1) {
+            let mut sequence = Vec::with_capacity(rest.len() + 1);
+            sequence.push(first);
+            sequence.extend(rest);
+            sequences.push(sequence);
+        }
+    }
+    sequences
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,7 +647,7 @@ mod tests {
         assert!(board.do_move(&pos, TakDir::Right, &[1]).is_ok());
 
         let new_pos = TakPos { x: 1, y: 0 };
-        assert!(board.stacks[(new_pos.y * board.size as i32 + new_pos.x) as usize].is_some());
+        assert!(board.stack_at(&new_pos).is_some());
 
         assert!(
             board
@@ -282,12 +657,9 @@ mod tests {
         assert!(board.can_do_move(&pos, TakDir::Right, &[1]).is_ok());
         assert!(board.do_move(&pos, TakDir::Right, &[1]).is_ok());
 
+        assert_eq!(board.stack_at(&pos), None);
         assert_eq!(
-            board.stacks[(pos.y * board.size as i32 + pos.x) as usize],
-            None
-        );
-        assert_eq!(
-            board.stacks[(new_pos.y * board.size as i32 + new_pos.x) as usize],
+            board.stack_at(&new_pos),
             Some(TakStack {
                 variant: TakVariant::Capstone,
                 composition: vec![TakPlayer::White, TakPlayer::Black],
@@ -298,14 +670,14 @@ mod tests {
         assert!(board.do_move(&new_pos, TakDir::Left, &[1]).is_ok());
 
         assert_eq!(
-            board.stacks[(pos.y * board.size as i32 + pos.x) as usize],
+            board.stack_at(&pos),
             Some(TakStack {
                 variant: TakVariant::Capstone,
                 composition: vec![TakPlayer::Black],
             })
         );
         assert_eq!(
-            board.stacks[(new_pos.y * board.size as i32 + new_pos.x) as usize],
+            board.stack_at(&new_pos),
             Some(TakStack {
                 variant: TakVariant::Flat,
                 composition: vec![TakPlayer::White],
@@ -369,4 +741,159 @@ mod tests {
 
         assert!(board.check_for_road(player));
     }
+
+    #[test]
+    fn test_incremental_hash() {
+        let mut board = TakBoard::new(5);
+        assert_eq!(board.hash(), board.compute_hash());
+        let start_pos = TakPos { x: 1, y: 1 };
+        board
+            .do_place(&start_pos, TakVariant::Capstone, vec![TakPlayer::White])
+            .unwrap();
+        board
+            .do_place(
+                &TakPos { x: 2, y: 1 },
+                TakVariant::Flat,
+                vec![TakPlayer::Black, TakPlayer::White],
+            )
+            .unwrap();
+        board
+            .do_place(
+                &TakPos { x: 3, y: 1 },
+                TakVariant::Standing,
+                vec![TakPlayer::Black],
+            )
+            .unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        let hash_before_moves = board.hash();
+
+        board.do_move(&start_pos, TakDir::Right, &[1]).unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        board
+            .do_move(&TakPos { x: 2, y: 1 }, TakDir::Right, &[1])
+            .unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        board
+            .do_move(&TakPos { x: 3, y: 1 }, TakDir::Left, &[1])
+            .unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        board
+            .do_move(&TakPos { x: 2, y: 1 }, TakDir::Left, &[1])
+            .unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        // the wall has been flattened, so the position differs from the start
+        assert_ne!(board.hash(), hash_before_moves);
+
+        let mut other = TakBoard::new(5);
+        other
+            .do_place(
+                &TakPos { x: 0, y: 0 },
+                TakVariant::Flat,
+                vec![TakPlayer::White, TakPlayer::Black],
+            )
+            .unwrap();
+        let mut swapped = TakBoard::new(5);
+        swapped
+            .do_place(
+                &TakPos { x: 0, y: 0 },
+                TakVariant::Flat,
+                vec![TakPlayer::Black, TakPlayer::White],
+            )
+            .unwrap();
+        assert_ne!(other.hash(), swapped.hash());
+    }
+
+    #[test]
+    fn test_road_bitboards() {
+        let place = |board: &mut TakBoard, x, y, variant| {
+            board
+                .do_place(&TakPos { x, y }, variant, vec![TakPlayer::Black])
+                .unwrap();
+        };
+
+        // the last square of a row must not connect to the first square of the next one
+        let mut board = TakBoard::new(4);
+        for (x, y) in [(1, 0), (2, 0), (3, 0), (0, 1)] {
+            place(&mut board, x, y, TakVariant::Flat);
+        }
+        assert!(!board.check_for_road(TakPlayer::Black));
+
+        // winding road that doubles back on itself
+        let mut board = TakBoard::new(5);
+        for (x, y) in [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+        ] {
+            place(&mut board, x, y, TakVariant::Flat);
+        }
+        assert!(board.check_for_road(TakPlayer::Black));
+        assert!(!board.check_for_road(TakPlayer::White));
+        // the dead ends on a1 and a2 are not part of the shortest road
+        assert_eq!(
+            board.find_roads(TakPlayer::Black),
+            [(2, 1), (3, 1), (4, 1), (0, 2), (1, 2), (2, 2)].map(|(x, y)| TakPos { x, y })
+        );
+
+        // walls break roads, capstones don't
+        let mut board = TakBoard::new(3);
+        place(&mut board, 0, 0, TakVariant::Flat);
+        place(&mut board, 0, 1, TakVariant::Standing);
+        place(&mut board, 0, 2, TakVariant::Flat);
+        assert!(!board.check_for_road(TakPlayer::Black));
+        board.undo_place(&TakPos { x: 0, y: 1 });
+        place(&mut board, 0, 1, TakVariant::Capstone);
+        assert!(board.check_for_road(TakPlayer::Black));
+        assert_eq!(board.count_flats(), (0, 2));
+
+        let mut board = TakBoard::new(8);
+        for y in 0..8 {
+            place(&mut board, 7, y, TakVariant::Flat);
+        }
+        assert!(board.check_for_road(TakPlayer::Black));
+    }
+
+    #[test]
+    fn test_tall_stacks() {
+        let composition = (0..300)
+            .map(|i| {
+                if i % 3 == 2 {
+                    TakPlayer::Black
+                } else {
+                    TakPlayer::White
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut stacks = vec![None; 9];
+        stacks[0] = Some(TakStack {
+            variant: TakVariant::Capstone,
+            composition: composition.clone(),
+        });
+        let mut board = TakBoard::from_stacks(3, stacks.clone());
+        assert_eq!(board.stacks(), stacks);
+        assert_eq!(board.hash(), board.compute_hash());
+
+        let pos = TakPos { x: 0, y: 0 };
+        board.do_move(&pos, TakDir::Right, &[2, 1]).unwrap();
+        assert_eq!(board.hash(), board.compute_hash());
+        assert_eq!(
+            board.stack_at(&pos).unwrap().composition,
+            composition[..297].to_vec()
+        );
+        assert_eq!(
+            board.stack_at(&TakPos { x: 2, y: 0 }).unwrap(),
+            TakStack {
+                variant: TakVariant::Capstone,
+                composition: vec![TakPlayer::Black],
+            }
+        );
+        board.undo_move(&pos, TakDir::Right, &[2, 1], TakVariant::Capstone, None);
+        assert_eq!(board.stacks(), stacks);
+        assert_eq!(board.hash(), board.compute_hash());
+    }
 }
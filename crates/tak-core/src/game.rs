@@ -1,15 +1,18 @@
 use std::time::{Duration, Instant};
 
 use crate::{
-    InvalidActionReason, MaybeTimeout, TakAction, TakAsyncTimeControl, TakGameResult,
-    TakGameSettings, TakPlayer, TakRealtimeTimeControl, TakTimeInfo, TakTimeSettings, TakWinReason,
+    InvalidActionReason, InvalidPositionReason, MaybeTimeout, TakAction, TakAsyncTimeControl,
+    TakGameResult, TakGameSettings, TakIncrementMode, TakPlayer, TakPlyTime, TakPos,
+    TakRealtimeTimeControl, TakTimeInfo, TakTimeSettings, TakWinReason,
     base::{TakFinishedBaseGame, TakOngoingBaseGame},
+    ptn::TakGamePosition,
 };
 
 #[derive(Clone, Debug)]
 pub struct TakFinishedGame {
     base: TakFinishedBaseGame,
     time_info: TakTimeInfo,
+    ply_times: Vec<TakPlyTime>,
 }
 
 impl TakFinishedGame {
@@ -17,6 +20,7 @@ impl TakFinishedGame {
         TakFinishedGame {
             base: TakFinishedBaseGame::new(&ongoing_game.base, game_result),
             time_info: ongoing_game.get_time_info(now),
+            ply_times: ongoing_game.ply_times.clone(),
         }
     }
 
@@ -28,6 +32,7 @@ impl TakFinishedGame {
         TakFinishedGame {
             base: finished_base,
             time_info: ongoing_game.get_time_info(now),
+            ply_times: ongoing_game.ply_times.clone(),
         }
     }
 
@@ -39,9 +44,17 @@ impl TakFinishedGame {
         &self.base.game_result
     }
 
+    pub fn winning_road(&self) -> &Vec<TakPos> {
+        &self.base.winning_road
+    }
+
     pub fn get_time_info(&self) -> TakTimeInfo {
         self.time_info.clone()
     }
+
+    pub fn ply_times(&self) -> &Vec<TakPlyTime> {
+        &self.ply_times
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -51,16 +64,36 @@ enum TakClockUpdatePolicy {
 }
 
 impl TakClockUpdatePolicy {
-    fn end_turn(&mut self, game: &TakOngoingBaseGame, clock: &mut TakClock, player: TakPlayer) {
+    fn end_turn(
+        &mut self,
+        game: &TakOngoingBaseGame,
+        clock: &mut TakClock,
+        player: TakPlayer,
+        elapsed: Duration,
+    ) {
         match self {
             TakClockUpdatePolicy::Realtime(policy) => {
-                policy.end_turn(game, clock, player);
+                policy.end_turn(game, clock, player, elapsed);
             }
             TakClockUpdatePolicy::Async(policy) => {
                 policy.end_turn(clock);
             }
         }
     }
+
+    /// Time at the start of each turn during which `player`'s clock doesn't run.
+    fn delay(&self, player: TakPlayer) -> Duration {
+        match self {
+            TakClockUpdatePolicy::Realtime(policy) => {
+                let settings = policy.settings(player);
+                match settings.increment_mode {
+                    TakIncrementMode::Delay => settings.increment,
+                    TakIncrementMode::Fischer | TakIncrementMode::Bronstein => Duration::ZERO,
+                }
+            }
+            TakClockUpdatePolicy::Async(_) => Duration::ZERO,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -68,6 +101,7 @@ pub struct TakOngoingGame {
     base: TakOngoingBaseGame,
     clock: TakClock,
     clock_update_policy: TakClockUpdatePolicy,
+    ply_times: Vec<TakPlyTime>,
 }
 
 #[derive(Clone, Debug)]
@@ -75,23 +109,56 @@ struct TakClock {
     remaining_time: (Duration, Duration),
     last_update_timestamp: Instant,
     is_ticking: bool,
+    /// Paused clocks are not charged while their player is to move.
+    is_paused: (bool, bool),
+    /// Time charged in the current turn before `last_update_timestamp`, i.e. before the
+    /// clock of the player to move was last paused. Counts against their delay.
+    turn_elapsed: Duration,
+}
+
+impl TakClock {
+    fn is_paused(&self, player: TakPlayer) -> bool {
+        match player {
+            TakPlayer::White => self.is_paused.0,
+            TakPlayer::Black => self.is_paused.1,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 struct TakRealtimeClockUpdatePolicy {
-    settings: TakRealtimeTimeControl,
+    settings: (TakRealtimeTimeControl, TakRealtimeTimeControl),
     has_gained_extra_time: (bool, bool),
 }
 
 impl TakRealtimeClockUpdatePolicy {
-    fn end_turn(&mut self, game: &TakOngoingBaseGame, clock: &mut TakClock, player: TakPlayer) {
+    fn settings(&self, player: TakPlayer) -> &TakRealtimeTimeControl {
+        match player {
+            TakPlayer::White => &self.settings.0,
+            TakPlayer::Black => &self.settings.1,
+        }
+    }
+
+    fn end_turn(
+        &mut self,
+        game: &TakOngoingBaseGame,
+        clock: &mut TakClock,
+        player: TakPlayer,
+        elapsed: Duration,
+    ) {
+        let settings = self.settings(player).clone();
         let remaining = match player {
             TakPlayer::White => &mut clock.remaining_time.0,
             TakPlayer::Black => &mut clock.remaining_time.1,
         };
-        *remaining = remaining.saturating_add(self.settings.increment);
+        let bonus = match settings.increment_mode {
+            TakIncrementMode::Fischer => settings.increment,
+            TakIncrementMode::Delay => Duration::ZERO,
+            TakIncrementMode::Bronstein => elapsed.min(settings.increment),
+        };
+        *remaining = remaining.saturating_add(bonus);
 
-        if let Some((extra_move_index, extra_time)) = self.settings.extra {
+        if let Some((extra_move_index, extra_time)) = settings.extra {
             let has_gained_extra_time = match player {
                 TakPlayer::White => &mut self.has_gained_extra_time.0,
                 TakPlayer::Black => &mut self.has_gained_extra_time.1,
@@ -99,7 +166,7 @@ impl TakRealtimeClockUpdatePolicy {
             // ply index is incremented before clock update, which means it is odd for white moves and starts at 1 for move 1
             // move 1: white 1, black 2 ---(+1)--> (2, 3) ---(/2)--> (1, 1)
             // move 2: white 3, black 4 ---(+1)--> (4, 5) ---(/2)--> (2, 2)
-            let move_index = (game.action_history.len() + 1) / 2;
+            let move_index = (game.ply_index() + 1) / 2;
             if !*has_gained_extra_time && extra_move_index as usize == move_index {
                 *remaining = remaining.saturating_add(extra_time);
                 *has_gained_extra_time = true;
@@ -122,34 +189,102 @@ impl TakAsyncClockUpdatePolicy {
 impl TakOngoingGame {
     pub fn new(settings: TakGameSettings) -> Self {
         let base_game = TakOngoingBaseGame::new(settings.base);
-        let clock = match &settings.time_settings {
-            TakTimeSettings::Realtime(settings) => TakClock {
-                remaining_time: (settings.contingent, settings.contingent),
-                last_update_timestamp: Instant::now(),
-                is_ticking: false,
-            },
-            TakTimeSettings::Async(settings) => TakClock {
-                remaining_time: (settings.contingent, settings.contingent),
-                last_update_timestamp: Instant::now(),
-                is_ticking: false,
+        Self::with_base(base_game, &settings.time_settings)
+    }
+
+    pub fn from_position(
+        settings: TakGameSettings,
+        position: TakGamePosition,
+    ) -> Result<Self, InvalidPositionReason> {
+        let base_game = TakOngoingBaseGame::from_position(settings.base, position)?;
+        Ok(Self::with_base(base_game, &settings.time_settings))
+    }
+
+    /// Rebuilds a game from its action history and the clocks as they were last seen, e.g. after
+    /// a server restart. The clock of the player to move starts running again at `now`, the time
+    /// in between isn't charged to anyone. `paused` are the pause states of the white and black
+    /// clock, see [`Self::set_clock_paused`], and `turn_think_time` is the time the player to
+    /// move had already used, see [`Self::turn_think_time`]. Returns `None` if the actions don't
+    /// lead to an ongoing game.
+    pub fn resume(
+        settings: TakGameSettings,
+        actions: Vec<TakAction>,
+        ply_times: Vec<TakPlyTime>,
+        time_info: TakTimeInfo,
+        paused: (bool, bool),
+        turn_think_time: Duration,
+        now: Instant,
+    ) -> Option<Self> {
+        let mut game = Self::new(settings);
+        for action in actions {
+            if game.base.do_action(action).ok()?.is_some() {
+                return None;
+            }
+        }
+        if let TakClockUpdatePolicy::Realtime(policy) = &mut game.clock_update_policy {
+            // extra time is already part of the remaining time once a player got past the move
+            let plies = game.base.ply_index();
+            let (white_extra, black_extra) = (&policy.settings.0.extra, &policy.settings.1.extra);
+            policy.has_gained_extra_time = (
+                white_extra.is_some_and(|(move_index, _)| plies.div_ceil(2) >= move_index as usize),
+                black_extra.is_some_and(|(move_index, _)| plies / 2 >= move_index as usize),
+            );
+        }
+        let is_ticking = !game.base.action_history.is_empty();
+        game.clock = TakClock {
+            remaining_time: (time_info.white_remaining, time_info.black_remaining),
+            last_update_timestamp: now,
+            is_ticking,
+            is_paused: paused,
+            turn_elapsed: if is_ticking {
+                turn_think_time
+            } else {
+                Duration::ZERO
             },
         };
-        let mode = match &settings.time_settings {
+        game.ply_times = ply_times;
+        Some(game)
+    }
+
+    fn with_base(base_game: TakOngoingBaseGame, time_settings: &TakTimeSettings) -> Self {
+        let mode = match time_settings {
             TakTimeSettings::Realtime(t) => {
                 TakClockUpdatePolicy::Realtime(TakRealtimeClockUpdatePolicy {
                     has_gained_extra_time: (false, false),
-                    settings: t.clone(),
+                    settings: (t.clone(), t.clone()),
+                })
+            }
+            TakTimeSettings::RealtimeOdds(white, black) => {
+                TakClockUpdatePolicy::Realtime(TakRealtimeClockUpdatePolicy {
+                    has_gained_extra_time: (false, false),
+                    settings: (white.clone(), black.clone()),
                 })
             }
             TakTimeSettings::Async(t) => TakClockUpdatePolicy::Async(TakAsyncClockUpdatePolicy {
                 settings: t.clone(),
             }),
         };
+        let remaining_time = match &mode {
+            TakClockUpdatePolicy::Realtime(policy) => {
+                (policy.settings.0.contingent, policy.settings.1.contingent)
+            }
+            TakClockUpdatePolicy::Async(policy) => {
+                (policy.settings.contingent, policy.settings.contingent)
+            }
+        };
+        let clock = TakClock {
+            remaining_time,
+            last_update_timestamp: Instant::now(),
+            is_ticking: false,
+            is_paused: (false, false),
+            turn_elapsed: Duration::ZERO,
+        };
 
         TakOngoingGame {
             base: base_game,
             clock,
             clock_update_policy: mode,
+            ply_times: Vec::new(),
         }
     }
 
@@ -161,6 +296,22 @@ impl TakOngoingGame {
         self.base.current_player
     }
 
+    /// The game without its clock, e.g. to try out actions on a clone.
+    pub fn base_game(&self) -> &TakOngoingBaseGame {
+        &self.base
+    }
+
+    /// Whether the player who made the last move threatens to complete a road, see
+    /// [`TakOngoingBaseGame::is_tak`].
+    pub fn is_tak(&self) -> bool {
+        self.base.is_tak()
+    }
+
+    /// Clock usage of every ply in `action_history`.
+    pub fn ply_times(&self) -> &Vec<TakPlyTime> {
+        &self.ply_times
+    }
+
     fn set_game_over(&mut self, now: Instant, game_result: TakGameResult) -> TakFinishedGame {
         let player = self.base.current_player;
         self.stop_clock(now, player);
@@ -173,7 +324,7 @@ impl TakOngoingGame {
         if time_remaining.is_zero() {
             let game_result = TakGameResult::Win {
                 winner: player.opponent(),
-                reason: TakWinReason::Default,
+                reason: TakWinReason::Time,
             };
             let finished_game = self.set_game_over(now, game_result);
             MaybeTimeout::Timeout(finished_game)
@@ -187,11 +338,30 @@ impl TakOngoingGame {
             TakPlayer::White => self.clock.remaining_time.0,
             TakPlayer::Black => self.clock.remaining_time.1,
         };
-        if self.base.current_player != player || !self.clock.is_ticking {
+        if self.base.current_player != player
+            || !self.clock.is_ticking
+            || self.clock.is_paused(player)
+        {
             return base_remaining;
         }
         let elapsed = now.saturating_duration_since(self.clock.last_update_timestamp);
-        base_remaining.saturating_sub(elapsed)
+        base_remaining.saturating_sub(elapsed.saturating_sub(self.delay_left(player)))
+    }
+
+    /// The part of `player`'s delay that the current turn hasn't used up yet.
+    fn delay_left(&self, player: TakPlayer) -> Duration {
+        self.clock_update_policy
+            .delay(player)
+            .saturating_sub(self.clock.turn_elapsed)
+    }
+
+    /// How long the player to move has had the turn, as long as they haven't made their first
+    /// ply yet. The clocks don't run before white's first ply, so this is tracked separately.
+    pub fn first_ply_wait(&self, now: Instant) -> Option<Duration> {
+        if self.base.action_history.len() >= 2 {
+            return None;
+        }
+        Some(now.saturating_duration_since(self.clock.last_update_timestamp))
     }
 
     pub fn get_time_info(&self, now: Instant) -> TakTimeInfo {
@@ -201,14 +371,18 @@ impl TakOngoingGame {
         }
     }
 
+    /// Charges the time since the last update to `player` and adds it to the turn's elapsed time.
     fn maybe_apply_elapsed(&mut self, now: Instant, player: TakPlayer) {
+        let delay = self.delay_left(player);
+        let is_charged = self.clock.is_ticking && !self.clock.is_paused(player);
         let remaining = match player {
             TakPlayer::White => &mut self.clock.remaining_time.0,
             TakPlayer::Black => &mut self.clock.remaining_time.1,
         };
-        if self.clock.is_ticking {
+        if is_charged {
             let elapsed = now.saturating_duration_since(self.clock.last_update_timestamp);
-            *remaining = remaining.saturating_sub(elapsed);
+            *remaining = remaining.saturating_sub(elapsed.saturating_sub(delay));
+            self.clock.turn_elapsed += elapsed;
         }
 
         self.clock.last_update_timestamp = now;
@@ -216,16 +390,49 @@ impl TakOngoingGame {
 
     fn start_or_update_clock(&mut self, now: Instant, player: TakPlayer) {
         self.maybe_apply_elapsed(now, player);
+        let elapsed = std::mem::take(&mut self.clock.turn_elapsed);
         self.clock_update_policy
-            .end_turn(&self.base, &mut self.clock, player);
+            .end_turn(&self.base, &mut self.clock, player, elapsed);
         self.clock.is_ticking = true;
     }
 
+    pub fn is_clock_paused(&self, player: TakPlayer) -> bool {
+        self.clock.is_paused(player)
+    }
+
+    /// Pauses or resumes the clock of a player, no time is charged to them while it is paused.
+    /// Returns whether the state changed.
+    pub fn set_clock_paused(&mut self, player: TakPlayer, paused: bool, now: Instant) -> bool {
+        if self.clock.is_paused(player) == paused {
+            return false;
+        }
+        if self.base.current_player == player {
+            // the time used so far still counts towards the ply's think time and delay
+            self.maybe_apply_elapsed(now, player);
+        }
+        match player {
+            TakPlayer::White => self.clock.is_paused.0 = paused,
+            TakPlayer::Black => self.clock.is_paused.1 = paused,
+        }
+        true
+    }
+
     fn stop_clock(&mut self, now: Instant, player: TakPlayer) {
         self.maybe_apply_elapsed(now, player);
+        self.clock.turn_elapsed = Duration::ZERO;
         self.clock.is_ticking = false;
     }
 
+    /// Time the player to move has been thinking in the current turn, not counting pauses.
+    pub fn turn_think_time(&self, now: Instant) -> Duration {
+        let since_update = if self.clock.is_paused(self.base.current_player) {
+            Duration::ZERO
+        } else {
+            now.saturating_duration_since(self.clock.last_update_timestamp)
+        };
+        self.clock.turn_elapsed + since_update
+    }
+
     pub fn do_action(
         &mut self,
         action: TakAction,
@@ -236,14 +443,25 @@ impl TakOngoingGame {
         };
 
         let player = self.base.current_player;
+        let think_time = self.turn_think_time(now);
+        let remaining_before = self.get_time_remaining(player, now);
 
         match self.base.do_action(action) {
             Ok(None) => {
                 self.start_or_update_clock(now, player);
+                let remaining_after = self.get_time_remaining(player, now);
+                self.ply_times.push(TakPlyTime {
+                    think_time,
+                    time_gained: remaining_after.saturating_sub(remaining_before),
+                });
                 Ok(MaybeTimeout::Result(None))
             }
             Ok(Some(finished_base)) => {
                 self.stop_clock(now, player);
+                self.ply_times.push(TakPlyTime {
+                    think_time,
+                    time_gained: Duration::ZERO,
+                });
                 let finished_game = TakFinishedGame::from_finished_base(finished_base, self, now);
                 Ok(MaybeTimeout::Result(Some(finished_game)))
             }
@@ -260,15 +478,34 @@ impl TakOngoingGame {
         if !self.base.undo_action() {
             return MaybeTimeout::Result(false);
         }
+        self.ply_times.pop();
 
         self.start_or_update_clock(now, player); // TODO: verify that we want increment after undo
 
         MaybeTimeout::Result(true)
     }
 
-    pub fn resign_or_abandon(
+    pub fn resign(
+        &mut self,
+        player: TakPlayer,
+        now: Instant,
+    ) -> MaybeTimeout<TakFinishedGame, TakFinishedGame> {
+        self.concede(player, TakWinReason::Resignation, now)
+    }
+
+    pub fn abandon(
+        &mut self,
+        player: TakPlayer,
+        now: Instant,
+    ) -> MaybeTimeout<TakFinishedGame, TakFinishedGame> {
+        self.concede(player, TakWinReason::Abandonment, now)
+    }
+
+    /// Ends the game as a loss for `player`, unless their clock already ran out.
+    fn concede(
         &mut self,
         player: TakPlayer,
+        reason: TakWinReason,
         now: Instant,
     ) -> MaybeTimeout<TakFinishedGame, TakFinishedGame> {
         if let MaybeTimeout::Timeout(finished_game) = self.check_timeout(now) {
@@ -278,7 +515,7 @@ impl TakOngoingGame {
             now,
             TakGameResult::Win {
                 winner: player.opponent(),
-                reason: TakWinReason::Default,
+                reason,
             },
         ))
     }
@@ -295,8 +532,8 @@ impl TakOngoingGame {
             TakPlayer::White => 0,
             TakPlayer::Black => 1,
         };
-        if self.base.action_history.len() <= max_ply_index {
-            // if the player hasn't made a move yet, they can abort without penalty
+        if self.base.action_history.len() > max_ply_index {
+            // only players who haven't made a move yet can abort without penalty
             return MaybeTimeout::Result(None);
         }
         MaybeTimeout::Result(Some(self.set_game_over(now, TakGameResult::Aborted)))
@@ -340,7 +577,7 @@ impl TakOngoingGame {
 mod tests {
     use crate::{
         TakBaseGameSettings, TakDir, TakOpening, TakPos, TakRealtimeTimeControl, TakReserve,
-        TakVariant,
+        TakRules, TakVariant, ptn::game_position_from_string,
     };
 
     use super::*;
@@ -378,6 +615,47 @@ mod tests {
         }
     }
 
+    /// A standard 5x5 game with the given time settings.
+    fn game_settings(time_settings: TakTimeSettings) -> TakGameSettings {
+        TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::new(21, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings,
+        }
+    }
+
+    fn realtime(
+        contingent: u64,
+        increment: u64,
+        increment_mode: TakIncrementMode,
+    ) -> TakRealtimeTimeControl {
+        TakRealtimeTimeControl {
+            contingent: Duration::from_secs(contingent),
+            increment: Duration::from_secs(increment),
+            extra: None,
+            increment_mode,
+        }
+    }
+
+    fn place(x: i32, y: i32) -> TakAction {
+        TakAction::Place {
+            pos: TakPos::new(x, y),
+            variant: TakVariant::Flat,
+        }
+    }
+
+    /// Returns a function giving the instant `secs` seconds from now.
+    fn timeline() -> impl Fn(u64) -> Instant {
+        let start = Instant::now();
+        move |secs| start + Duration::from_secs(secs)
+    }
+
     #[test]
     fn test_reserve_constraints() {
         let now = Instant::now();
@@ -386,12 +664,15 @@ mod tests {
                 board_size: 5,
                 half_komi: 0,
                 reserve: TakReserve::new(3, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(300),
                 increment: Duration::from_secs(5),
                 extra: Some((2, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         });
 
@@ -532,12 +813,15 @@ mod tests {
                     board_size: 5,
                     half_komi,
                     reserve: TakReserve::new(2, 0),
+                    black_reserve: None,
                     opening: TakOpening::Swap,
+                    rules: TakRules::default(),
                 },
                 time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                     contingent: Duration::from_secs(300),
                     increment: Duration::from_secs(5),
                     extra: Some((2, Duration::from_secs(60))),
+                    increment_mode: TakIncrementMode::Fischer,
                 }),
             });
             do_move(
@@ -571,16 +855,19 @@ mod tests {
                     board_size: 5,
                     half_komi,
                     reserve: TakReserve::new(1, 1),
+                    black_reserve: None,
                     opening: TakOpening::Swap,
+                    rules: TakRules::default(),
                 },
                 time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                     contingent: Duration::from_secs(300),
                     increment: Duration::from_secs(5),
                     extra: Some((2, Duration::from_secs(60))),
+                    increment_mode: TakIncrementMode::Fischer,
                 }),
             });
             do_move(
-                &mut game,
+                &mut game2,
                 TakAction::Place {
                     pos: TakPos::new(0, 0),
                     variant: TakVariant::Flat,
@@ -615,12 +902,15 @@ mod tests {
                 board_size: 5,
                 half_komi: 0,
                 reserve: TakReserve::new(21, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(300),
                 increment: Duration::from_secs(5),
                 extra: Some((2, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         });
 
@@ -723,4 +1013,401 @@ mod tests {
             now,
         );
     }
+
+    #[test]
+    fn test_ply_times() {
+        let mut game = TakOngoingGame::new(game_settings(TakTimeSettings::Realtime(
+            TakRealtimeTimeControl {
+                extra: Some((2, Duration::from_secs(60))),
+                ..realtime(600, 5, TakIncrementMode::Fischer)
+            },
+        )));
+        let at = timeline();
+        do_move(&mut game, place(0, 0), at(0));
+        do_move(&mut game, place(4, 4), at(3));
+        do_move(&mut game, place(2, 2), at(10));
+        do_move(&mut game, place(2, 3), at(12));
+
+        let ply_times = game.ply_times();
+        assert_eq!(ply_times.len(), 4);
+        assert_eq!(ply_times[0].time_gained, Duration::from_secs(5));
+        assert_eq!(
+            ply_times[1..],
+            [
+                TakPlyTime {
+                    think_time: Duration::from_secs(3),
+                    time_gained: Duration::from_secs(5),
+                },
+                TakPlyTime {
+                    think_time: Duration::from_secs(7),
+                    time_gained: Duration::from_secs(65),
+                },
+                TakPlyTime {
+                    think_time: Duration::from_secs(2),
+                    time_gained: Duration::from_secs(65),
+                },
+            ]
+        );
+
+        assert!(matches!(
+            game.undo_action(at(13)),
+            MaybeTimeout::Result(true)
+        ));
+        assert_eq!(game.ply_times().len(), 3);
+
+        let MaybeTimeout::Result(finished) = game.resign(TakPlayer::Black, at(0)) else {
+            panic!("Game finished unexpectedly due to timeout");
+        };
+        assert_eq!(finished.ply_times().len(), 3);
+    }
+
+    #[test]
+    fn test_increment_modes_and_time_odds() {
+        let mut game = TakOngoingGame::new(game_settings(TakTimeSettings::RealtimeOdds(
+            realtime(60, 10, TakIncrementMode::Delay),
+            realtime(30, 10, TakIncrementMode::Bronstein),
+        )));
+        let at = timeline();
+        let remaining = |game: &TakOngoingGame, secs| {
+            let time_info = game.get_time_info(at(secs));
+            (
+                time_info.white_remaining.as_secs(),
+                time_info.black_remaining.as_secs(),
+            )
+        };
+        assert_eq!(remaining(&game, 0), (60, 30));
+
+        do_move(&mut game, place(0, 0), at(0));
+        // Bronstein: black's clock runs immediately, but a fast move is refunded.
+        assert_eq!(remaining(&game, 4), (60, 26));
+        do_move(&mut game, place(4, 4), at(4));
+        assert_eq!(remaining(&game, 4), (60, 30));
+        // Delay: white's clock only starts running after ten seconds.
+        assert_eq!(remaining(&game, 10), (60, 30));
+        assert_eq!(remaining(&game, 19), (55, 30));
+        do_move(&mut game, place(2, 2), at(19));
+        assert_eq!(remaining(&game, 19), (55, 30));
+        // Bronstein refunds at most the increment.
+        do_move(&mut game, place(2, 3), at(44));
+        assert_eq!(remaining(&game, 44), (55, 15));
+        // White's 55s only start running after the delay.
+        assert!(matches!(
+            game.check_timeout(at(44 + 64)),
+            MaybeTimeout::Result(())
+        ));
+        assert!(matches!(
+            game.check_timeout(at(44 + 65)),
+            MaybeTimeout::Timeout(_)
+        ));
+    }
+
+    #[test]
+    fn test_from_position() {
+        let now = Instant::now();
+        let settings = TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::new(21, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
+                contingent: Duration::from_secs(300),
+                increment: Duration::from_secs(5),
+                extra: None,
+                increment_mode: TakIncrementMode::Fischer,
+            }),
+        };
+        let position = game_position_from_string("x5/x5/x5/2,2,2,2,1C/1,1,21,1,x 1 6").unwrap();
+        let mut game = TakOngoingGame::from_position(settings.clone(), position).unwrap();
+        assert_eq!(game.current_player(), TakPlayer::White);
+        assert_eq!(game.base.reserves.0, TakReserve::new(17, 0));
+        assert_eq!(game.base.reserves.1, TakReserve::new(16, 1));
+
+        // opening rules no longer apply, but the white capstone is already on the board
+        assert!(
+            game.do_action(
+                TakAction::Place {
+                    pos: TakPos::new(0, 2),
+                    variant: TakVariant::Capstone,
+                },
+                now
+            )
+            .is_err()
+        );
+        do_finish_move(
+            &mut game,
+            TakAction::Place {
+                pos: TakPos::new(4, 0),
+                variant: TakVariant::Flat,
+            },
+            now,
+            TakGameResult::Win {
+                winner: TakPlayer::White,
+                reason: TakWinReason::Road,
+            },
+        );
+    }
+
+    #[test]
+    fn test_from_position_validation() {
+        let settings = TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::new(21, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
+                contingent: Duration::from_secs(300),
+                increment: Duration::from_secs(5),
+                extra: None,
+                increment_mode: TakIncrementMode::Fischer,
+            }),
+        };
+        for (tps, expected) in [
+            ("x4/x4/x4/x4 1 3", InvalidPositionReason::BoardSizeMismatch),
+            (
+                "x5/x5/x5/x5/1C,1C,x3 2 2",
+                InvalidPositionReason::NotEnoughCapstones(TakPlayer::White),
+            ),
+            (
+                "x5/x5/x5/x5/2222222222222222222222,x4 1 3",
+                InvalidPositionReason::NotEnoughPieces(TakPlayer::Black),
+            ),
+            (
+                "x5/x5/x5/x5/1,x4 2 1",
+                InvalidPositionReason::OpeningViolation,
+            ),
+            (
+                "x5/x5/x5/x5/2,x4 1 1",
+                InvalidPositionReason::OpeningViolation,
+            ),
+            (
+                "x5/x5/x5/2,2,x3/1,1,1,1,1 2 5",
+                InvalidPositionReason::GameAlreadyOver,
+            ),
+        ] {
+            let position = game_position_from_string(tps).unwrap();
+            assert_eq!(
+                TakOngoingGame::from_position(settings.clone(), position).err(),
+                Some(expected),
+                "tps: {}",
+                tps
+            );
+        }
+
+        let position = game_position_from_string("x5/x5/x5/x5/2,x4 2 1").unwrap();
+        assert!(TakOngoingGame::from_position(settings, position).is_ok());
+    }
+
+    #[test]
+    fn test_resume() {
+        let settings = game_settings(TakTimeSettings::Realtime(TakRealtimeTimeControl {
+            extra: Some((2, Duration::from_secs(60))),
+            ..realtime(600, 5, TakIncrementMode::Fischer)
+        }));
+        let at = timeline();
+        let mut game = TakOngoingGame::new(settings.clone());
+        do_move(&mut game, place(0, 0), at(0));
+        do_move(&mut game, place(4, 4), at(3));
+        do_move(&mut game, place(2, 2), at(10));
+        let time_info = game.get_time_info(at(15));
+        assert_eq!(time_info.white_remaining, Duration::from_secs(663));
+        assert_eq!(time_info.black_remaining, Duration::from_secs(597));
+
+        // the server was down between 15s and 100s
+        let mut resumed = TakOngoingGame::resume(
+            settings.clone(),
+            game.action_history().clone(),
+            game.ply_times().clone(),
+            time_info.clone(),
+            (false, false),
+            Duration::ZERO,
+            at(100),
+        )
+        .unwrap();
+        assert_eq!(resumed.current_player(), TakPlayer::Black);
+        assert_eq!(resumed.ply_times(), game.ply_times());
+        assert_eq!(resumed.get_time_info(at(100)), time_info);
+        assert_eq!(
+            resumed.get_time_info(at(102)).black_remaining,
+            Duration::from_secs(595)
+        );
+        do_move(&mut resumed, place(2, 3), at(102));
+        assert_eq!(
+            resumed.get_time_info(at(102)).black_remaining,
+            Duration::from_secs(660)
+        );
+
+        let not_started = TakOngoingGame::resume(
+            settings.clone(),
+            vec![],
+            vec![],
+            time_info.clone(),
+            (false, false),
+            Duration::ZERO,
+            at(0),
+        )
+        .unwrap();
+        assert_eq!(not_started.get_time_info(at(50)), time_info);
+
+        // a paused clock stays paused
+        let paused = TakOngoingGame::resume(
+            settings.clone(),
+            game.action_history().clone(),
+            game.ply_times().clone(),
+            time_info.clone(),
+            (false, true),
+            Duration::ZERO,
+            at(100),
+        )
+        .unwrap();
+        assert!(paused.is_clock_paused(TakPlayer::Black));
+        assert_eq!(paused.get_time_info(at(200)), time_info);
+
+        assert!(
+            TakOngoingGame::resume(
+                settings,
+                vec![place(0, 0), place(0, 0)],
+                vec![],
+                time_info,
+                (false, false),
+                Duration::ZERO,
+                at(0)
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_clock_pause() {
+        let mut game =
+            TakOngoingGame::new(game_settings(TakTimeSettings::Async(TakAsyncTimeControl {
+                contingent: Duration::from_secs(1000),
+            })));
+        let at = timeline();
+        do_move(&mut game, place(0, 0), at(0));
+
+        // pausing the player who isn't to move only takes effect on their turn
+        assert!(game.set_clock_paused(TakPlayer::White, true, at(10)));
+        assert!(!game.set_clock_paused(TakPlayer::White, true, at(10)));
+        assert!(game.is_clock_paused(TakPlayer::White));
+        assert_eq!(
+            game.get_time_info(at(100)).black_remaining,
+            Duration::from_secs(900)
+        );
+        do_move(&mut game, place(4, 4), at(100));
+        assert_eq!(
+            game.get_time_info(at(5000)).white_remaining,
+            Duration::from_secs(1000)
+        );
+        assert!(matches!(
+            game.check_timeout(at(5000)),
+            MaybeTimeout::Result(())
+        ));
+
+        assert!(game.set_clock_paused(TakPlayer::White, false, at(5000)));
+        assert_eq!(
+            game.get_time_info(at(5300)).white_remaining,
+            Duration::from_secs(700)
+        );
+
+        // time used before pausing stays used
+        assert!(game.set_clock_paused(TakPlayer::White, true, at(5300)));
+        assert_eq!(
+            game.get_time_info(at(9000)).white_remaining,
+            Duration::from_secs(700)
+        );
+        assert!(game.set_clock_paused(TakPlayer::White, false, at(9000)));
+        assert_eq!(
+            game.get_time_info(at(9100)).white_remaining,
+            Duration::from_secs(600)
+        );
+
+        // the think time matches the clock usage, no matter how often it was paused
+        do_move(&mut game, place(1, 1), at(9100));
+        assert_eq!(game.ply_times()[2].think_time, Duration::from_secs(400));
+    }
+
+    #[test]
+    fn test_delay_clock_pause() {
+        let settings = game_settings(TakTimeSettings::Realtime(realtime(
+            60,
+            10,
+            TakIncrementMode::Delay,
+        )));
+        let at = timeline();
+        let white_remaining =
+            |game: &TakOngoingGame, secs| game.get_time_info(at(secs)).white_remaining;
+        let mut game = TakOngoingGame::new(settings.clone());
+        do_move(&mut game, place(0, 0), at(0));
+        do_move(&mut game, place(4, 4), at(0));
+
+        // white used 6s of the delay before pausing, only 4s are left after resuming
+        assert!(game.set_clock_paused(TakPlayer::White, true, at(6)));
+        assert!(game.set_clock_paused(TakPlayer::White, false, at(100)));
+        assert_eq!(white_remaining(&game, 104), Duration::from_secs(60));
+        assert_eq!(white_remaining(&game, 110), Duration::from_secs(54));
+        assert!(game.set_clock_paused(TakPlayer::White, true, at(110)));
+        assert!(game.set_clock_paused(TakPlayer::White, false, at(200)));
+        assert_eq!(white_remaining(&game, 205), Duration::from_secs(49));
+
+        // a restart doesn't grant the delay again either
+        let turn_think_time = game.turn_think_time(at(205));
+        assert_eq!(turn_think_time, Duration::from_secs(21));
+        let mut resumed = TakOngoingGame::resume(
+            settings,
+            game.action_history().clone(),
+            game.ply_times().clone(),
+            game.get_time_info(at(205)),
+            (false, false),
+            turn_think_time,
+            at(300),
+        )
+        .unwrap();
+        assert_eq!(white_remaining(&resumed, 305), Duration::from_secs(44));
+        do_move(&mut resumed, place(2, 2), at(305));
+        assert_eq!(resumed.ply_times()[2].think_time, Duration::from_secs(26));
+        assert_eq!(white_remaining(&resumed, 305), Duration::from_secs(44));
+    }
+
+    #[test]
+    fn test_abort() {
+        let settings = game_settings(TakTimeSettings::Realtime(realtime(
+            300,
+            5,
+            TakIncrementMode::Fischer,
+        )));
+        let at = timeline();
+
+        let mut game = TakOngoingGame::new(settings.clone());
+        assert!(game.first_ply_wait(at(10)).is_some());
+        assert!(matches!(
+            game.abort(at(10), TakPlayer::White),
+            MaybeTimeout::Result(Some(ref finished)) if *finished.game_result() == TakGameResult::Aborted
+        ));
+
+        let mut game = TakOngoingGame::new(settings);
+        do_move(&mut game, place(0, 0), at(10));
+        assert_eq!(game.first_ply_wait(at(40)), Some(Duration::from_secs(30)));
+        assert!(matches!(
+            game.abort(at(40), TakPlayer::White),
+            MaybeTimeout::Result(None)
+        ));
+        assert!(matches!(
+            game.clone().abort(at(40), TakPlayer::Black),
+            MaybeTimeout::Result(Some(_))
+        ));
+        do_move(&mut game, place(4, 4), at(50));
+        assert_eq!(game.first_ply_wait(at(60)), None);
+        assert!(matches!(
+            game.abort(at(60), TakPlayer::Black),
+            MaybeTimeout::Result(None)
+        ));
+    }
 }
@@ -1,7 +1,13 @@
 mod base;
 mod board;
 mod game;
+mod perft;
+pub mod playtak;
+#[cfg(test)]
+mod proptests;
 pub mod ptn;
+mod symmetry;
+mod tinue;
 
 use std::time::Duration;
 
@@ -10,8 +16,11 @@ pub use base::TakOngoingBaseGame;
 pub use board::TakBoard;
 pub use game::TakFinishedGame;
 pub use game::TakOngoingGame;
+pub use symmetry::TakSymmetry;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakGameSettings {
     pub base: TakBaseGameSettings,
     pub time_settings: TakTimeSettings,
@@ -22,24 +31,74 @@ impl TakGameSettings {
         self.base.is_valid()
             && match &self.time_settings {
                 TakTimeSettings::Realtime(rt) => rt.is_valid(),
+                TakTimeSettings::RealtimeOdds(white, black) => white.is_valid() && black.is_valid(),
                 TakTimeSettings::Async(at) => at.is_valid(),
             }
     }
+
+    /// Whether games with these settings may be rated at all. Handicaps, rule variants, time
+    /// odds and delay or Bronstein clocks aren't comparable to standard games.
+    pub fn is_rateable(&self) -> bool {
+        let is_standard_clock = match &self.time_settings {
+            TakTimeSettings::Realtime(time_control) => {
+                time_control.increment_mode == TakIncrementMode::Fischer
+            }
+            TakTimeSettings::RealtimeOdds(..) => false,
+            TakTimeSettings::Async(_) => true,
+        };
+        is_standard_clock && !self.base.is_handicap() && self.base.rules.is_standard()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakTimeSettings {
     Realtime(TakRealtimeTimeControl),
+    /// Realtime clocks with separate settings for white and black, e.g. for time odds.
+    RealtimeOdds(TakRealtimeTimeControl, TakRealtimeTimeControl),
     Async(TakAsyncTimeControl),
 }
 
+impl TakTimeSettings {
+    pub fn is_realtime(&self) -> bool {
+        matches!(
+            self,
+            TakTimeSettings::Realtime(_) | TakTimeSettings::RealtimeOdds(..)
+        )
+    }
+
+    /// The realtime clock settings of both players, or `None` for async games.
+    pub fn realtime_controls(&self) -> Option<(&TakRealtimeTimeControl, &TakRealtimeTimeControl)> {
+        match self {
+            TakTimeSettings::Realtime(rt) => Some((rt, rt)),
+            TakTimeSettings::RealtimeOdds(white, black) => Some((white, black)),
+            TakTimeSettings::Async(_) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakTimeInfo {
     pub white_remaining: Duration,
     pub black_remaining: Duration,
 }
 
+/// Clock usage of a single ply.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TakPlyTime {
+    /// Wall time between the mover's turn starting and the ply being played, without the time
+    /// their clock was paused.
+    pub think_time: Duration,
+    /// Time added to the mover's clock afterwards, e.g. increment or extra time.
+    pub time_gained: Duration,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakPlayer {
     White,
     Black,
@@ -55,6 +114,8 @@ impl TakPlayer {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "camelCase"))]
 pub enum TakAction {
     Place {
         pos: TakPos,
@@ -68,6 +129,8 @@ pub enum TakAction {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakReserve {
     pub pieces: u32,
     pub capstones: u32,
@@ -107,6 +170,8 @@ impl TakReserve {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakOpening {
     Swap,
     NoSwap,
@@ -114,33 +179,131 @@ pub enum TakOpening {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakBaseGameSettings {
     pub board_size: u32,
     pub half_komi: u32,
     pub reserve: TakReserve,
+    /// Black's reserve in a handicap game, `reserve` is then only white's.
+    pub black_reserve: Option<TakReserve>,
     pub opening: TakOpening,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: TakRules,
+}
+
+/// Rule variants that deviate from the standard ruleset, which is the default.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct TakRules {
+    pub repetition: TakRepetitionRule,
+    pub flat_tiebreak: TakFlatTiebreak,
+    pub reserve_runout: TakReserveRunout,
+    /// Standing stones cannot be placed.
+    pub no_standing_stones: bool,
+}
+
+impl TakRules {
+    pub fn is_standard(&self) -> bool {
+        *self == TakRules::default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum TakRepetitionRule {
+    /// The game is drawn when a position occurs for the third time.
+    #[default]
+    ThreefoldDraw,
+    /// Repeating positions has no effect.
+    Allowed,
+}
+
+/// How a flat count with equal scores is decided.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum TakFlatTiebreak {
+    #[default]
+    Draw,
+    /// The player with more pieces left in their reserve wins.
+    Reserve,
+}
+
+/// When running out of pieces ends the game with a flat count.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum TakReserveRunout {
+    /// The game ends as soon as either reserve is empty.
+    #[default]
+    EitherEmpty,
+    /// The game goes on until both reserves are empty, or the player to move has no pieces left
+    /// and controls no stack.
+    BothEmpty,
 }
 
 impl TakBaseGameSettings {
     pub fn is_valid(&self) -> bool {
-        self.board_size >= 3 && self.board_size <= 8 && self.reserve.pieces > 0
+        self.board_size >= 3
+            && self.board_size <= 8
+            && self.reserve.pieces > 0
+            && self.black_reserve.as_ref().is_none_or(|r| r.pieces > 0)
+    }
+
+    /// The starting reserves of white and black.
+    pub fn reserves(&self) -> (TakReserve, TakReserve) {
+        let black = self.black_reserve.as_ref().unwrap_or(&self.reserve);
+        (self.reserve.clone(), black.clone())
+    }
+
+    /// Whether the players start with different reserves.
+    pub fn is_handicap(&self) -> bool {
+        self.black_reserve
+            .as_ref()
+            .is_some_and(|black| *black != self.reserve)
     }
 }
 
 impl TakRealtimeTimeControl {
     pub fn is_valid(&self) -> bool {
-        !self.contingent.is_zero() && self.extra.is_none_or(|(n, d)| n > 0 && !d.is_zero())
+        !self.contingent.is_zero()
+            && self.extra.is_none_or(|(n, d)| n > 0 && !d.is_zero())
+            && (self.increment_mode == TakIncrementMode::Fischer || !self.increment.is_zero())
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakRealtimeTimeControl {
     pub contingent: Duration,
+    /// Increment or delay per move, depending on `increment_mode`.
     pub increment: Duration,
     pub extra: Option<(u32, Duration)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub increment_mode: TakIncrementMode,
+}
+
+/// How the per-move `increment` of a realtime clock is applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum TakIncrementMode {
+    /// The full increment is added after every move.
+    #[default]
+    Fischer,
+    /// The clock only starts running once the delay has passed.
+    Delay,
+    /// The time spent on a move is given back after it, up to the increment.
+    Bronstein,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct TakAsyncTimeControl {
     pub contingent: Duration,
 }
@@ -152,13 +315,20 @@ impl TakAsyncTimeControl {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakVariant {
     Flat,
     Standing,
     Capstone,
 }
 
+impl TakVariant {
+    pub const ALL: [TakVariant; 3] = [TakVariant::Flat, TakVariant::Standing, TakVariant::Capstone];
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TakPos {
     pub x: i32,
     pub y: i32,
@@ -196,6 +366,8 @@ impl TakPos {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakDir {
     Up,
     Left,
@@ -208,6 +380,8 @@ impl TakDir {
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "camelCase"))]
 pub enum TakGameResult {
     Win {
         winner: TakPlayer,
@@ -218,16 +392,29 @@ pub enum TakGameResult {
 }
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub enum TakWinReason {
     Road,
     Flats,
+    Time,
+    Resignation,
+    Abandonment,
+    /// Decided without the game being played out, e.g. a tournament no-show. Only read from
+    /// PTN and stored results, the server doesn't end games this way.
+    Forfeit,
+    /// A win for an unspecified reason, e.g. a plain `1-0` from a PTN file.
     Default,
 }
 
 impl TakWinReason {
-    pub const ALL: [TakWinReason; 3] = [
+    pub const ALL: [TakWinReason; 7] = [
         TakWinReason::Road,
         TakWinReason::Flats,
+        TakWinReason::Time,
+        TakWinReason::Resignation,
+        TakWinReason::Abandonment,
+        TakWinReason::Forfeit,
         TakWinReason::Default,
     ];
 }
@@ -244,23 +431,159 @@ pub enum InvalidActionReason {
     InvalidMove(InvalidMoveReason),
 }
 
+impl InvalidActionReason {
+    /// A stable identifier of the reason, meant for clients and bots to match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidActionReason::OpeningViolation => "opening_violation",
+            InvalidActionReason::InvalidPlace(reason) => reason.code(),
+            InvalidActionReason::InvalidMove(reason) => reason.code(),
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidActionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidActionReason::OpeningViolation => {
+                write!(f, "the first move of each player has to place a flat stone")
+            }
+            InvalidActionReason::InvalidPlace(reason) => reason.fmt(f),
+            InvalidActionReason::InvalidMove(reason) => reason.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for InvalidActionReason {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvalidPositionReason {
+    BoardSizeMismatch,
+    EmptyStack,
+    NotEnoughPieces(TakPlayer),
+    NotEnoughCapstones(TakPlayer),
+    OpeningViolation,
+    GameAlreadyOver,
+}
+
 #[derive(Clone, Debug)]
 pub enum InvalidPlaceReason {
-    OutOfBounds,
-    PositionOccupied,
-    NoPiecesRemaining,
+    OutOfBounds(TakPos),
+    PositionOccupied(TakPos),
+    NoPiecesRemaining(TakVariant),
+    VariantNotAllowed,
+}
+
+impl InvalidPlaceReason {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidPlaceReason::OutOfBounds(_) => "place_out_of_bounds",
+            InvalidPlaceReason::PositionOccupied(_) => "square_occupied",
+            InvalidPlaceReason::NoPiecesRemaining(_) => "no_pieces_remaining",
+            InvalidPlaceReason::VariantNotAllowed => "variant_not_allowed",
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidPlaceReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidPlaceReason::OutOfBounds(pos) => {
+                write!(f, "{} is not on the board", ptn::pos_to_string(pos))
+            }
+            InvalidPlaceReason::PositionOccupied(pos) => {
+                write!(f, "{} is already occupied", ptn::pos_to_string(pos))
+            }
+            InvalidPlaceReason::NoPiecesRemaining(TakVariant::Capstone) => {
+                write!(f, "no capstones left in reserve")
+            }
+            InvalidPlaceReason::NoPiecesRemaining(_) => write!(f, "no stones left in reserve"),
+            InvalidPlaceReason::VariantNotAllowed => {
+                write!(f, "standing stones are not allowed in this game")
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum InvalidMoveReason {
-    OutOfBounds,
-    PositionEmpty,
-    InvalidNumberOfPieces,
-    CannotMoveOverStandingPieces,
-    CannotMoveOverCapstonePieces,
+    /// The square the move starts from.
+    OutOfBounds(TakPos),
+    PositionEmpty(TakPos),
+    NotPlayersStack(TakPos),
+    InvalidNumberOfPieces {
+        pos: TakPos,
+        available: u32,
+    },
+    ExceedsCarryLimit {
+        carry_limit: u32,
+    },
+    CannotMoveOverStandingPieces(TakPos),
+    CannotMoveOverCapstonePieces(TakPos),
     InvalidDropDistribution,
 }
 
+impl InvalidMoveReason {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InvalidMoveReason::OutOfBounds(_) => "move_out_of_bounds",
+            InvalidMoveReason::PositionEmpty(_) => "square_empty",
+            InvalidMoveReason::NotPlayersStack(_) => "not_players_stack",
+            InvalidMoveReason::InvalidNumberOfPieces { .. } => "not_enough_pieces",
+            InvalidMoveReason::ExceedsCarryLimit { .. } => "exceeds_carry_limit",
+            InvalidMoveReason::CannotMoveOverStandingPieces(_) => "blocked_by_standing_stone",
+            InvalidMoveReason::CannotMoveOverCapstonePieces(_) => "blocked_by_capstone",
+            InvalidMoveReason::InvalidDropDistribution => "invalid_drop_distribution",
+        }
+    }
+}
+
+impl std::fmt::Display for InvalidMoveReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidMoveReason::OutOfBounds(pos) => {
+                write!(
+                    f,
+                    "the move from {} leaves the board",
+                    ptn::pos_to_string(pos)
+                )
+            }
+            InvalidMoveReason::PositionEmpty(pos) => {
+                write!(f, "there is no stack on {}", ptn::pos_to_string(pos))
+            }
+            InvalidMoveReason::NotPlayersStack(pos) => {
+                write!(
+                    f,
+                    "the stack on {} belongs to the opponent",
+                    ptn::pos_to_string(pos)
+                )
+            }
+            InvalidMoveReason::InvalidNumberOfPieces { pos, available } => write!(
+                f,
+                "the stack on {} only has {} pieces",
+                ptn::pos_to_string(pos),
+                available
+            ),
+            InvalidMoveReason::ExceedsCarryLimit { carry_limit } => {
+                write!(f, "at most {} pieces can be carried", carry_limit)
+            }
+            InvalidMoveReason::CannotMoveOverStandingPieces(pos) => write!(
+                f,
+                "the standing stone on {} can only be flattened by a lone capstone",
+                ptn::pos_to_string(pos)
+            ),
+            InvalidMoveReason::CannotMoveOverCapstonePieces(pos) => write!(
+                f,
+                "the capstone on {} cannot be moved onto",
+                ptn::pos_to_string(pos)
+            ),
+            InvalidMoveReason::InvalidDropDistribution => {
+                write!(f, "every square passed has to receive at least one piece")
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,12 +596,15 @@ mod tests {
                 board_size: 5,
                 half_komi: 0,
                 reserve: TakReserve::new(21, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(300),
                 increment: Duration::from_secs(5),
                 extra: Some((2, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         };
         assert!(valid_settings.is_valid());
@@ -288,12 +614,15 @@ mod tests {
                 board_size: 9,
                 half_komi: 0,
                 reserve: TakReserve::new(21, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(300),
                 increment: Duration::from_secs(5),
                 extra: Some((2, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         };
         assert!(!invalid_settings.is_valid());
@@ -303,12 +632,15 @@ mod tests {
                 board_size: 5,
                 half_komi: 0,
                 reserve: TakReserve::new(21, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(0),
                 increment: Duration::from_secs(5),
                 extra: Some((2, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         };
         assert!(!invalid_time_control.is_valid());
@@ -318,15 +650,96 @@ mod tests {
                 board_size: 5,
                 half_komi: 0,
                 reserve: TakReserve::new(21, 1),
+                black_reserve: None,
                 opening: TakOpening::Swap,
+                rules: TakRules::default(),
             },
             time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
                 contingent: Duration::from_secs(300),
                 increment: Duration::from_secs(5),
                 extra: Some((0, Duration::from_secs(60))),
+                increment_mode: TakIncrementMode::Fischer,
             }),
         };
         assert!(!invalid_extra_time.is_valid());
+
+        let handicap = |black_pieces| TakGameSettings {
+            base: TakBaseGameSettings {
+                black_reserve: Some(TakReserve::new(black_pieces, 0)),
+                ..valid_settings.base.clone()
+            },
+            ..valid_settings.clone()
+        };
+        assert!(handicap(15).is_valid());
+        assert!(!handicap(0).is_valid());
+
+        let time_control = |increment, increment_mode| TakRealtimeTimeControl {
+            contingent: Duration::from_secs(300),
+            increment: Duration::from_secs(increment),
+            extra: None,
+            increment_mode,
+        };
+        let base = valid_settings.base.clone();
+        let with_time_settings = |time_settings| TakGameSettings {
+            base: base.clone(),
+            time_settings,
+        };
+        assert!(
+            with_time_settings(TakTimeSettings::Realtime(time_control(
+                3,
+                TakIncrementMode::Delay
+            )))
+            .is_valid()
+        );
+        assert!(
+            !with_time_settings(TakTimeSettings::Realtime(time_control(
+                0,
+                TakIncrementMode::Bronstein
+            )))
+            .is_valid()
+        );
+        assert!(
+            with_time_settings(TakTimeSettings::RealtimeOdds(
+                time_control(5, TakIncrementMode::Fischer),
+                time_control(0, TakIncrementMode::Fischer),
+            ))
+            .is_valid()
+        );
+        assert!(
+            !with_time_settings(TakTimeSettings::RealtimeOdds(
+                time_control(5, TakIncrementMode::Fischer),
+                time_control(0, TakIncrementMode::Delay),
+            ))
+            .is_valid()
+        );
+
+        assert!(valid_settings.is_rateable());
+        assert!(!handicap(15).is_rateable());
+        assert!(
+            !with_time_settings(TakTimeSettings::Realtime(time_control(
+                3,
+                TakIncrementMode::Delay
+            )))
+            .is_rateable()
+        );
+        assert!(
+            !with_time_settings(TakTimeSettings::RealtimeOdds(
+                time_control(5, TakIncrementMode::Fischer),
+                time_control(5, TakIncrementMode::Fischer),
+            ))
+            .is_rateable()
+        );
+        let variant = TakGameSettings {
+            base: TakBaseGameSettings {
+                rules: TakRules {
+                    no_standing_stones: true,
+                    ..TakRules::default()
+                },
+                ..valid_settings.base.clone()
+            },
+            ..valid_settings.clone()
+        };
+        assert!(!variant.is_rateable());
     }
 
     #[test]
@@ -360,4 +773,84 @@ mod tests {
         let right_pos = start_pos.offset(TakDir::Right, 1);
         assert_eq!(right_pos, TakPos::new(3, 2));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        use serde_json::json;
+
+        let action = ptn::action_from_ptn("3b2>21").unwrap();
+        let value = serde_json::to_value(&action).unwrap();
+        assert_eq!(
+            value,
+            json!({ "type": "move", "pos": { "x": 1, "y": 1 }, "dir": "right", "drops": [2, 1] })
+        );
+        assert_eq!(serde_json::from_value::<TakAction>(value).unwrap(), action);
+
+        let result = TakGameResult::Win {
+            winner: TakPlayer::Black,
+            reason: TakWinReason::Road,
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            value,
+            json!({ "type": "win", "winner": "black", "reason": "road" })
+        );
+        assert_eq!(
+            serde_json::from_value::<TakGameResult>(value).unwrap(),
+            result
+        );
+
+        let settings = TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 6,
+                half_komi: 4,
+                reserve: TakReserve::new(30, 1),
+                black_reserve: Some(TakReserve::new(28, 1)),
+                opening: TakOpening::Swap,
+                rules: TakRules {
+                    flat_tiebreak: TakFlatTiebreak::Reserve,
+                    ..TakRules::default()
+                },
+            },
+            time_settings: TakTimeSettings::RealtimeOdds(
+                TakRealtimeTimeControl {
+                    contingent: Duration::from_secs(600),
+                    increment: Duration::from_secs(5),
+                    extra: Some((35, Duration::from_secs(300))),
+                    increment_mode: TakIncrementMode::Bronstein,
+                },
+                TakRealtimeTimeControl {
+                    contingent: Duration::from_secs(300),
+                    increment: Duration::ZERO,
+                    extra: None,
+                    increment_mode: TakIncrementMode::Fischer,
+                },
+            ),
+        };
+        let json = serde_json::to_string(&settings).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TakGameSettings>(&json).unwrap(),
+            settings
+        );
+
+        let time_info = TakTimeInfo {
+            white_remaining: Duration::from_millis(1500),
+            black_remaining: Duration::ZERO,
+        };
+        let json = serde_json::to_string(&time_info).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TakTimeInfo>(&json).unwrap(),
+            time_info
+        );
+
+        let board = ptn::game_position_from_string("x3/x,12C,x/2S,x2 1 3")
+            .unwrap()
+            .board;
+        let json = serde_json::to_string(&board).unwrap();
+        let parsed = serde_json::from_str::<TakBoard>(&json).unwrap();
+        assert_eq!(parsed.stacks(), board.stacks());
+        assert_eq!(parsed.hash(), board.hash());
+        assert!(serde_json::from_str::<TakBoard>(r#"{"size":3,"stacks":[null]}"#).is_err());
+    }
 }
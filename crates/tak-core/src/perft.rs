@@ -0,0 +1,98 @@
+use crate::TakOngoingBaseGame;
+
+impl TakOngoingBaseGame {
+    /// Counts the positions reached after exactly `depth` plies, where a finished game counts as
+    /// a single leaf. Comparing these counts against known values validates move generation.
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().count_leaves(depth)
+    }
+
+    fn count_leaves(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let actions = self.legal_actions();
+        if depth == 1 {
+            return actions.len() as u64;
+        }
+        actions
+            .into_iter()
+            .map(|action| {
+                let result = self
+                    .make_action(action)
+                    .expect("legal actions should be valid");
+                let count = match result {
+                    Some(_) => 1,
+                    None => self.count_leaves(depth - 1),
+                };
+                self.unmake_action();
+                count
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        TakBaseGameSettings, TakOpening, TakReserve, TakRules, ptn::game_position_from_string,
+    };
+
+    use super::*;
+
+    fn settings(board_size: u32, opening: TakOpening) -> TakBaseGameSettings {
+        TakBaseGameSettings {
+            board_size,
+            half_komi: 0,
+            reserve: TakReserve::from_size(board_size).unwrap(),
+            black_reserve: None,
+            opening,
+            rules: TakRules::default(),
+        }
+    }
+
+    #[test]
+    fn test_perft_initial_positions() {
+        for (size, counts) in [
+            (3, vec![9, 72, 1200]),
+            (4, vec![16, 240, 7440]),
+            (5, vec![25, 600, 43320]),
+            (6, vec![36, 1260, 132720]),
+            (7, vec![49, 2352]),
+            (8, vec![64, 4032]),
+        ] {
+            let game = TakOngoingBaseGame::new(settings(size, TakOpening::Swap));
+            for (depth, count) in counts.into_iter().enumerate() {
+                assert_eq!(game.perft(depth as u32 + 1), count, "size {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_perft_positions() {
+        for (size, tps, counts) in [
+            (
+                5,
+                "2,x4/x,2,x3/1,2S,1C,x2/x,1,x3/1,x4 2 5",
+                vec![62, 2794, 154498],
+            ),
+            (5, "2,2,x3/x5/x5/x4,1/1,1,1,x2 1 5", vec![68, 4051]),
+            (
+                5,
+                "x3,12,2S/x,22S,22C,11,21/121,212,12,1121C,1212S/21S,1,21,211S,12S/x,21S,2,x2 1 26",
+                vec![91, 5632],
+            ),
+            (6, "x6/x6/x2,1,2,x2/x2,2C,1C,x2/x6/x6 1 4", vec![70, 4773]),
+        ] {
+            let game = TakOngoingBaseGame::from_position(
+                settings(size, TakOpening::Swap),
+                game_position_from_string(tps).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(game.perft(0), 1);
+            for (depth, count) in counts.into_iter().enumerate() {
+                assert_eq!(game.perft(depth as u32 + 1), count, "tps: {tps}");
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    TakAction, TakDir, TakGameResult, TakPlayer, TakPos, TakVariant, TakWinReason,
+    ptn::{game_result_from_string, game_result_to_string},
+};
+
+/// Converts an action to the notation of the legacy PlayTak server protocol, which writes a
+/// placement as `P <square> [W|C]` and a move as `M <from> <to> <drops...>`.
+pub fn action_to_playtak(action: &TakAction, size: u32) -> Option<String> {
+    match action {
+        TakAction::Place { pos, variant } => {
+            let square = pos_to_playtak(pos, size)?;
+            Some(match variant {
+                TakVariant::Flat => format!("P {square}"),
+                TakVariant::Standing => format!("P {square} W"),
+                TakVariant::Capstone => format!("P {square} C"),
+            })
+        }
+        TakAction::Move { pos, dir, drops } => {
+            if drops.is_empty() {
+                return None;
+            }
+            let from = pos_to_playtak(pos, size)?;
+            let to = pos_to_playtak(&pos.offset(*dir, drops.len() as i32), size)?;
+            let drops = drops
+                .iter()
+                .map(|drop| drop.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(format!("M {from} {to} {drops}"))
+        }
+    }
+}
+
+pub fn action_from_playtak(s: &str, size: u32) -> Option<TakAction> {
+    let parts = s.split_whitespace().collect::<Vec<_>>();
+    match parts.as_slice() {
+        ["P", square, rest @ ..] => {
+            let variant = match rest {
+                [] => TakVariant::Flat,
+                ["W"] => TakVariant::Standing,
+                ["C"] => TakVariant::Capstone,
+                _ => return None,
+            };
+            Some(TakAction::Place {
+                pos: pos_from_playtak(square, size)?,
+                variant,
+            })
+        }
+        ["M", from, to, drops @ ..] => {
+            let pos = pos_from_playtak(from, size)?;
+            let to = pos_from_playtak(to, size)?;
+            let drops = drops
+                .iter()
+                .map(|drop| drop.parse::<u32>().ok().filter(|&drop| drop > 0))
+                .collect::<Option<Vec<_>>>()?;
+            let dir = TakDir::ALL
+                .into_iter()
+                .find(|&dir| pos.offset(dir, drops.len() as i32) == to)?;
+            Some(TakAction::Move { pos, dir, drops })
+        }
+        _ => None,
+    }
+}
+
+/// PlayTak only knows road (`R`) and flat (`F`) wins, every other win is written as `1-0` or
+/// `0-1`.
+pub fn game_result_to_playtak(game_result: &TakGameResult) -> String {
+    match game_result {
+        TakGameResult::Win { winner, reason } => {
+            let letter = match reason {
+                TakWinReason::Road => "R",
+                TakWinReason::Flats => "F",
+                _ => "1",
+            };
+            match winner {
+                TakPlayer::White => format!("{letter}-0"),
+                TakPlayer::Black => format!("0-{letter}"),
+            }
+        }
+        TakGameResult::Draw => "1/2-1/2".to_string(),
+        TakGameResult::Aborted => "0-0".to_string(),
+    }
+}
+
+/// Wins written as `1-0` or `0-1` are read with [`TakWinReason::Default`] since the actual
+/// reason was not recorded.
+pub fn game_result_from_playtak(s: &str) -> Option<TakGameResult> {
+    let result = game_result_from_string(s.trim())?;
+    match &result {
+        TakGameResult::Win { reason, .. }
+            if !matches!(
+                reason,
+                TakWinReason::Road | TakWinReason::Flats | TakWinReason::Default
+            ) =>
+        {
+            None
+        }
+        _ => Some(result),
+    }
+}
+
+pub fn playtak_result_to_ptn(s: &str) -> Option<String> {
+    game_result_from_playtak(s).map(|result| game_result_to_string(&result))
+}
+
+pub fn ptn_result_to_playtak(s: &str) -> Option<String> {
+    game_result_from_string(s.trim()).map(|result| game_result_to_playtak(&result))
+}
+
+fn pos_to_playtak(pos: &TakPos, size: u32) -> Option<String> {
+    if !pos.is_valid(size) {
+        return None;
+    }
+    Some(format!("{}{}", (b'A' + pos.x as u8) as char, pos.y + 1))
+}
+
+fn pos_from_playtak(s: &str, size: u32) -> Option<TakPos> {
+    let mut chars = s.chars();
+    let file = chars.next()?.to_ascii_uppercase();
+    if !file.is_ascii_uppercase() {
+        return None;
+    }
+    let rank = chars.as_str().parse::<i32>().ok()?;
+    let pos = TakPos::new(file as i32 - 'A' as i32, rank - 1);
+    pos.is_valid(size).then_some(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ptn::action_from_ptn;
+
+    use super::*;
+
+    #[test]
+    fn test_playtak_actions() {
+        for (ptn, playtak) in [
+            ("a1", "P A1"),
+            ("Se5", "P E5 W"),
+            ("Cc3", "P C3 C"),
+            ("3a1+12", "M A1 A3 1 2"),
+            ("e3<", "M E3 D3 1"),
+            ("5c5-212", "M C5 C2 2 1 2"),
+        ] {
+            let action = action_from_ptn(ptn).unwrap();
+            assert_eq!(action_to_playtak(&action, 5).as_deref(), Some(playtak));
+            assert_eq!(action_from_playtak(playtak, 5), Some(action));
+        }
+
+        assert_eq!(action_to_playtak(&action_from_ptn("f1").unwrap(), 5), None);
+        assert_eq!(
+            action_to_playtak(&action_from_ptn("2a1-11").unwrap(), 5),
+            None
+        );
+        assert_eq!(action_from_playtak("P F1", 5), None);
+        assert_eq!(action_from_playtak("P A1 S", 5), None);
+        assert_eq!(action_from_playtak("M A1 B2 1", 5), None);
+        assert_eq!(action_from_playtak("M A1 A3 1", 5), None);
+        assert_eq!(action_from_playtak("M A1 A2 0", 5), None);
+        assert_eq!(action_from_playtak("M A1 A2", 5), None);
+    }
+
+    #[test]
+    fn test_playtak_results() {
+        for (ptn, playtak) in [
+            ("R-0", "R-0"),
+            ("0-F", "0-F"),
+            ("T-0", "1-0"),
+            ("0-S", "0-1"),
+            ("1/2-1/2", "1/2-1/2"),
+            ("0-0", "0-0"),
+        ] {
+            assert_eq!(ptn_result_to_playtak(ptn).as_deref(), Some(playtak));
+        }
+        assert_eq!(playtak_result_to_ptn("0-1").as_deref(), Some("0-1"));
+        assert_eq!(playtak_result_to_ptn("F-0").as_deref(), Some("F-0"));
+        assert_eq!(playtak_result_to_ptn("T-0"), None);
+        assert_eq!(
+            game_result_from_playtak("1-0"),
+            Some(TakGameResult::Win {
+                winner: TakPlayer::White,
+                reason: TakWinReason::Default,
+            })
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use proptest::prelude::*;
+
+use crate::{
+    TakAction, TakBaseGameSettings, TakOngoingBaseGame, TakOpening, TakPlayer, TakReserve,
+    TakRules, TakVariant,
+    ptn::{
+        Ptn, PtnHeader, action_from_ptn, action_to_ptn, game_position_from_string,
+        game_position_to_string,
+    },
+};
+
+fn settings(board_size: u32, opening: TakOpening) -> TakBaseGameSettings {
+    TakBaseGameSettings {
+        board_size,
+        half_komi: 0,
+        reserve: TakReserve::from_size(board_size).unwrap(),
+        black_reserve: None,
+        opening,
+        rules: TakRules::default(),
+    }
+}
+
+fn opening() -> impl Strategy<Value = TakOpening> {
+    prop_oneof![
+        Just(TakOpening::Swap),
+        Just(TakOpening::NoSwap),
+        Just(TakOpening::DoubleStack),
+    ]
+}
+
+/// Plays the legal action picked by each choice until the game ends or the choices run out,
+/// calling `check` before the first and after every action.
+fn playout(
+    game: &mut TakOngoingBaseGame,
+    choices: &[usize],
+    mut check: impl FnMut(&TakOngoingBaseGame),
+) -> Vec<TakAction> {
+    let mut actions = Vec::new();
+    check(game);
+    for choice in choices {
+        let legal = game.legal_actions();
+        assert!(
+            !legal.is_empty(),
+            "ongoing games always have a legal action"
+        );
+        let action = legal[choice % legal.len()].clone();
+        actions.push(action.clone());
+        let result = game.make_action(action).unwrap();
+        check(game);
+        if result.is_some() {
+            break;
+        }
+    }
+    actions
+}
+
+/// The pieces of a player on the board, and how many of them are capstones.
+fn pieces_on_board(game: &TakOngoingBaseGame, player: TakPlayer) -> (u32, u32) {
+    game.board
+        .stacks()
+        .iter()
+        .flatten()
+        .fold((0, 0), |(pieces, capstones), stack| {
+            let owned = stack.composition.iter().filter(|p| **p == player).count() as u32;
+            let is_capstone =
+                stack.variant == TakVariant::Capstone && stack.composition.last() == Some(&player);
+            (pieces + owned, capstones + is_capstone as u32)
+        })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_pieces_are_conserved(
+        size in 3u32..=8,
+        opening in opening(),
+        choices in prop::collection::vec(any::<usize>(), 0..120),
+    ) {
+        let settings = settings(size, opening);
+        let initial = settings.reserve.clone();
+        let mut game = TakOngoingBaseGame::new(settings);
+        playout(&mut game, &choices, |game| {
+            for (player, reserve) in [
+                (TakPlayer::White, &game.reserves.0),
+                (TakPlayer::Black, &game.reserves.1),
+            ] {
+                let (pieces, capstones) = pieces_on_board(game, player);
+                assert_eq!(
+                    pieces + reserve.pieces + reserve.capstones,
+                    initial.pieces + initial.capstones
+                );
+                assert_eq!(capstones + reserve.capstones, initial.capstones);
+            }
+            assert_eq!(game.board.hash(), game.board.compute_hash());
+        });
+    }
+
+    #[test]
+    fn test_undo_restores_state(
+        size in 3u32..=8,
+        opening in opening(),
+        choices in prop::collection::vec(any::<usize>(), 0..120),
+    ) {
+        let mut game = TakOngoingBaseGame::new(settings(size, opening));
+        let mut snapshots = Vec::new();
+        playout(&mut game, &choices, |game| {
+            snapshots.push((
+                game.board.stacks().to_vec(),
+                game.position_hash(),
+                game.reserves.clone(),
+                game.current_player,
+                game.board_hash_history.clone(),
+            ));
+        });
+        snapshots.pop();
+        while let Some((stacks, hash, reserves, current_player, history)) = snapshots.pop() {
+            prop_assert!(game.undo_action());
+            prop_assert_eq!(game.board.stacks(), stacks);
+            prop_assert_eq!(game.position_hash(), hash);
+            prop_assert_eq!(&game.reserves, &reserves);
+            prop_assert_eq!(game.current_player, current_player);
+            prop_assert_eq!(&game.board_hash_history, &history);
+        }
+        prop_assert!(!game.undo_action());
+    }
+
+    #[test]
+    fn test_ptn_and_tps_round_trip(
+        size in 3u32..=8,
+        opening in opening(),
+        choices in prop::collection::vec(any::<usize>(), 0..120),
+    ) {
+        let mut game = TakOngoingBaseGame::new(settings(size, opening));
+        let actions = playout(&mut game, &choices, |game| {
+            let tps = game_position_to_string(&game.position());
+            let position = game_position_from_string(&tps).unwrap();
+            assert_eq!(position.board.stacks(), game.board.stacks());
+            assert_eq!(position.ply_index, game.ply_index());
+            assert_eq!(game_position_to_string(&position), tps);
+        });
+        for action in &actions {
+            prop_assert_eq!(action_from_ptn(&action_to_ptn(action)), Some(action.clone()));
+        }
+        let ptn = Ptn::new(vec![PtnHeader::Size(size)], actions);
+        prop_assert_eq!(Ptn::parse(&ptn.to_string()).unwrap(), ptn);
+    }
+}
@@ -1,19 +1,34 @@
-use std::time::Duration;
+use std::{str::FromStr, time::Duration};
 
 use crate::{
-    TakAction, TakBoard, TakDir, TakGameResult, TakGameSettings, TakPlayer, TakPos,
-    TakRealtimeTimeControl, TakReserve, TakTimeSettings, TakVariant, TakWinReason, board::TakStack,
+    TakAction, TakAsyncTimeControl, TakBaseGameSettings, TakBoard, TakDir, TakFlatTiebreak,
+    TakGameResult, TakGameSettings, TakIncrementMode, TakOpening, TakPlayer, TakPos,
+    TakRealtimeTimeControl, TakRepetitionRule, TakReserve, TakReserveRunout, TakRules, TakTimeInfo,
+    TakTimeSettings, TakVariant, TakWinReason, board::TakStack,
 };
 
+mod tree;
+
+pub use tree::{PtnEvaluation, PtnNode, PtnTakMark, PtnTree};
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum PtnHeader {
     Size(u32),
     HalfKomi(u32),
     Player(TakPlayer, String),
     Rating(TakPlayer, f64),
     TimeControl(TakRealtimeTimeControl),
+    /// White's and black's clock settings of a time odds game.
+    TimeControlOdds(TakRealtimeTimeControl, TakRealtimeTimeControl),
+    AsyncTimeControl(TakAsyncTimeControl),
     Date(chrono::DateTime<chrono::Utc>),
     Result(TakGameResult),
     Reserve(TakReserve),
+    /// Black's reserve in a handicap game, written as `Flats2`/`Caps2`.
+    BlackReserve(TakReserve),
+    /// Non-standard rule variants as space separated flags, e.g. `no-standing`.
+    Rules(TakRules),
+    Tag(String, String),
 }
 
 impl PtnHeader {
@@ -44,59 +59,144 @@ impl PtnHeader {
 
         out
     }
+    /// Like [`PtnHeader::timer_info`], but writes a delay as `d<secs>` and a Bronstein
+    /// increment as `b<secs>` instead of the Fischer `+<secs>`.
+    pub fn realtime_timer_info(tc: &TakRealtimeTimeControl) -> String {
+        let mut out = PtnHeader::timer_info(tc.contingent, Duration::ZERO);
+        if !tc.increment.is_zero() {
+            let prefix = match tc.increment_mode {
+                TakIncrementMode::Fischer => '+',
+                TakIncrementMode::Delay => 'd',
+                TakIncrementMode::Bronstein => 'b',
+            };
+            out.push_str(&format!(" {prefix}{}", tc.increment.as_secs()));
+        }
+        out
+    }
+    /// Inverse of [`PtnHeader::realtime_timer_info`], accepting `[[h:]m:]s [+|d|b<inc>]`.
+    /// The one-shot extra time is not part of the clock header and is always `None`.
+    pub fn timer_info_from_string(s: &str) -> Option<TakRealtimeTimeControl> {
+        let mut parts = s.split_whitespace();
+        let mut total_secs: u64 = 0;
+        let time_parts = parts.next()?.split(':').collect::<Vec<_>>();
+        if time_parts.len() > 3 {
+            return None;
+        }
+        for part in time_parts {
+            total_secs = total_secs * 60 + part.parse::<u64>().ok()?;
+        }
+        let (increment, increment_mode) = match parts.next() {
+            Some(inc) => {
+                let increment_mode = match inc.chars().next()? {
+                    '+' => TakIncrementMode::Fischer,
+                    'd' => TakIncrementMode::Delay,
+                    'b' => TakIncrementMode::Bronstein,
+                    _ => return None,
+                };
+                (inc[1..].parse::<u64>().ok()?, increment_mode)
+            }
+            None => (0, TakIncrementMode::Fischer),
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(TakRealtimeTimeControl {
+            contingent: Duration::from_secs(total_secs),
+            increment: Duration::from_secs(increment),
+            extra: None,
+            increment_mode,
+        })
+    }
     pub fn to_header_string(&self) -> String {
+        let player_number = |player: &TakPlayer| match player {
+            TakPlayer::White => 1,
+            TakPlayer::Black => 2,
+        };
         match self {
-            PtnHeader::Size(size) => format!("[Size \"{size}\"]"),
-            PtnHeader::Player(player, name) => format!(
-                "[Player{} \"{name}\"]",
-                match player {
-                    TakPlayer::White => 1,
-                    TakPlayer::Black => 2,
-                }
+            PtnHeader::Size(size) => tag_pair("Size", &size.to_string()),
+            PtnHeader::Player(player, name) => {
+                tag_pair(&format!("Player{}", player_number(player)), name)
+            }
+            PtnHeader::Rating(player, rating) => tag_pair(
+                &format!("Rating{}", player_number(player)),
+                &rating.to_string(),
             ),
-            PtnHeader::Rating(player, rating) => format!(
-                "[Rating{} \"{rating}\"]",
-                match player {
-                    TakPlayer::White => 1,
-                    TakPlayer::Black => 2,
-                }
+            PtnHeader::TimeControl(tc) => tag_pair("Clock", &PtnHeader::realtime_timer_info(tc)),
+            PtnHeader::TimeControlOdds(white, black) => tag_pair(
+                "Clock",
+                &format!(
+                    "{} / {}",
+                    PtnHeader::realtime_timer_info(white),
+                    PtnHeader::realtime_timer_info(black)
+                ),
             ),
-            PtnHeader::TimeControl(tc) => format!(
-                "[Clock \"{}\"]",
-                PtnHeader::timer_info(tc.contingent, tc.increment)
+            PtnHeader::AsyncTimeControl(tc) => tag_pair(
+                "Clock",
+                &format!(
+                    "async {}",
+                    PtnHeader::timer_info(tc.contingent, Duration::ZERO)
+                ),
             ),
             PtnHeader::Date(date) => format!(
-                "[Date \"{}\"]\n[Time \"{}\"]",
-                date.format("%Y.%m.%d"),
-                date.format("%H:%M:%S")
+                "{}\n{}",
+                tag_pair("Date", &date.format("%Y.%m.%d").to_string()),
+                tag_pair("Time", &date.format("%H:%M:%S").to_string())
             ),
-            PtnHeader::Result(result) => {
-                format!("[Result \"{}\"]", game_result_to_string(result))
-            }
+            PtnHeader::Result(result) => tag_pair("Result", &game_result_to_string(result)),
             PtnHeader::Reserve(reserve) => format!(
-                "[Flats \"{}\"]\n[Caps \"{}\"]",
-                reserve.pieces, reserve.capstones
+                "{}\n{}",
+                tag_pair("Flats", &reserve.pieces.to_string()),
+                tag_pair("Caps", &reserve.capstones.to_string())
             ),
+            PtnHeader::BlackReserve(reserve) => format!(
+                "{}\n{}",
+                tag_pair("Flats2", &reserve.pieces.to_string()),
+                tag_pair("Caps2", &reserve.capstones.to_string())
+            ),
+            PtnHeader::Rules(rules) => tag_pair("Rules", &rules_to_string(rules)),
             PtnHeader::HalfKomi(half_komi) => {
                 if half_komi % 2 == 0 {
-                    format!("[Komi \"{}\"]", half_komi / 2)
+                    tag_pair("Komi", &(half_komi / 2).to_string())
                 } else {
-                    format!("[Komi \"{}.5\"]", half_komi / 2)
+                    tag_pair("Komi", &format!("{}.5", half_komi / 2))
                 }
             }
+            PtnHeader::Tag(name, value) => tag_pair(name, value),
         }
     }
 }
 
+/// Writes a tag pair, escaping the value so that it can be read back by the scanner.
+fn tag_pair(name: &str, value: &str) -> String {
+    format!(
+        "[{name} \"{}\"]",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Ptn {
     pub headers: Vec<PtnHeader>,
     pub moves: Vec<TakAction>,
+    /// Remaining time of both players after each move, written as `{clk <white> <black>}`
+    /// comments. Either empty or as long as `moves`.
+    pub clocks: Vec<Option<TakTimeInfo>>,
 }
 
 impl Ptn {
     pub fn new(headers: Vec<PtnHeader>, moves: Vec<TakAction>) -> Self {
-        Self { headers, moves }
+        Self {
+            headers,
+            moves,
+            clocks: Vec::new(),
+        }
     }
+
+    pub fn with_clocks(mut self, clocks: Vec<Option<TakTimeInfo>>) -> Self {
+        self.clocks = clocks;
+        self
+    }
+
     pub fn to_string(&self) -> String {
         let mut out = String::new();
         for header in &self.headers {
@@ -105,29 +205,361 @@ impl Ptn {
         }
         out.push('\n');
         let mut ptn_moves: Vec<String> = Vec::new();
-        for action in &self.moves {
-            ptn_moves.push(action_to_ptn(action));
+        for (i, action) in self.moves.iter().enumerate() {
+            let mut ptn_move = action_to_ptn(action);
+            if let Some(Some(time_info)) = self.clocks.get(i) {
+                ptn_move.push_str(&format!(" {{{}}}", clock_comment(time_info)));
+            }
+            ptn_moves.push(ptn_move);
         }
         let pairs = ptn_moves
             .chunks(2)
-            .map(|chunk| match chunk {
-                [first, second] => format!(
-                    "{}. {} {}",
-                    (ptn_moves.iter().position(|m| m == first).unwrap() / 2) + 1,
-                    first,
-                    second
-                ),
-                [first] => format!(
-                    "{}. {}",
-                    (ptn_moves.iter().position(|m| m == first).unwrap() / 2) + 1,
-                    first
-                ),
+            .enumerate()
+            .map(|(i, chunk)| match chunk {
+                [first, second] => format!("{}. {} {}", i + 1, first, second),
+                [first] => format!("{}. {}", i + 1, first),
                 _ => "".to_string(),
             })
             .collect::<Vec<String>>();
         out.push_str(&pairs.join("\n"));
         out
     }
+
+    /// Parses a complete PTN game: tag pairs, move numbers, moves (optionally
+    /// annotated with `'`, `"`, `!`, `?` or `*`), `{...}` comments and the result token.
+    /// A result token in the move text is added as a `Result` header if none is present.
+    /// `{clk ...}` comments following a move are read into [`Ptn::clocks`]. Variations are
+    /// skipped, use [`PtnTree::parse`] to keep them.
+    pub fn parse(s: &str) -> Result<Self, PtnParseError> {
+        PtnTree::parse(s).map(|tree| tree.main_line())
+    }
+}
+
+impl FromStr for Ptn {
+    type Err = PtnParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ptn::parse(s)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PtnParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: PtnParseErrorKind,
+}
+
+impl PtnParseError {
+    fn new(line: usize, column: usize, kind: PtnParseErrorKind) -> Self {
+        Self { line, column, kind }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PtnParseErrorKind {
+    MalformedTag,
+    InvalidTagValue { name: String, value: String },
+    InvalidMoveNumber(String),
+    InvalidMove(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+    VariationTooDeep,
+    InvalidClock(String),
+    UnexpectedToken(String),
+}
+
+impl std::fmt::Display for PtnParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PtnParseErrorKind::MalformedTag => write!(f, "malformed tag pair"),
+            PtnParseErrorKind::InvalidTagValue { name, value } => {
+                write!(f, "invalid value \"{}\" for tag {}", value, name)
+            }
+            PtnParseErrorKind::InvalidMoveNumber(token) => {
+                write!(f, "unexpected move number \"{}\"", token)
+            }
+            PtnParseErrorKind::InvalidMove(token) => write!(f, "invalid move \"{}\"", token),
+            PtnParseErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            PtnParseErrorKind::UnterminatedVariation => write!(f, "unterminated variation"),
+            PtnParseErrorKind::VariationTooDeep => write!(f, "variations nested too deeply"),
+            PtnParseErrorKind::InvalidClock(comment) => {
+                write!(f, "invalid clock comment \"{}\"", comment)
+            }
+            PtnParseErrorKind::UnexpectedToken(token) => {
+                write!(f, "unexpected token \"{}\"", token)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PtnParseError {}
+
+struct PtnTag {
+    name: String,
+    value: String,
+    line: usize,
+    column: usize,
+}
+
+impl PtnTag {
+    fn invalid_value(&self) -> PtnParseError {
+        PtnParseError::new(
+            self.line,
+            self.column,
+            PtnParseErrorKind::InvalidTagValue {
+                name: self.name.clone(),
+                value: self.value.clone(),
+            },
+        )
+    }
+
+    fn parse_value<T: FromStr>(&self) -> Result<T, PtnParseError> {
+        self.value
+            .trim()
+            .parse::<T>()
+            .map_err(|_| self.invalid_value())
+    }
+}
+
+struct PtnScanner {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+impl PtnScanner {
+    fn new(s: &str) -> Self {
+        Self {
+            chars: s.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn skip_inline_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace() && c != '\n') {
+            self.next();
+        }
+    }
+
+    fn read_token(&mut self) -> String {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, '{' | '[' | '(' | ')') {
+                break;
+            }
+            token.push(c);
+            self.next();
+        }
+        token
+    }
+
+    fn read_comment(&mut self) -> Result<String, PtnParseError> {
+        let (line, column) = self.position();
+        self.next();
+        let mut comment = String::new();
+        loop {
+            match self.next() {
+                Some('}') => return Ok(comment),
+                Some('\\') => match self.next() {
+                    Some(c) => comment.push(c),
+                    None => break,
+                },
+                Some(c) => comment.push(c),
+                None => break,
+            }
+        }
+        Err(PtnParseError::new(
+            line,
+            column,
+            PtnParseErrorKind::UnterminatedComment,
+        ))
+    }
+
+    fn read_tag(&mut self) -> Result<PtnTag, PtnParseError> {
+        let (line, column) = self.position();
+        let malformed = || PtnParseError::new(line, column, PtnParseErrorKind::MalformedTag);
+        self.next();
+        self.skip_inline_whitespace();
+        let mut name = String::new();
+        while let Some(c) = self.peek()
+            && (c.is_ascii_alphanumeric() || c == '_')
+        {
+            name.push(c);
+            self.next();
+        }
+        if name.is_empty() {
+            return Err(malformed());
+        }
+        self.skip_inline_whitespace();
+        if self.next() != Some('"') {
+            return Err(malformed());
+        }
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    Some(c) if c != '\n' => value.push(c),
+                    _ => return Err(malformed()),
+                },
+                Some('\n') | None => return Err(malformed()),
+                Some(c) => value.push(c),
+            }
+        }
+        self.skip_inline_whitespace();
+        if self.next() != Some(']') {
+            return Err(malformed());
+        }
+        Ok(PtnTag {
+            name,
+            value,
+            line,
+            column,
+        })
+    }
+}
+
+fn tags_to_ptn_headers(tags: Vec<PtnTag>) -> Result<Vec<PtnHeader>, PtnParseError> {
+    let find_tag = |name: &str| tags.iter().find(|t| t.name == name);
+    let mut headers = Vec::new();
+    for tag in &tags {
+        let header = match tag.name.as_str() {
+            "Size" => PtnHeader::Size(tag.parse_value()?),
+            "Komi" => PtnHeader::HalfKomi(
+                parse_half_komi(tag.value.trim()).ok_or_else(|| tag.invalid_value())?,
+            ),
+            "Player1" => PtnHeader::Player(TakPlayer::White, tag.value.clone()),
+            "Player2" => PtnHeader::Player(TakPlayer::Black, tag.value.clone()),
+            "Rating1" => PtnHeader::Rating(TakPlayer::White, tag.parse_value()?),
+            "Rating2" => PtnHeader::Rating(TakPlayer::Black, tag.parse_value()?),
+            "Clock" => match tag.value.trim().strip_prefix("async ") {
+                Some(contingent) => {
+                    let tc = PtnHeader::timer_info_from_string(contingent)
+                        .filter(|tc| tc.increment.is_zero())
+                        .ok_or_else(|| tag.invalid_value())?;
+                    PtnHeader::AsyncTimeControl(TakAsyncTimeControl {
+                        contingent: tc.contingent,
+                    })
+                }
+                None => match tag.value.split_once('/') {
+                    Some((white, black)) => PtnHeader::TimeControlOdds(
+                        PtnHeader::timer_info_from_string(white)
+                            .ok_or_else(|| tag.invalid_value())?,
+                        PtnHeader::timer_info_from_string(black)
+                            .ok_or_else(|| tag.invalid_value())?,
+                    ),
+                    None => PtnHeader::TimeControl(
+                        PtnHeader::timer_info_from_string(&tag.value)
+                            .ok_or_else(|| tag.invalid_value())?,
+                    ),
+                },
+            },
+            "Rules" => {
+                PtnHeader::Rules(rules_from_string(&tag.value).ok_or_else(|| tag.invalid_value())?)
+            }
+            "Result" => PtnHeader::Result(
+                game_result_from_string(tag.value.trim()).ok_or_else(|| tag.invalid_value())?,
+            ),
+            "Date" => {
+                let date = chrono::NaiveDate::parse_from_str(tag.value.trim(), "%Y.%m.%d")
+                    .map_err(|_| tag.invalid_value())?;
+                let time = match find_tag("Time") {
+                    Some(time_tag) => {
+                        chrono::NaiveTime::parse_from_str(time_tag.value.trim(), "%H:%M:%S")
+                            .map_err(|_| time_tag.invalid_value())?
+                    }
+                    None => chrono::NaiveTime::MIN,
+                };
+                PtnHeader::Date(date.and_time(time).and_utc())
+            }
+            "Time" if find_tag("Date").is_some() => continue,
+            "Flats" | "Caps" => {
+                if headers.iter().any(|h| matches!(h, PtnHeader::Reserve(_))) {
+                    continue;
+                }
+                let default_reserve = find_tag("Size")
+                    .and_then(|t| t.value.trim().parse::<u32>().ok())
+                    .and_then(TakReserve::from_size)
+                    .unwrap_or(TakReserve::new(0, 0));
+                let pieces = match find_tag("Flats") {
+                    Some(t) => t.parse_value()?,
+                    None => default_reserve.pieces,
+                };
+                let capstones = match find_tag("Caps") {
+                    Some(t) => t.parse_value()?,
+                    None => default_reserve.capstones,
+                };
+                PtnHeader::Reserve(TakReserve::new(pieces, capstones))
+            }
+            "Flats2" | "Caps2" => {
+                if headers
+                    .iter()
+                    .any(|h| matches!(h, PtnHeader::BlackReserve(_)))
+                {
+                    continue;
+                }
+                let pieces = find_tag("Flats2").or(find_tag("Flats"));
+                let capstones = find_tag("Caps2").or(find_tag("Caps"));
+                let default_reserve = find_tag("Size")
+                    .and_then(|t| t.value.trim().parse::<u32>().ok())
+                    .and_then(TakReserve::from_size)
+                    .unwrap_or(TakReserve::new(0, 0));
+                PtnHeader::BlackReserve(TakReserve::new(
+                    match pieces {
+                        Some(t) => t.parse_value()?,
+                        None => default_reserve.pieces,
+                    },
+                    match capstones {
+                        Some(t) => t.parse_value()?,
+                        None => default_reserve.capstones,
+                    },
+                ))
+            }
+            _ => PtnHeader::Tag(tag.name.clone(), tag.value.clone()),
+        };
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
+fn parse_half_komi(s: &str) -> Option<u32> {
+    match s.split_once('.') {
+        Some((whole, "5")) => Some(whole.parse::<u32>().ok()? * 2 + 1),
+        Some((whole, "0")) | Some((whole, "")) => Some(whole.parse::<u32>().ok()? * 2),
+        Some(_) => None,
+        None => Some(s.parse::<u32>().ok()? * 2),
+    }
 }
 
 pub fn game_to_ptn(
@@ -160,17 +592,191 @@ pub fn settings_to_ptn_headers(settings: &TakGameSettings) -> Vec<PtnHeader> {
     headers.push(PtnHeader::Size(settings.base.board_size));
     headers.push(PtnHeader::HalfKomi(settings.base.half_komi));
     headers.push(PtnHeader::Reserve(settings.base.reserve.clone()));
+    if let Some(black_reserve) = &settings.base.black_reserve {
+        headers.push(PtnHeader::BlackReserve(black_reserve.clone()));
+    }
+    if !settings.base.rules.is_standard() {
+        headers.push(PtnHeader::Rules(settings.base.rules.clone()));
+    }
     match &settings.time_settings {
         TakTimeSettings::Realtime(rt) => {
             headers.push(PtnHeader::TimeControl(rt.clone()));
         }
-        TakTimeSettings::Async(_) => {
-            // TODO: implement async time settings in PTN
+        TakTimeSettings::RealtimeOdds(white, black) => {
+            headers.push(PtnHeader::TimeControlOdds(white.clone(), black.clone()));
+        }
+        TakTimeSettings::Async(at) => {
+            headers.push(PtnHeader::AsyncTimeControl(at.clone()));
         }
     };
     headers
 }
 
+/// Reconstructs the base settings from PTN headers. Missing `Flats`/`Caps` fall back to the
+/// standard reserve for the board size and a missing `Komi` to zero. `Flats2`/`Caps2` give
+/// black a different reserve and a missing `Rules` header means standard rules. The opening
+/// is not recorded in PTN and is assumed to be [`TakOpening::Swap`].
+pub fn ptn_headers_to_base_settings(headers: &[PtnHeader]) -> Option<TakBaseGameSettings> {
+    let board_size = headers.iter().find_map(|h| match h {
+        PtnHeader::Size(size) => Some(*size),
+        _ => None,
+    })?;
+    let half_komi = headers
+        .iter()
+        .find_map(|h| match h {
+            PtnHeader::HalfKomi(half_komi) => Some(*half_komi),
+            _ => None,
+        })
+        .unwrap_or(0);
+    let reserve = match headers.iter().find_map(|h| match h {
+        PtnHeader::Reserve(reserve) => Some(reserve.clone()),
+        _ => None,
+    }) {
+        Some(reserve) => reserve,
+        None => TakReserve::from_size(board_size)?,
+    };
+    let black_reserve = headers.iter().find_map(|h| match h {
+        PtnHeader::BlackReserve(reserve) => Some(reserve.clone()),
+        _ => None,
+    });
+    let rules = headers
+        .iter()
+        .find_map(|h| match h {
+            PtnHeader::Rules(rules) => Some(rules.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let settings = TakBaseGameSettings {
+        board_size,
+        half_komi,
+        reserve,
+        black_reserve,
+        opening: TakOpening::Swap,
+        rules,
+    };
+    // an explicit reserve skips the size check of `TakReserve::from_size`
+    settings.is_valid().then_some(settings)
+}
+
+const NO_REPETITION_RULE: &str = "no-repetition";
+const RESERVE_TIEBREAK_RULE: &str = "reserve-tiebreak";
+const NO_STANDING_RULE: &str = "no-standing";
+const BOTH_RESERVES_RULE: &str = "both-reserves";
+
+fn rules_to_string(rules: &TakRules) -> String {
+    let mut flags = Vec::new();
+    if rules.repetition == TakRepetitionRule::Allowed {
+        flags.push(NO_REPETITION_RULE);
+    }
+    if rules.flat_tiebreak == TakFlatTiebreak::Reserve {
+        flags.push(RESERVE_TIEBREAK_RULE);
+    }
+    if rules.no_standing_stones {
+        flags.push(NO_STANDING_RULE);
+    }
+    if rules.reserve_runout == TakReserveRunout::BothEmpty {
+        flags.push(BOTH_RESERVES_RULE);
+    }
+    flags.join(" ")
+}
+
+fn rules_from_string(s: &str) -> Option<TakRules> {
+    let mut rules = TakRules::default();
+    for flag in s.split_whitespace() {
+        match flag {
+            NO_REPETITION_RULE => rules.repetition = TakRepetitionRule::Allowed,
+            RESERVE_TIEBREAK_RULE => rules.flat_tiebreak = TakFlatTiebreak::Reserve,
+            NO_STANDING_RULE => rules.no_standing_stones = true,
+            BOTH_RESERVES_RULE => rules.reserve_runout = TakReserveRunout::BothEmpty,
+            _ => return None,
+        }
+    }
+    Some(rules)
+}
+
+/// Inverse of [`settings_to_ptn_headers`]. Requires a `Clock` header for the time settings.
+pub fn ptn_headers_to_settings(headers: &[PtnHeader]) -> Option<TakGameSettings> {
+    let base = ptn_headers_to_base_settings(headers)?;
+    let time_settings = headers.iter().find_map(|h| match h {
+        PtnHeader::TimeControl(tc) => Some(TakTimeSettings::Realtime(tc.clone())),
+        PtnHeader::TimeControlOdds(white, black) => {
+            Some(TakTimeSettings::RealtimeOdds(white.clone(), black.clone()))
+        }
+        PtnHeader::AsyncTimeControl(tc) => Some(TakTimeSettings::Async(tc.clone())),
+        _ => None,
+    })?;
+    Some(TakGameSettings {
+        base,
+        time_settings,
+    })
+}
+
+fn clock_comment(time_info: &TakTimeInfo) -> String {
+    format!(
+        "clk {} {}",
+        clock_to_string(time_info.white_remaining),
+        clock_to_string(time_info.black_remaining)
+    )
+}
+
+fn clock_from_comment(s: &str) -> Option<TakTimeInfo> {
+    let mut parts = s.split_whitespace();
+    let white_remaining = clock_from_string(parts.next()?)?;
+    let black_remaining = clock_from_string(parts.next()?)?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(TakTimeInfo {
+        white_remaining,
+        black_remaining,
+    })
+}
+
+/// Formats a remaining time as `[h:]mm:ss`, followed by milliseconds if there are any.
+fn clock_to_string(time: Duration) -> String {
+    let total_secs = time.as_secs();
+    let mut out = if total_secs >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            total_secs / 3600,
+            (total_secs / 60) % 60,
+            total_secs % 60
+        )
+    } else {
+        format!("{}:{:02}", total_secs / 60, total_secs % 60)
+    };
+    let millis = time.subsec_millis();
+    if millis > 0 {
+        out.push_str(&format!(".{:03}", millis));
+    }
+    out
+}
+
+fn clock_from_string(s: &str) -> Option<Duration> {
+    let (time, millis) = match s.split_once('.') {
+        Some((time, fraction)) => {
+            if fraction.is_empty()
+                || fraction.len() > 3
+                || !fraction.chars().all(|c| c.is_ascii_digit())
+            {
+                return None;
+            }
+            let millis = fraction.parse::<u64>().ok()? * 10u64.pow(3 - fraction.len() as u32);
+            (time, millis)
+        }
+        None => (s, 0),
+    };
+    let parts = time.split(':').collect::<Vec<_>>();
+    if parts.len() > 3 {
+        return None;
+    }
+    let mut total_secs: u64 = 0;
+    for part in parts {
+        total_secs = total_secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(Duration::from_secs(total_secs) + Duration::from_millis(millis))
+}
+
 pub fn action_to_ptn(action: &TakAction) -> String {
     match action {
         TakAction::Place { pos, variant } => {
@@ -261,14 +867,13 @@ pub fn action_from_ptn(ptn: &str) -> Option<TakAction> {
     }
 }
 
+/// Results use the PTN letters `R` and `F` for road and flat wins and `1` for any other win.
+/// Wins on time, by resignation, abandonment or forfeit are written with the non-standard
+/// letters `T`, `S`, `A` and `X` so they survive a round trip.
 pub fn game_result_to_string(game_result: &TakGameResult) -> String {
     match game_result {
         TakGameResult::Win { winner, reason } => {
-            let letter = match reason {
-                TakWinReason::Road => "R",
-                TakWinReason::Flats => "F",
-                TakWinReason::Default => "1",
-            };
+            let letter = win_reason_to_letter(reason);
             match winner {
                 TakPlayer::White => format!("{}-0", letter),
                 TakPlayer::Black => format!("0-{}", letter),
@@ -281,33 +886,31 @@ pub fn game_result_to_string(game_result: &TakGameResult) -> String {
 
 pub fn game_result_from_string(s: &str) -> Option<TakGameResult> {
     match s {
-        "R-0" => Some(TakGameResult::Win {
-            winner: TakPlayer::White,
-            reason: TakWinReason::Road,
-        }),
-        "0-R" => Some(TakGameResult::Win {
-            winner: TakPlayer::Black,
-            reason: TakWinReason::Road,
-        }),
-        "F-0" => Some(TakGameResult::Win {
-            winner: TakPlayer::White,
-            reason: TakWinReason::Flats,
-        }),
-        "0-F" => Some(TakGameResult::Win {
-            winner: TakPlayer::Black,
-            reason: TakWinReason::Flats,
-        }),
-        "1-0" => Some(TakGameResult::Win {
-            winner: TakPlayer::White,
-            reason: TakWinReason::Default,
-        }),
-        "0-1" => Some(TakGameResult::Win {
-            winner: TakPlayer::Black,
-            reason: TakWinReason::Default,
-        }),
-        "1/2-1/2" => Some(TakGameResult::Draw),
-        "0-0" => Some(TakGameResult::Aborted),
-        _ => None,
+        "1/2-1/2" => return Some(TakGameResult::Draw),
+        "0-0" => return Some(TakGameResult::Aborted),
+        _ => {}
+    }
+    let (white, black) = s.split_once('-')?;
+    let (winner, letter) = match (white, black) {
+        (letter, "0") => (TakPlayer::White, letter),
+        ("0", letter) => (TakPlayer::Black, letter),
+        _ => return None,
+    };
+    let reason = TakWinReason::ALL
+        .into_iter()
+        .find(|reason| win_reason_to_letter(reason) == letter)?;
+    Some(TakGameResult::Win { winner, reason })
+}
+
+fn win_reason_to_letter(reason: &TakWinReason) -> &'static str {
+    match reason {
+        TakWinReason::Road => "R",
+        TakWinReason::Flats => "F",
+        TakWinReason::Time => "T",
+        TakWinReason::Resignation => "S",
+        TakWinReason::Abandonment => "A",
+        TakWinReason::Forfeit => "X",
+        TakWinReason::Default => "1",
     }
 }
 
@@ -323,8 +926,7 @@ pub fn game_position_to_string(position: &TakGamePosition) -> String {
     for y in (0..board.size).rev() {
         let mut empty_count = 0;
         for x in 0..board.size {
-            let index = (y * board.size + x) as usize;
-            let Some(stack) = board.stacks[index].as_ref() else {
+            let Some(stack) = board.stack_at(&TakPos::new(x as i32, y as i32)) else {
                 empty_count += 1;
                 continue;
             };
@@ -380,6 +982,9 @@ pub fn game_position_from_string(s: &str) -> Option<TakGamePosition> {
     let ply_index = (move_index - 1) * 2 + (turn_indicator - 1);
     let rows = board_str.split('/').collect::<Vec<_>>();
     let size = rows.len();
+    if size > TakBoard::MAX_SIZE as usize {
+        return None;
+    }
     let mut stacks = vec![None; size * size];
     for (y, row) in rows.iter().rev().enumerate() {
         let cells = row.split(',');
@@ -412,13 +1017,16 @@ pub fn game_position_from_string(s: &str) -> Option<TakGamePosition> {
                     chars.pop();
                 }
                 let mut composition = Vec::new();
-                while let Some(c) = chars.pop() {
+                for c in chars {
                     match c {
                         '1' => composition.push(TakPlayer::White),
                         '2' => composition.push(TakPlayer::Black),
                         _ => return None,
                     }
                 }
+                if composition.is_empty() {
+                    return None;
+                }
                 let index = (y * size + x) as usize;
                 stacks[index] = Some(TakStack {
                     variant,
@@ -431,10 +1039,7 @@ pub fn game_position_from_string(s: &str) -> Option<TakGamePosition> {
             return None;
         }
     }
-    let board = TakBoard {
-        size: size as u32,
-        stacks,
-    };
+    let board = TakBoard::from_stacks(size as u32, stacks);
     Some(TakGamePosition { board, ply_index })
 }
 
@@ -446,7 +1051,7 @@ fn variant_to_string(variant: &TakVariant) -> &'static str {
     }
 }
 
-fn pos_to_string(pos: &TakPos) -> String {
+pub fn pos_to_string(pos: &TakPos) -> String {
     format!("{}{}", (b'a' + pos.x as u8) as char, pos.y + 1)
 }
 
@@ -619,5 +1224,306 @@ mod tests {
 
         let state = TakGameResult::Draw;
         assert_eq!(game_result_to_string(&state), "1/2-1/2");
+
+        let state = TakGameResult::Win {
+            winner: TakPlayer::Black,
+            reason: TakWinReason::Time,
+        };
+        assert_eq!(game_result_to_string(&state), "0-T");
+
+        for winner in [TakPlayer::White, TakPlayer::Black] {
+            for reason in TakWinReason::ALL {
+                let state = TakGameResult::Win { winner, reason };
+                let string = game_result_to_string(&state);
+                assert_eq!(game_result_from_string(&string), Some(state));
+            }
+        }
+        for state in [TakGameResult::Draw, TakGameResult::Aborted] {
+            assert_eq!(
+                game_result_from_string(&game_result_to_string(&state)),
+                Some(state)
+            );
+        }
+        for invalid in ["0-Q", "T-T", "1-1", "R", "", "0-0-0"] {
+            assert_eq!(game_result_from_string(invalid), None);
+        }
+    }
+
+    #[test]
+    fn test_ptn_round_trip() {
+        let settings = TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 6,
+                half_komi: 5,
+                reserve: TakReserve::new(30, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings: TakTimeSettings::Realtime(TakRealtimeTimeControl {
+                contingent: Duration::from_secs(15 * 60),
+                increment: Duration::from_secs(10),
+                extra: None,
+                increment_mode: TakIncrementMode::Fischer,
+            }),
+        };
+        let date = chrono::DateTime::parse_from_rfc3339("2024-03-05T17:04:09Z")
+            .unwrap()
+            .to_utc();
+        let moves = ["a1", "f6", "d3", "c4", "d3<", "Sc3", "d3", "2c4-11"]
+            .iter()
+            .map(|m| action_from_ptn(m).unwrap())
+            .collect::<Vec<_>>();
+        let ptn = game_to_ptn(
+            &settings,
+            Some(TakGameResult::Win {
+                winner: TakPlayer::Black,
+                reason: TakWinReason::Road,
+            }),
+            moves,
+            ("alice \"the wall\" \\o/".to_string(), Some(1520.5)),
+            ("bob".to_string(), None),
+            date,
+        );
+        let parsed = Ptn::parse(&ptn.to_string()).unwrap();
+        assert_eq!(parsed, ptn);
+        assert_eq!(ptn_headers_to_settings(&parsed.headers), Some(settings));
+    }
+
+    #[test]
+    fn test_handicap_reserve_headers() {
+        let base = TakBaseGameSettings {
+            board_size: 6,
+            half_komi: 4,
+            reserve: TakReserve::new(30, 1),
+            black_reserve: Some(TakReserve::new(25, 0)),
+            opening: TakOpening::Swap,
+            rules: TakRules::default(),
+        };
+        let headers = settings_to_ptn_headers(&TakGameSettings {
+            base: base.clone(),
+            time_settings: TakTimeSettings::Async(TakAsyncTimeControl {
+                contingent: Duration::from_secs(24 * 3600),
+            }),
+        });
+        let ptn_string = Ptn::new(headers, Vec::new()).to_string();
+        assert!(ptn_string.contains("[Flats2 \"25\"]\n[Caps2 \"0\"]"));
+        let parsed = Ptn::parse(&ptn_string).unwrap();
+        assert_eq!(ptn_headers_to_base_settings(&parsed.headers), Some(base));
+        assert!(!ptn_string.contains("[Rules"));
+
+        // a missing Caps2 falls back to the shared capstone count
+        let parsed = Ptn::parse("[Size \"6\"]\n[Flats \"30\"]\n[Flats2 \"25\"]").unwrap();
+        assert_eq!(
+            ptn_headers_to_base_settings(&parsed.headers).and_then(|s| s.black_reserve),
+            Some(TakReserve::new(25, 1))
+        );
+
+        // explicit reserves don't allow unsupported board sizes
+        let parsed = Ptn::parse("[Size \"9\"]\n[Flats \"50\"]\n[Caps \"2\"]").unwrap();
+        assert_eq!(ptn_headers_to_base_settings(&parsed.headers), None);
+    }
+
+    #[test]
+    fn test_rules_header() {
+        let rules = TakRules {
+            repetition: TakRepetitionRule::Allowed,
+            flat_tiebreak: TakFlatTiebreak::Reserve,
+            reserve_runout: TakReserveRunout::BothEmpty,
+            no_standing_stones: true,
+        };
+        let header = PtnHeader::Rules(rules.clone());
+        assert_eq!(
+            header.to_header_string(),
+            "[Rules \"no-repetition reserve-tiebreak no-standing both-reserves\"]"
+        );
+        let parsed = Ptn::parse(&format!("[Size \"5\"]\n{}", header.to_header_string())).unwrap();
+        assert_eq!(
+            ptn_headers_to_base_settings(&parsed.headers).map(|s| s.rules),
+            Some(rules)
+        );
+        assert!(Ptn::parse("[Rules \"no-capstones\"]").is_err());
+    }
+
+    #[test]
+    fn test_ptn_clock_comments() {
+        let settings = TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::new(21, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings: TakTimeSettings::Async(TakAsyncTimeControl {
+                contingent: Duration::from_secs(3 * 24 * 3600),
+            }),
+        };
+        let moves = ["a1", "e5", "c3"]
+            .iter()
+            .map(|m| action_from_ptn(m).unwrap())
+            .collect::<Vec<_>>();
+        let time_info = |white: u64, black: u64| TakTimeInfo {
+            white_remaining: Duration::from_millis(white),
+            black_remaining: Duration::from_millis(black),
+        };
+        let clocks = vec![
+            Some(time_info(259_200_000, 259_200_000)),
+            None,
+            Some(time_info(59_999, 3_723_040)),
+        ];
+        let ptn = game_to_ptn(
+            &settings,
+            None,
+            moves,
+            ("alice".to_string(), None),
+            ("bob".to_string(), None),
+            chrono::DateTime::UNIX_EPOCH,
+        )
+        .with_clocks(clocks);
+        let ptn_string = ptn.to_string();
+        assert!(ptn_string.contains("[Clock \"async 72:0:0\"]"));
+        assert!(
+            ptn_string
+                .contains("1. a1 {clk 72:00:00 72:00:00} e5\n2. c3 {clk 0:59.999 1:02:03.040}")
+        );
+        let parsed = Ptn::parse(&ptn_string).unwrap();
+        assert_eq!(parsed, ptn);
+        assert_eq!(ptn_headers_to_settings(&parsed.headers), Some(settings));
+
+        let err = Ptn::parse("1. a1 {clk 1:00} e5").unwrap_err();
+        assert_eq!(
+            err.kind,
+            PtnParseErrorKind::InvalidClock("clk 1:00".to_string())
+        );
+        assert!(Ptn::parse("[Clock \"async 10:00 +5\"]").is_err());
+    }
+
+    #[test]
+    fn test_ptn_parse_comments_and_result() {
+        let ptn = "[Size \"5\"]\n[Event \"Some \\\"quoted\\\" event\"]\n\n\
+            {opening comment}\n1. a1 e5 {multi\nline} 2. Cc3'' d4!?\n3. c3>* R-0\n";
+        let parsed: Ptn = ptn.parse().unwrap();
+        assert_eq!(
+            parsed.headers,
+            vec![
+                PtnHeader::Size(5),
+                PtnHeader::Tag("Event".to_string(), "Some \"quoted\" event".to_string()),
+                PtnHeader::Result(TakGameResult::Win {
+                    winner: TakPlayer::White,
+                    reason: TakWinReason::Road,
+                }),
+            ]
+        );
+        assert_eq!(parsed.moves.len(), 5);
+        assert_eq!(parsed.moves[4], action_from_ptn("c3>").unwrap());
+        assert_eq!(
+            ptn_headers_to_base_settings(&parsed.headers),
+            Some(TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::new(21, 1),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            })
+        );
+        assert_eq!(ptn_headers_to_settings(&parsed.headers), None);
+    }
+
+    #[test]
+    fn test_ptn_parse_errors() {
+        let err = Ptn::parse("[Size \"5\"]\n\n1. a1 e5\n2. a2 a0").unwrap_err();
+        assert_eq!(
+            err,
+            PtnParseError {
+                line: 4,
+                column: 7,
+                kind: PtnParseErrorKind::InvalidMove("a0".to_string()),
+            }
+        );
+
+        let err = Ptn::parse("[Size \"5\"]\n[Komi \"1.3\"]").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 1));
+        assert!(matches!(
+            err.kind,
+            PtnParseErrorKind::InvalidTagValue { .. }
+        ));
+
+        let err = Ptn::parse("[Size 5]").unwrap_err();
+        assert_eq!(err.kind, PtnParseErrorKind::MalformedTag);
+
+        let err = Ptn::parse("1. a1 e5\n3. a2").unwrap_err();
+        assert_eq!(
+            err,
+            PtnParseError {
+                line: 2,
+                column: 1,
+                kind: PtnParseErrorKind::InvalidMoveNumber("3.".to_string()),
+            }
+        );
+
+        let err = Ptn::parse("1. a1 {never closed").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 7));
+        assert_eq!(err.kind, PtnParseErrorKind::UnterminatedComment);
+    }
+
+    #[test]
+    fn test_timer_info_round_trip() {
+        for (contingent, increment) in [(5, 0), (600, 5), (3600 + 61, 30)] {
+            let s = PtnHeader::timer_info(
+                Duration::from_secs(contingent),
+                Duration::from_secs(increment),
+            );
+            let tc = PtnHeader::timer_info_from_string(&s).unwrap();
+            assert_eq!(tc.contingent, Duration::from_secs(contingent));
+            assert_eq!(tc.increment, Duration::from_secs(increment));
+        }
+        assert!(PtnHeader::timer_info_from_string("10:00 5").is_none());
+        assert!(PtnHeader::timer_info_from_string("10:00 x5").is_none());
+    }
+
+    #[test]
+    fn test_time_odds_header_round_trip() {
+        let time_control = |contingent, increment, increment_mode| TakRealtimeTimeControl {
+            contingent: Duration::from_secs(contingent),
+            increment: Duration::from_secs(increment),
+            extra: None,
+            increment_mode,
+        };
+        let white = time_control(600, 5, TakIncrementMode::Delay);
+        let black = time_control(180, 2, TakIncrementMode::Bronstein);
+        let header = PtnHeader::TimeControlOdds(white.clone(), black.clone());
+        assert_eq!(header.to_header_string(), "[Clock \"10:0 d5 / 3:0 b2\"]");
+
+        let parsed = Ptn::parse(&header.to_header_string()).unwrap();
+        assert_eq!(parsed.headers, vec![header]);
+        assert_eq!(
+            ptn_headers_to_settings(
+                &[PtnHeader::Size(5)]
+                    .into_iter()
+                    .chain(parsed.headers)
+                    .collect::<Vec<_>>()
+            )
+            .map(|settings| settings.time_settings),
+            Some(TakTimeSettings::RealtimeOdds(white, black))
+        );
+    }
+
+    #[test]
+    fn test_game_position_round_trip() {
+        let tps =
+            "x3,12,2S/x,22S,22C,11,21/121,212,12,1121C,1212S/21S,1,21,211S,12S/x,21S,2,x2 1 26";
+        let position = game_position_from_string(tps).unwrap();
+        assert_eq!(
+            position.board.stacks()[2].as_ref().unwrap().composition,
+            vec![TakPlayer::Black]
+        );
+        assert_eq!(
+            position.board.stacks()[1].as_ref().unwrap().composition,
+            vec![TakPlayer::Black, TakPlayer::White]
+        );
+        assert_eq!(game_position_to_string(&position), tps);
     }
 }
@@ -0,0 +1,522 @@
+use std::{fmt, str::FromStr};
+
+use crate::{TakAction, TakGameResult, TakTimeInfo};
+
+use super::{
+    Ptn, PtnHeader, PtnParseError, PtnParseErrorKind, PtnScanner, action_from_ptn, action_to_ptn,
+    clock_comment, clock_from_comment, game_result_from_string,
+};
+
+/// How deeply variations may be nested, so that parsing can't exhaust the stack.
+const MAX_VARIATION_DEPTH: usize = 64;
+
+/// A game with sidelines, e.g. a study or an annotated game. Variations are written in
+/// parentheses after the move they replace, as in `1. a1 (1. e1) e5`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PtnTree {
+    pub headers: Vec<PtnHeader>,
+    /// Comments before the first move.
+    pub comments: Vec<String>,
+    /// Candidates for the first move, the main line first.
+    pub variations: Vec<PtnNode>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PtnNode {
+    pub action: TakAction,
+    pub tak_mark: Option<PtnTakMark>,
+    pub evaluation: Option<PtnEvaluation>,
+    /// Comments after the move. Comments at the start of a variation are attached to its first
+    /// move.
+    pub comments: Vec<String>,
+    /// Remaining time of both players after the move, written as a `{clk ...}` comment.
+    pub clock: Option<TakTimeInfo>,
+    /// Candidates for the next move, the main line first.
+    pub children: Vec<PtnNode>,
+}
+
+/// Marks a move that threatens a road, `'`, or that cannot be defended against, `''`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PtnTakMark {
+    Tak,
+    Tinue,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PtnEvaluation {
+    /// `!`
+    Good,
+    /// `?`
+    Mistake,
+    /// `!!`
+    Brilliant,
+    /// `??`
+    Blunder,
+    /// `!?`
+    Interesting,
+    /// `?!`
+    Dubious,
+}
+
+impl PtnNode {
+    pub fn new(action: TakAction) -> Self {
+        Self {
+            action,
+            tak_mark: None,
+            evaluation: None,
+            comments: Vec::new(),
+            clock: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl PtnTree {
+    pub fn new(headers: Vec<PtnHeader>) -> Self {
+        Self {
+            headers,
+            ..Default::default()
+        }
+    }
+
+    /// The game following the first variation at every move.
+    pub fn main_line(&self) -> Ptn {
+        let mut moves = Vec::new();
+        let mut clocks = Vec::new();
+        let mut nodes = &self.variations;
+        while let Some(node) = nodes.first() {
+            moves.push(node.action.clone());
+            clocks.push(node.clock.clone());
+            nodes = &node.children;
+        }
+        if clocks.iter().all(Option::is_none) {
+            clocks.clear();
+        }
+        Ptn::new(self.headers.clone(), moves).with_clocks(clocks)
+    }
+
+    /// Parses PTN with nested variations in parentheses. Move numbers are checked against the
+    /// position in the tree, a black move may be numbered as `3...`.
+    pub fn parse(s: &str) -> Result<Self, PtnParseError> {
+        let mut parser = PtnTreeParser {
+            scanner: PtnScanner::new(s),
+            result: None,
+        };
+        let mut tags = Vec::new();
+        let mut comments = Vec::new();
+        loop {
+            parser.scanner.skip_whitespace();
+            match parser.scanner.peek() {
+                Some('[') => tags.push(parser.scanner.read_tag()?),
+                Some('{') => comments.push(parser.scanner.read_comment()?),
+                _ => break,
+            }
+        }
+        let (leading_comments, variations) = parser.parse_line(0, None, 0)?;
+        comments.extend(leading_comments);
+
+        let mut headers = super::tags_to_ptn_headers(tags)?;
+        if let Some(result) = parser.result
+            && !headers.iter().any(|h| matches!(h, PtnHeader::Result(_)))
+        {
+            headers.push(PtnHeader::Result(result));
+        }
+        Ok(PtnTree {
+            headers,
+            comments,
+            variations,
+        })
+    }
+}
+
+impl From<Ptn> for PtnTree {
+    fn from(ptn: Ptn) -> Self {
+        let mut variations = Vec::new();
+        for (i, action) in ptn.moves.into_iter().enumerate().rev() {
+            let mut node = PtnNode::new(action);
+            node.clock = ptn.clocks.get(i).cloned().flatten();
+            node.children = variations;
+            variations = vec![node];
+        }
+        PtnTree {
+            headers: ptn.headers,
+            comments: Vec::new(),
+            variations,
+        }
+    }
+}
+
+impl FromStr for PtnTree {
+    type Err = PtnParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PtnTree::parse(s)
+    }
+}
+
+impl fmt::Display for PtnTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for header in &self.headers {
+            writeln!(f, "{}", header.to_header_string())?;
+        }
+        writeln!(f)?;
+        let mut out = String::new();
+        for comment in &self.comments {
+            push_token(&mut out, &comment_token(comment));
+        }
+        write_line(&mut out, &self.variations, 0, 0);
+        f.write_str(&out)
+    }
+}
+
+struct PtnTreeParser {
+    scanner: PtnScanner,
+    result: Option<TakGameResult>,
+}
+
+impl PtnTreeParser {
+    /// Reads moves until the end of the input, or until the closing parenthesis of a variation
+    /// that was opened at `opened_at`, `depth` variations deep. Returns the comments before the
+    /// first move and the candidates for the first move.
+    fn parse_line(
+        &mut self,
+        start_ply: usize,
+        opened_at: Option<(usize, usize)>,
+        depth: usize,
+    ) -> Result<(Vec<String>, Vec<PtnNode>), PtnParseError> {
+        let mut leading_comments = Vec::new();
+        // Each move of the line with the variations replacing it.
+        let mut entries: Vec<(PtnNode, Vec<PtnNode>)> = Vec::new();
+        loop {
+            self.scanner.skip_whitespace();
+            let (line, column) = self.scanner.position();
+            let error = |kind| PtnParseError::new(line, column, kind);
+            let Some(c) = self.scanner.peek() else {
+                if let Some((line, column)) = opened_at {
+                    return Err(PtnParseError::new(
+                        line,
+                        column,
+                        PtnParseErrorKind::UnterminatedVariation,
+                    ));
+                }
+                break;
+            };
+            match c {
+                '{' => {
+                    let comment = self.scanner.read_comment()?;
+                    let clock = match comment.trim().strip_prefix("clk ") {
+                        Some(clock) => Some(clock_from_comment(clock).ok_or_else(|| {
+                            error(PtnParseErrorKind::InvalidClock(comment.clone()))
+                        })?),
+                        None => None,
+                    };
+                    match (entries.last_mut(), clock) {
+                        (Some((node, _)), Some(clock)) => node.clock = Some(clock),
+                        (Some((node, _)), None) => node.comments.push(comment),
+                        (None, Some(_)) => {
+                            return Err(error(PtnParseErrorKind::InvalidClock(comment)));
+                        }
+                        (None, None) => leading_comments.push(comment),
+                    }
+                }
+                '(' => {
+                    self.scanner.next();
+                    if entries.is_empty() || self.result.is_some() {
+                        return Err(error(PtnParseErrorKind::UnexpectedToken("(".to_string())));
+                    }
+                    if depth >= MAX_VARIATION_DEPTH {
+                        return Err(error(PtnParseErrorKind::VariationTooDeep));
+                    }
+                    let ply = start_ply + entries.len() - 1;
+                    let (comments, mut variation) =
+                        self.parse_line(ply, Some((line, column)), depth + 1)?;
+                    if let Some(first) = variation.first_mut() {
+                        first.comments.splice(0..0, comments);
+                    }
+                    if let Some((_, alternatives)) = entries.last_mut() {
+                        alternatives.append(&mut variation);
+                    }
+                }
+                ')' if opened_at.is_some() => {
+                    self.scanner.next();
+                    break;
+                }
+                '[' | ')' => {
+                    self.scanner.next();
+                    return Err(error(PtnParseErrorKind::UnexpectedToken(c.to_string())));
+                }
+                _ => {
+                    let token = self.scanner.read_token();
+                    if self.result.is_some() {
+                        return Err(error(PtnParseErrorKind::UnexpectedToken(token)));
+                    }
+                    let ply = start_ply + entries.len();
+                    if opened_at.is_none()
+                        && let Some(game_result) = game_result_from_string(&token)
+                    {
+                        self.result = Some(game_result);
+                    } else if let Some(number) = token
+                        .strip_suffix("...")
+                        .filter(|_| !ply.is_multiple_of(2))
+                        .or_else(|| token.strip_suffix('.').filter(|_| ply.is_multiple_of(2)))
+                    {
+                        if number.parse::<usize>().ok() != Some(ply / 2 + 1) {
+                            return Err(error(PtnParseErrorKind::InvalidMoveNumber(token)));
+                        }
+                    } else if token.ends_with('.') {
+                        return Err(error(PtnParseErrorKind::InvalidMoveNumber(token)));
+                    } else {
+                        let node = node_from_ptn(&token)
+                            .ok_or_else(|| error(PtnParseErrorKind::InvalidMove(token.clone())))?;
+                        entries.push((node, Vec::new()));
+                    }
+                }
+            }
+        }
+
+        let mut candidates = Vec::new();
+        for (mut node, alternatives) in entries.into_iter().rev() {
+            node.children = candidates;
+            candidates = std::iter::once(node).chain(alternatives).collect();
+        }
+        Ok((leading_comments, candidates))
+    }
+}
+
+/// Writes a line following the first candidate at every move, with the other candidates as
+/// parenthesized variations.
+fn write_line(out: &mut String, mut nodes: &[PtnNode], mut ply: usize, depth: usize) {
+    let mut needs_number = true;
+    while let Some((node, alternatives)) = nodes.split_first() {
+        let number = ply / 2 + 1;
+        if ply.is_multiple_of(2) {
+            if depth == 0 && ply > 0 {
+                out.push('\n');
+            }
+            push_token(out, &format!("{number}."));
+        } else if needs_number {
+            push_token(out, &format!("{number}..."));
+        }
+        push_token(out, &node_to_ptn(node));
+        if let Some(clock) = &node.clock {
+            push_token(out, &format!("{{{}}}", clock_comment(clock)));
+        }
+        for comment in &node.comments {
+            push_token(out, &comment_token(comment));
+        }
+        for alternative in alternatives {
+            push_token(out, "(");
+            write_line(out, std::slice::from_ref(alternative), ply, depth + 1);
+            out.push(')');
+        }
+        needs_number = !alternatives.is_empty();
+        nodes = &node.children;
+        ply += 1;
+    }
+}
+
+/// Wraps a comment in braces, escaping the characters that would end it early.
+fn comment_token(comment: &str) -> String {
+    format!("{{{}}}", comment.replace('\\', "\\\\").replace('}', "\\}"))
+}
+
+fn push_token(out: &mut String, token: &str) {
+    if !(out.is_empty() || out.ends_with(['\n', '('])) {
+        out.push(' ');
+    }
+    out.push_str(token);
+}
+
+fn node_to_ptn(node: &PtnNode) -> String {
+    let mut out = action_to_ptn(&node.action);
+    match node.tak_mark {
+        Some(PtnTakMark::Tak) => out.push('\''),
+        Some(PtnTakMark::Tinue) => out.push_str("''"),
+        None => {}
+    }
+    if let Some(evaluation) = node.evaluation {
+        out.push_str(evaluation_to_string(evaluation));
+    }
+    out
+}
+
+/// Parses a move followed by optional tak marks and an evaluation, e.g. `c3>''!?`. A trailing
+/// `*` is accepted and ignored.
+fn node_from_ptn(token: &str) -> Option<PtnNode> {
+    let move_str = token.trim_end_matches(['\'', '"', '!', '?', '*']);
+    let marks = &token[move_str.len()..];
+    let mut node = PtnNode::new(action_from_ptn(move_str)?);
+    let quotes: usize = marks
+        .chars()
+        .map(|c| match c {
+            '\'' => 1,
+            '"' => 2,
+            _ => 0,
+        })
+        .sum();
+    node.tak_mark = match quotes {
+        0 => None,
+        1 => Some(PtnTakMark::Tak),
+        2 => Some(PtnTakMark::Tinue),
+        _ => return None,
+    };
+    let evaluation = marks
+        .chars()
+        .filter(|c| matches!(c, '!' | '?'))
+        .collect::<String>();
+    if !evaluation.is_empty() {
+        node.evaluation = Some(evaluation_from_string(&evaluation)?);
+    }
+    Some(node)
+}
+
+fn evaluation_to_string(evaluation: PtnEvaluation) -> &'static str {
+    match evaluation {
+        PtnEvaluation::Good => "!",
+        PtnEvaluation::Mistake => "?",
+        PtnEvaluation::Brilliant => "!!",
+        PtnEvaluation::Blunder => "??",
+        PtnEvaluation::Interesting => "!?",
+        PtnEvaluation::Dubious => "?!",
+    }
+}
+
+fn evaluation_from_string(s: &str) -> Option<PtnEvaluation> {
+    Some(match s {
+        "!" => PtnEvaluation::Good,
+        "?" => PtnEvaluation::Mistake,
+        "!!" => PtnEvaluation::Brilliant,
+        "??" => PtnEvaluation::Blunder,
+        "!?" => PtnEvaluation::Interesting,
+        "?!" => PtnEvaluation::Dubious,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_round_trip() {
+        let ptn = "[Size \"5\"]\n\n\
+            {study} 1. a1 {quiet} (1. e1 {mirror} e5 (1... a5)) e5\n\
+            2. c3! (2. c2?! {slow}) 2... c4 (2... d3)\n\
+            3. d3' d4?? {clk 1:00 2:00}\n";
+        let tree = PtnTree::parse(ptn).unwrap();
+        assert_eq!(tree.comments, vec!["study".to_string()]);
+        assert_eq!(tree.variations.len(), 2);
+        let a1 = &tree.variations[0];
+        assert_eq!(a1.comments, vec!["quiet".to_string()]);
+        let e1 = &tree.variations[1];
+        assert_eq!(e1.action, action_from_ptn("e1").unwrap());
+        assert_eq!(e1.children.len(), 2);
+        assert_eq!(e1.children[1].action, action_from_ptn("a5").unwrap());
+
+        let c3 = &a1.children[0].children[0];
+        assert_eq!(c3.evaluation, Some(PtnEvaluation::Good));
+        assert_eq!(c3.children.len(), 2);
+        let d3 = &c3.children[0].children[0];
+        assert_eq!(d3.tak_mark, Some(PtnTakMark::Tak));
+        assert_eq!(d3.children[0].evaluation, Some(PtnEvaluation::Blunder));
+        assert!(d3.children[0].clock.is_some());
+
+        let written = tree.to_string();
+        assert_eq!(
+            written,
+            "[Size \"5\"]\n\n\
+            {study} 1. a1 {quiet} (1. e1 {mirror} e5 (1... a5)) 1... e5\n\
+            2. c3! (2. c2?! {slow}) 2... c4 (2... d3)\n\
+            3. d3' d4?? {clk 1:00 2:00}"
+        );
+        assert_eq!(PtnTree::parse(&written).unwrap(), tree);
+
+        let main_line = tree.main_line();
+        assert_eq!(main_line.moves.len(), 6);
+        assert_eq!(Ptn::parse(ptn).unwrap(), main_line);
+        assert_eq!(PtnTree::from(main_line.clone()).main_line(), main_line);
+    }
+
+    #[test]
+    fn test_tree_marks() {
+        let tree = PtnTree::parse("1. a1 e5 2. Cc3\" c4''!? 3. c3>*").unwrap();
+        let c3 = &tree.variations[0].children[0].children[0];
+        assert_eq!(c3.tak_mark, Some(PtnTakMark::Tinue));
+        assert_eq!(c3.children[0].tak_mark, Some(PtnTakMark::Tinue));
+        assert_eq!(c3.children[0].evaluation, Some(PtnEvaluation::Interesting));
+        assert_eq!(c3.children[0].children[0].tak_mark, None);
+        assert!(PtnTree::parse("1. a1'''").is_err());
+        assert!(PtnTree::parse("1. a1!!!").is_err());
+    }
+
+    #[test]
+    fn test_tree_parse_errors() {
+        let err = PtnTree::parse("1. a1 (1. e1 e5").unwrap_err();
+        assert_eq!(
+            err,
+            PtnParseError::new(1, 7, PtnParseErrorKind::UnterminatedVariation)
+        );
+
+        let err = PtnTree::parse("(1. a1)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            PtnParseErrorKind::UnexpectedToken("(".to_string())
+        );
+
+        let err = PtnTree::parse("1. a1 e5)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            PtnParseErrorKind::UnexpectedToken(")".to_string())
+        );
+
+        let err = PtnTree::parse("1. a1 (2. e1)").unwrap_err();
+        assert_eq!(
+            err,
+            PtnParseError::new(1, 8, PtnParseErrorKind::InvalidMoveNumber("2.".to_string()))
+        );
+
+        let err = PtnTree::parse("1. a1 e5 (1. a5)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            PtnParseErrorKind::InvalidMoveNumber("1.".to_string())
+        );
+
+        let err = PtnTree::parse("1. a1 e5 R-0 (1... a5)").unwrap_err();
+        assert_eq!(
+            err.kind,
+            PtnParseErrorKind::UnexpectedToken("(".to_string())
+        );
+
+        let err = PtnTree::parse("1. a1 ({clk 1:00 2:00} 1. e1)").unwrap_err();
+        assert_eq!(
+            err,
+            PtnParseError::new(
+                1,
+                8,
+                PtnParseErrorKind::InvalidClock("clk 1:00 2:00".to_string())
+            )
+        );
+
+        let nested = format!("1. a1 {}", "(1. e1 ".repeat(MAX_VARIATION_DEPTH + 1));
+        let err = PtnTree::parse(&nested).unwrap_err();
+        assert_eq!(err.kind, PtnParseErrorKind::VariationTooDeep);
+        let nested = format!(
+            "1. a1 {}{}",
+            "(1. e1 ".repeat(MAX_VARIATION_DEPTH),
+            ")".repeat(MAX_VARIATION_DEPTH)
+        );
+        assert!(PtnTree::parse(&nested).is_ok());
+    }
+
+    #[test]
+    fn test_tree_comment_escaping() {
+        let mut tree = PtnTree::parse("1. a1").unwrap();
+        tree.comments
+            .push("a {brace} and a \\ backslash".to_string());
+        tree.variations[0].comments.push("}".to_string());
+        let written = tree.to_string();
+        assert!(written.contains("{a {brace\\} and a \\\\ backslash}"));
+        assert!(written.contains("1. a1 {\\}}"));
+        assert_eq!(PtnTree::parse(&written).unwrap(), tree);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    TakAction, TakBoard, TakDir, TakPos,
+    ptn::{TakGamePosition, game_position_to_string},
+};
+
+/// One of the 8 symmetries of a square board. Rotations are clockwise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TakSymmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors the files, a1 becomes e1 on a 5x5 board.
+    MirrorHorizontal,
+    /// Mirrors the ranks, a1 becomes a5 on a 5x5 board.
+    MirrorVertical,
+    /// Mirrors along the a1-e5 diagonal.
+    Transpose,
+    /// Mirrors along the a5-e1 diagonal.
+    AntiTranspose,
+}
+
+impl TakSymmetry {
+    pub const ALL: [TakSymmetry; 8] = [
+        TakSymmetry::Identity,
+        TakSymmetry::Rotate90,
+        TakSymmetry::Rotate180,
+        TakSymmetry::Rotate270,
+        TakSymmetry::MirrorHorizontal,
+        TakSymmetry::MirrorVertical,
+        TakSymmetry::Transpose,
+        TakSymmetry::AntiTranspose,
+    ];
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            TakSymmetry::Rotate90 => TakSymmetry::Rotate270,
+            TakSymmetry::Rotate270 => TakSymmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    /// Maps a coordinate pair, `max` being the highest coordinate on the board. With a `max` of 0
+    /// this maps direction vectors instead.
+    fn apply(self, x: i32, y: i32, max: i32) -> (i32, i32) {
+        match self {
+            TakSymmetry::Identity => (x, y),
+            TakSymmetry::Rotate90 => (y, max - x),
+            TakSymmetry::Rotate180 => (max - x, max - y),
+            TakSymmetry::Rotate270 => (max - y, x),
+            TakSymmetry::MirrorHorizontal => (max - x, y),
+            TakSymmetry::MirrorVertical => (x, max - y),
+            TakSymmetry::Transpose => (y, x),
+            TakSymmetry::AntiTranspose => (max - y, max - x),
+        }
+    }
+}
+
+impl TakPos {
+    pub fn transform(&self, symmetry: TakSymmetry, size: u32) -> Self {
+        let (x, y) = symmetry.apply(self.x, self.y, size as i32 - 1);
+        TakPos::new(x, y)
+    }
+}
+
+impl TakDir {
+    pub fn transform(self, symmetry: TakSymmetry) -> Self {
+        let delta = TakPos::new(0, 0).offset(self, 1);
+        match symmetry.apply(delta.x, delta.y, 0) {
+            (0, 1) => TakDir::Up,
+            (1, 0) => TakDir::Right,
+            (0, -1) => TakDir::Down,
+            _ => TakDir::Left,
+        }
+    }
+}
+
+impl TakAction {
+    pub fn transform(&self, symmetry: TakSymmetry, size: u32) -> Self {
+        match self {
+            TakAction::Place { pos, variant } => TakAction::Place {
+                pos: pos.transform(symmetry, size),
+                variant: *variant,
+            },
+            TakAction::Move { pos, dir, drops } => TakAction::Move {
+                pos: pos.transform(symmetry, size),
+                dir: dir.transform(symmetry),
+                drops: drops.clone(),
+            },
+        }
+    }
+}
+
+impl TakBoard {
+    pub fn transform(&self, symmetry: TakSymmetry) -> Self {
+        let size = self.size;
+        let mut stacks = vec![None; (size * size) as usize];
+        for (index, stack) in self.stacks().iter().enumerate() {
+            let pos = TakPos::new((index as u32 % size) as i32, (index as u32 / size) as i32)
+                .transform(symmetry, size);
+            stacks[(pos.y * size as i32 + pos.x) as usize] = stack.clone();
+        }
+        TakBoard::from_stacks(size, stacks)
+    }
+}
+
+impl TakGamePosition {
+    pub fn transform(&self, symmetry: TakSymmetry) -> Self {
+        TakGamePosition {
+            board: self.board.transform(symmetry),
+            ply_index: self.ply_index,
+        }
+    }
+
+    /// The representative of this position's symmetry class, which is the variant with the
+    /// smallest TPS, together with the symmetry that maps this position onto it. Actions played
+    /// from this position can be mapped onto the canonical one with the same symmetry.
+    pub fn canonical(&self) -> (TakGamePosition, TakSymmetry) {
+        TakSymmetry::ALL
+            .into_iter()
+            .map(|symmetry| {
+                let position = self.transform(symmetry);
+                (game_position_to_string(&position), position, symmetry)
+            })
+            .min_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, position, symmetry)| (position, symmetry))
+            .expect("there is always at least one symmetry")
+    }
+
+    /// A TPS that is identical for all positions that are mirrors or rotations of each other.
+    pub fn canonical_key(&self) -> String {
+        game_position_to_string(&self.canonical().0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        TakVariant,
+        ptn::{action_from_ptn, action_to_ptn, game_position_from_string},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_transform_pos_and_action() {
+        let a1 = TakPos::new(0, 0);
+        let b1 = TakPos::new(1, 0);
+        assert_eq!(a1.transform(TakSymmetry::Rotate90, 5), TakPos::new(0, 4));
+        assert_eq!(b1.transform(TakSymmetry::Rotate90, 5), TakPos::new(0, 3));
+        assert_eq!(b1.transform(TakSymmetry::Transpose, 5), TakPos::new(0, 1));
+        assert_eq!(
+            b1.transform(TakSymmetry::AntiTranspose, 5),
+            TakPos::new(4, 3)
+        );
+        assert_eq!(TakDir::Up.transform(TakSymmetry::Rotate90), TakDir::Right);
+        assert_eq!(TakDir::Right.transform(TakSymmetry::Transpose), TakDir::Up);
+
+        let action = action_from_ptn("3b1>12").unwrap();
+        for symmetry in TakSymmetry::ALL {
+            for pos in [a1.clone(), b1.clone(), TakPos::new(2, 3)] {
+                assert_eq!(
+                    pos.transform(symmetry, 5).transform(symmetry.inverse(), 5),
+                    pos
+                );
+            }
+            assert_eq!(
+                action
+                    .transform(symmetry, 5)
+                    .transform(symmetry.inverse(), 5),
+                action
+            );
+        }
+        assert_eq!(
+            action_to_ptn(&action.transform(TakSymmetry::MirrorHorizontal, 5)),
+            "3d1<12"
+        );
+        assert_eq!(
+            action_from_ptn("Sc3")
+                .unwrap()
+                .transform(TakSymmetry::Rotate180, 5),
+            TakAction::Place {
+                pos: TakPos::new(2, 2),
+                variant: TakVariant::Standing,
+            }
+        );
+    }
+
+    #[test]
+    fn test_transformed_moves_follow_board() {
+        let position = game_position_from_string("2,x4/x5/x2,1,x2/x5/x5 1 2").unwrap();
+        let pos = TakPos::new(2, 2);
+        for symmetry in TakSymmetry::ALL {
+            let mut board = position.board.clone();
+            board.do_move(&pos, TakDir::Right, &[1]).unwrap();
+            let mut transformed = position.board.transform(symmetry);
+            transformed
+                .do_move(
+                    &pos.transform(symmetry, 5),
+                    TakDir::Right.transform(symmetry),
+                    &[1],
+                )
+                .unwrap();
+            assert_eq!(transformed.stacks(), board.transform(symmetry).stacks());
+            assert_eq!(transformed.hash(), board.transform(symmetry).hash());
+        }
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let position = game_position_from_string("2,x4/x5/x5/x5/x3,1,x 1 2").unwrap();
+        let key = position.canonical_key();
+        for symmetry in TakSymmetry::ALL {
+            let transformed = position.transform(symmetry);
+            assert_eq!(transformed.canonical_key(), key);
+            let (canonical, to_canonical) = transformed.canonical();
+            assert_eq!(
+                game_position_to_string(&transformed.transform(to_canonical)),
+                game_position_to_string(&canonical)
+            );
+        }
+
+        let other = game_position_from_string("2,x4/x5/x5/x5/x2,1,x2 1 2").unwrap();
+        assert_ne!(other.canonical_key(), key);
+        let black_to_move = game_position_from_string("2,x4/x5/x5/x5/x3,1,x 2 2").unwrap();
+        assert_ne!(black_to_move.canonical_key(), key);
+    }
+}
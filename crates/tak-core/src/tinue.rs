@@ -0,0 +1,164 @@
+use crate::{TakAction, TakGameResult, TakOngoingBaseGame, TakPlayer, TakWinReason};
+
+impl TakOngoingBaseGame {
+    /// Actions that complete a road for the side to move.
+    pub fn road_threats(&self) -> Vec<TakAction> {
+        let mut game = self.clone();
+        let player = game.current_player;
+        game.legal_actions()
+            .into_iter()
+            .filter(|action| game.wins_by_road(action, player))
+            .collect()
+    }
+
+    /// Whether the player who made the last move could complete a road with their next move.
+    pub fn is_tak(&self) -> bool {
+        let mut game = self.clone();
+        game.has_road_threat(self.current_player.opponent())
+    }
+
+    /// Searches for a win of the side to move within `max_depth` of its own moves, where every
+    /// move before the winning one has to threaten a road. Returns the first move of the win.
+    pub fn find_tinue(&self, max_depth: u32) -> Option<TakAction> {
+        self.clone().forced_win(max_depth)
+    }
+
+    /// Whether the side to move loses whatever it does, with the opponent needing at most
+    /// `max_depth` moves to win.
+    pub fn is_tinue(&self, max_depth: u32) -> bool {
+        self.clone().defender_loses(max_depth)
+    }
+
+    fn wins_by_road(&mut self, action: &TakAction, player: TakPlayer) -> bool {
+        let Ok(result) = self.make_action(action.clone()) else {
+            return false;
+        };
+        self.unmake_action();
+        result
+            == Some(TakGameResult::Win {
+                winner: player,
+                reason: TakWinReason::Road,
+            })
+    }
+
+    /// Whether `player` could complete a road if it were their turn.
+    fn has_road_threat(&mut self, player: TakPlayer) -> bool {
+        let previous_player = self.current_player;
+        self.current_player = player;
+        let has_threat = self
+            .legal_actions()
+            .iter()
+            .any(|action| self.wins_by_road(action, player));
+        self.current_player = previous_player;
+        has_threat
+    }
+
+    fn forced_win(&mut self, depth: u32) -> Option<TakAction> {
+        if depth == 0 {
+            return None;
+        }
+        let attacker = self.current_player;
+        let actions = self.legal_actions();
+        for action in &actions {
+            let result = self
+                .make_action(action.clone())
+                .expect("legal actions should be valid");
+            self.unmake_action();
+            if let Some(TakGameResult::Win { winner, .. }) = result
+                && winner == attacker
+            {
+                return Some(action.clone());
+            }
+        }
+        if depth == 1 {
+            return None;
+        }
+        actions.into_iter().find(|action| {
+            let result = self
+                .make_action(action.clone())
+                .expect("legal actions should be valid");
+            let wins = result.is_none()
+                && self.has_road_threat(attacker)
+                && self.defender_loses(depth - 1);
+            self.unmake_action();
+            wins
+        })
+    }
+
+    fn defender_loses(&mut self, depth: u32) -> bool {
+        let defender = self.current_player;
+        self.legal_actions().into_iter().all(|action| {
+            let result = self
+                .make_action(action)
+                .expect("legal actions should be valid");
+            let loses = match result {
+                Some(TakGameResult::Win { winner, .. }) => winner != defender,
+                Some(_) => false,
+                None => self.forced_win(depth).is_some(),
+            };
+            self.unmake_action();
+            loses
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        TakBaseGameSettings, TakOpening, TakReserve, TakRules,
+        ptn::{action_from_ptn, game_position_from_string},
+    };
+
+    use super::*;
+
+    fn game(tps: &str) -> TakOngoingBaseGame {
+        let settings = TakBaseGameSettings {
+            board_size: 5,
+            half_komi: 0,
+            reserve: TakReserve::new(21, 1),
+            black_reserve: None,
+            opening: TakOpening::Swap,
+            rules: TakRules::default(),
+        };
+        TakOngoingBaseGame::from_position(settings, game_position_from_string(tps).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_road_threats() {
+        let black_to_move = game("x5/x5/x5/2,2,x3/1,1,1,1,x 2 5");
+        assert!(black_to_move.road_threats().is_empty());
+        // white threatens e1 after their last move
+        assert!(black_to_move.is_tak());
+
+        let white_to_move = game("x5/x5/x5/2,2,x3/1,1,1,1,x 1 5");
+        assert_eq!(
+            white_to_move.road_threats(),
+            vec![
+                action_from_ptn("e1").unwrap(),
+                action_from_ptn("Ce1").unwrap()
+            ]
+        );
+        assert!(!white_to_move.is_tak());
+    }
+
+    #[test]
+    fn test_tinue() {
+        // d1 threatens both e1 and d2
+        let game_before = game("2,2,x3/x5/x5/x4,1/1,1,1,x2 1 5");
+        assert_eq!(game_before.find_tinue(1), None);
+        let first_move = game_before
+            .find_tinue(2)
+            .expect("white should have a tinue");
+        let mut game_after = game_before.clone();
+        game_after.do_action(first_move).unwrap();
+        assert!(game_after.is_tinue(1));
+        assert!(game("2,2,x3/x5/x5/x4,1/1,1,1,1,x 2 5").is_tinue(1));
+
+        // a single threat can be blocked
+        let single_threat = game("2,2,x3/x5/x5/x5/1,1,1,1,x 2 5");
+        assert!(single_threat.is_tak());
+        assert!(!single_threat.is_tinue(1));
+        assert_eq!(game("2,2,x3/x5/x5/x5/1,1,1,x2 1 5").find_tinue(2), None);
+    }
+}
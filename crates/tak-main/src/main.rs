@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use tak_auth_ory::AuthenticationService;
 use tak_bot_registry::FileBotRepository;
@@ -32,6 +32,8 @@ use crate::{compose::ComposedListenerNotificationService, logs::init_logger};
 mod compose;
 mod logs;
 
+const DEFAULT_FIRST_PLY_TIMEOUT_SECS: u64 = 60;
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
@@ -104,6 +106,13 @@ async fn main() {
     let match_repo = Arc::new(MatchRepositoryImpl::new().await);
     let tournament_round_repo = Arc::new(TournamentRoundRepositoryImpl::new().await);
 
+    let first_ply_timeout = std::env::var("TAK_FIRST_PLY_TIMEOUT_SECS")
+        .map(|secs| {
+            secs.parse::<u64>()
+                .expect("TAK_FIRST_PLY_TIMEOUT_SECS must be a valid u64")
+        })
+        .unwrap_or(DEFAULT_FIRST_PLY_TIMEOUT_SECS);
+
     let app = Arc::new(
         build_application(
             game_repo,
@@ -125,6 +134,7 @@ async fn main() {
             tournament_player_registration_repo,
             match_repo,
             tournament_round_repo,
+            Duration::from_secs(first_ply_timeout),
         )
         .await,
     );
@@ -138,7 +148,10 @@ async fn main() {
 
     let app_clone = app.clone();
     let on_shutdown = async move {
-        shutdown_signal().await;
+        tokio::select! {
+            _ = shutdown_signal() => {},
+            _ = app_clone.shutdown_workflow.wait_until_drained() => {},
+        }
         tracing::info!("Executing shutdown workflow");
         app_clone.shutdown_workflow.shutdown().await;
     };
@@ -0,0 +1,177 @@
+use std::{collections::HashSet, time::Instant};
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+
+use crate::domain::{GameId, PlayerId};
+
+pub const MAX_VACATION_PERIODS: usize = 10;
+pub const MAX_VACATION_DURATION: chrono::Duration = chrono::Duration::days(30);
+
+/// A period in which the async clocks of a player are paused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VacationPeriod {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl VacationPeriod {
+    pub fn contains(&self, date: DateTime<Utc>) -> bool {
+        self.start <= date && date < self.end
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotificationPreferences {
+    /// Email when it's the player's turn in an async game.
+    pub turn_emails: bool,
+    /// Email when the clock of an async game is about to run out.
+    pub timeout_warning_emails: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            turn_emails: true,
+            timeout_warning_emails: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CorrespondenceSettings {
+    pub notifications: NotificationPreferences,
+    pub vacations: Vec<VacationPeriod>,
+}
+
+#[derive(Clone, Debug)]
+pub enum InvalidVacationReason {
+    TooManyPeriods,
+    EndsBeforeStart,
+    TooLong,
+}
+
+impl std::fmt::Display for InvalidVacationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidVacationReason::TooManyPeriods => write!(
+                f,
+                "At most {} vacation periods are allowed",
+                MAX_VACATION_PERIODS
+            ),
+            InvalidVacationReason::EndsBeforeStart => {
+                write!(f, "Vacation periods must end after they start")
+            }
+            InvalidVacationReason::TooLong => write!(
+                f,
+                "Vacation periods can't be longer than {} days",
+                MAX_VACATION_DURATION.num_days()
+            ),
+        }
+    }
+}
+
+impl CorrespondenceSettings {
+    pub fn is_on_vacation(&self, date: DateTime<Utc>) -> bool {
+        self.vacations.iter().any(|period| period.contains(date))
+    }
+
+    /// Drops vacation periods that are over and checks the remaining ones.
+    pub fn validate(&mut self, now: DateTime<Utc>) -> Result<(), InvalidVacationReason> {
+        self.vacations.retain(|period| period.end > now);
+        if self.vacations.len() > MAX_VACATION_PERIODS {
+            return Err(InvalidVacationReason::TooManyPeriods);
+        }
+        for period in &self.vacations {
+            if period.end <= period.start {
+                return Err(InvalidVacationReason::EndsBeforeStart);
+            }
+            if period.end - period.start > MAX_VACATION_DURATION {
+                return Err(InvalidVacationReason::TooLong);
+            }
+        }
+        self.vacations.sort_by_key(|period| period.start);
+        Ok(())
+    }
+}
+
+/// Keeps track of the notifications for async games that haven't been sent yet.
+pub trait CorrespondenceService {
+    /// Remembers that `player_id` has to move in `game_id`, notifications are sent in batches.
+    fn queue_turn_notification(&self, player_id: PlayerId, game_id: GameId, now: Instant);
+    fn cancel_turn_notification(&self, player_id: PlayerId, game_id: GameId);
+    /// Takes the queued notifications of every player whose oldest one was queued before `since`.
+    fn take_due_turn_notifications(&self, since: Instant) -> Vec<(PlayerId, Vec<GameId>)>;
+    /// Returns whether no timeout warning was sent for the ply of the game yet.
+    fn mark_timeout_warning(&self, game_id: GameId, ply_index: usize) -> bool;
+    /// Forgets the warnings of all games that are not ongoing anymore.
+    fn retain_games(&self, ongoing_games: &HashSet<GameId>);
+}
+
+struct PendingTurnNotifications {
+    queued_at: Instant,
+    game_ids: Vec<GameId>,
+}
+
+pub struct CorrespondenceServiceImpl {
+    pending_notifications: DashMap<PlayerId, PendingTurnNotifications>,
+    timeout_warnings: DashMap<GameId, usize>,
+}
+
+impl CorrespondenceServiceImpl {
+    pub fn new() -> Self {
+        Self {
+            pending_notifications: DashMap::new(),
+            timeout_warnings: DashMap::new(),
+        }
+    }
+}
+
+impl CorrespondenceService for CorrespondenceServiceImpl {
+    fn queue_turn_notification(&self, player_id: PlayerId, game_id: GameId, now: Instant) {
+        let mut pending = self
+            .pending_notifications
+            .entry(player_id)
+            .or_insert_with(|| PendingTurnNotifications {
+                queued_at: now,
+                game_ids: Vec::new(),
+            });
+        if !pending.game_ids.contains(&game_id) {
+            pending.game_ids.push(game_id);
+        }
+    }
+
+    fn cancel_turn_notification(&self, player_id: PlayerId, game_id: GameId) {
+        self.pending_notifications
+            .remove_if_mut(&player_id, |_, pending| {
+                pending.game_ids.retain(|id| *id != game_id);
+                pending.game_ids.is_empty()
+            });
+    }
+
+    fn take_due_turn_notifications(&self, since: Instant) -> Vec<(PlayerId, Vec<GameId>)> {
+        let due_players = self
+            .pending_notifications
+            .iter()
+            .filter(|entry| entry.queued_at <= since)
+            .map(|entry| *entry.key())
+            .collect::<Vec<_>>();
+        due_players
+            .into_iter()
+            .filter_map(|player_id| {
+                self.pending_notifications
+                    .remove(&player_id)
+                    .map(|(player_id, pending)| (player_id, pending.game_ids))
+            })
+            .collect()
+    }
+
+    fn mark_timeout_warning(&self, game_id: GameId, ply_index: usize) -> bool {
+        self.timeout_warnings.insert(game_id, ply_index) != Some(ply_index)
+    }
+
+    fn retain_games(&self, ongoing_games: &HashSet<GameId>) {
+        self.timeout_warnings
+            .retain(|game_id, _| ongoing_games.contains(game_id));
+    }
+}
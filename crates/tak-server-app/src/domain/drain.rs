@@ -0,0 +1,41 @@
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+
+/// Tracks whether the server is draining before a shutdown. While draining no new games are
+/// started, running games can still be finished until the deadline.
+pub trait DrainService {
+    fn start_draining(&self, deadline: DateTime<Utc>);
+    /// Returns whether the server was draining.
+    fn cancel_draining(&self) -> bool;
+    fn get_drain_deadline(&self) -> Option<DateTime<Utc>>;
+    fn is_draining(&self) -> bool {
+        self.get_drain_deadline().is_some()
+    }
+}
+
+pub struct DrainServiceImpl {
+    deadline: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl DrainServiceImpl {
+    pub fn new() -> Self {
+        Self {
+            deadline: RwLock::new(None),
+        }
+    }
+}
+
+impl DrainService for DrainServiceImpl {
+    fn start_draining(&self, deadline: DateTime<Utc>) {
+        *self.deadline.write().unwrap() = Some(deadline);
+    }
+
+    fn cancel_draining(&self) -> bool {
+        self.deadline.write().unwrap().take().is_some()
+    }
+
+    fn get_drain_deadline(&self) -> Option<DateTime<Utc>> {
+        *self.deadline.read().unwrap()
+    }
+}
@@ -5,14 +5,18 @@ use std::{
 
 use crate::domain::{
     GameId, MatchId, PlayerId,
-    game::request::{GameRequest, GameRequestSystem, GameRequestType},
+    game::{
+        premove::{GamePremoves, InvalidPremoveReason, PremoveTree},
+        request::{GameRequest, GameRequestSystem, GameRequestType},
+    },
 };
 use dashmap::DashMap;
 use tak_core::{
     MaybeTimeout, TakAction, TakFinishedGame, TakGameSettings, TakOngoingGame, TakPlayer,
-    TakTimeInfo, TakTimeSettings,
+    TakPlyTime, TakTimeInfo, TakTimeSettings,
 };
 
+pub mod premove;
 pub mod request;
 
 #[derive(Clone, Debug)]
@@ -36,6 +40,8 @@ impl GameEvent {
 pub enum GameEventType {
     Action {
         action: TakAction,
+        /// Absent for events recorded before ply times were tracked.
+        ply_time: Option<TakPlyTime>,
     },
     RequestSet {
         player: TakPlayer,
@@ -109,6 +115,7 @@ pub struct OngoingGame {
     pub metadata: GameMetadata,
     pub game: TakOngoingGame,
     pub requests: GameRequestSystem,
+    pub premoves: GamePremoves,
     pub events: Vec<GameEvent>,
 }
 
@@ -116,6 +123,38 @@ impl OngoingGame {
     pub fn get_time_info(&self, now: Instant) -> TakTimeInfo {
         self.game.get_time_info(now)
     }
+
+    pub fn snapshot(&self, now: Instant) -> OngoingGameSnapshot {
+        OngoingGameSnapshot {
+            actions: self.game.action_history().clone(),
+            ply_times: self.game.ply_times().clone(),
+            time_info: self.get_time_info(now),
+            clock_paused: (
+                self.game.is_clock_paused(TakPlayer::White),
+                self.game.is_clock_paused(TakPlayer::Black),
+            ),
+            turn_think_time: self.game.turn_think_time(now),
+            requests: self.requests.clone(),
+            premoves: self.premoves.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+/// The state of an ongoing game that is persisted so it can be resumed after a restart.
+#[derive(Clone, Debug)]
+pub struct OngoingGameSnapshot {
+    pub actions: Vec<TakAction>,
+    pub ply_times: Vec<TakPlyTime>,
+    pub time_info: TakTimeInfo,
+    /// Whether the white and black clock are paused, e.g. during a vacation.
+    pub clock_paused: (bool, bool),
+    /// Time the player to move already spent on the current turn, e.g. so a delay isn't
+    /// granted again after a restart.
+    pub turn_think_time: Duration,
+    pub requests: GameRequestSystem,
+    pub premoves: GamePremoves,
+    pub events: Vec<GameEvent>,
 }
 
 #[derive(Clone, Debug)]
@@ -148,9 +187,17 @@ pub trait GameService {
         match_id: Option<MatchId>,
     ) -> GameMetadata;
     fn create_game(&self, id: GameId, metadata: GameMetadata) -> OngoingGame;
+    /// Resumes a game from its snapshot, returns `None` if the snapshot doesn't describe an
+    /// ongoing game.
+    fn restore_game(
+        &self,
+        id: GameId,
+        metadata: GameMetadata,
+        snapshot: OngoingGameSnapshot,
+        now: Instant,
+    ) -> Option<OngoingGame>;
     fn get_game_by_id(&self, game_id: GameId) -> Option<OngoingGame>;
     fn get_games(&self) -> impl Iterator<Item = OngoingGame>;
-    fn abort_all_games(&self, now: Instant) -> Vec<FinishedGame>;
     fn check_timeout(&self, game_id: GameId, now: Instant) -> CheckTimeoutResult;
     fn check_disconnect_timeout(
         &self,
@@ -185,6 +232,25 @@ pub trait GameService {
         request: GameRequest,
         now: Instant,
     ) -> GamePlayerActionResult<Option<(PlayerGameRequest, TakTimeInfo)>>;
+    /// Replaces the conditional responses of a player, only allowed in async games while
+    /// waiting for the opponent.
+    fn set_premoves(
+        &self,
+        game_id: GameId,
+        player: PlayerId,
+        premoves: PremoveTree,
+        now: Instant,
+    ) -> GamePlayerActionResult<SetPremovesResult>;
+    /// Takes the response the player to move prepared for the last action, if any.
+    fn take_premove(&self, game_id: GameId) -> Option<(PlayerId, TakAction)>;
+    /// Returns whether the clock of the player was paused or resumed.
+    fn set_clock_paused(
+        &self,
+        game_id: GameId,
+        player: PlayerId,
+        paused: bool,
+        now: Instant,
+    ) -> bool;
     fn accept_draw_request(
         &self,
         game_id: GameId,
@@ -242,6 +308,13 @@ pub enum DoActionResult {
     InvalidAction(tak_core::InvalidActionReason),
 }
 
+pub enum SetPremovesResult {
+    PremovesSet,
+    NotAsyncGame,
+    PlayersTurn,
+    InvalidPremoves(InvalidPremoveReason),
+}
+
 pub enum GamePlayerActionResult<R> {
     GameNotFound,
     NotAPlayerInGame,
@@ -252,7 +325,13 @@ pub enum GamePlayerActionResult<R> {
 pub enum CheckTimeoutResult {
     GameNotFound,
     TimedOut(FinishedGame),
-    NoTimeout(TakTimeInfo),
+    /// The player to move didn't make their first ply in time.
+    Aborted(FinishedGame),
+    NoTimeout {
+        time_info: TakTimeInfo,
+        /// Time left for the player to move to make their first ply.
+        first_ply_remaining: Option<Duration>,
+    },
 }
 
 pub enum CheckDisconnectTimeoutResult {
@@ -263,6 +342,7 @@ pub enum CheckDisconnectTimeoutResult {
 
 pub struct GameServiceImpl {
     games: Arc<DashMap<GameId, OngoingGame>>,
+    first_ply_timeout: Duration,
 }
 
 enum GameControl {
@@ -271,9 +351,12 @@ enum GameControl {
 }
 
 impl GameServiceImpl {
-    pub fn new() -> Self {
+    /// Realtime games are aborted if the player to move doesn't make their first ply within
+    /// `first_ply_timeout`.
+    pub fn new(first_ply_timeout: Duration) -> Self {
         Self {
             games: Arc::new(DashMap::new()),
+            first_ply_timeout,
         }
     }
 
@@ -360,6 +443,7 @@ impl GameService for GameServiceImpl {
             game,
             metadata,
             requests: GameRequestSystem::new(),
+            premoves: GamePremoves::new(),
             events: Vec::new(),
         };
         self.games.insert(id, game_struct.clone());
@@ -367,6 +451,36 @@ impl GameService for GameServiceImpl {
         game_struct
     }
 
+    fn restore_game(
+        &self,
+        id: GameId,
+        metadata: GameMetadata,
+        snapshot: OngoingGameSnapshot,
+        now: Instant,
+    ) -> Option<OngoingGame> {
+        let game = TakOngoingGame::resume(
+            metadata.settings.clone(),
+            snapshot.actions,
+            snapshot.ply_times,
+            snapshot.time_info,
+            snapshot.clock_paused,
+            snapshot.turn_think_time,
+            now,
+        )?;
+
+        let game_struct = OngoingGame {
+            game_id: id,
+            game,
+            metadata,
+            requests: snapshot.requests,
+            premoves: snapshot.premoves,
+            events: snapshot.events,
+        };
+        self.games.insert(id, game_struct.clone());
+
+        Some(game_struct)
+    }
+
     fn get_game_by_id(&self, game_id: GameId) -> Option<OngoingGame> {
         self.games.get(&game_id).map(|entry| entry.clone())
     }
@@ -402,6 +516,7 @@ impl GameService for GameServiceImpl {
                     game_entry.events.push(GameEvent::new(
                         GameEventType::Action {
                             action: action.clone(),
+                            ply_time: finished_game.ply_times().last().cloned(),
                         },
                         time_info.clone(),
                     ));
@@ -424,6 +539,7 @@ impl GameService for GameServiceImpl {
                     game_entry.events.push(GameEvent::new(
                         GameEventType::Action {
                             action: action.clone(),
+                            ply_time: game_entry.game.ply_times().last().cloned(),
                         },
                         time_info.clone(),
                     ));
@@ -449,7 +565,7 @@ impl GameService for GameServiceImpl {
         self.game_player_action(
             game_id,
             player,
-            |game_entry, current_player| Ok(game_entry.game.resign_or_abandon(current_player, now)),
+            |game_entry, current_player| Ok(game_entry.game.resign(current_player, now)),
             |game_entry, _, finished_game| {
                 let time_info = finished_game.get_time_info();
                 game_entry.events.push(GameEvent::new(
@@ -487,26 +603,6 @@ impl GameService for GameServiceImpl {
         )
     }
 
-    fn abort_all_games(&self, now: Instant) -> Vec<FinishedGame> {
-        let mut finished_games = Vec::new();
-        self.games.retain(|_, game_entry| {
-            // Doesn't matter whether the game ended due to timeout or due to abort.
-            let finished_game = match game_entry.game.abort_forced(now) {
-                MaybeTimeout::Result(game) => game,
-                MaybeTimeout::Timeout(game) => game,
-            };
-            let time_info = finished_game.get_time_info();
-            game_entry.events.push(GameEvent::new(
-                GameEventType::GameOver(GameOverEventType::Aborted),
-                time_info,
-            ));
-            let finished_game = FinishedGame::new(game_entry, finished_game);
-            finished_games.push(finished_game);
-            false
-        });
-        finished_games
-    }
-
     fn set_request(
         &self,
         game_id: GameId,
@@ -551,6 +647,66 @@ impl GameService for GameServiceImpl {
         )
     }
 
+    fn set_premoves(
+        &self,
+        game_id: GameId,
+        player: PlayerId,
+        premoves: PremoveTree,
+        now: Instant,
+    ) -> GamePlayerActionResult<SetPremovesResult> {
+        self.game_player_action(
+            game_id,
+            player,
+            |game_entry, current_player| match game_entry.game.check_timeout(now) {
+                MaybeTimeout::Timeout(game) => Ok(MaybeTimeout::Timeout(game)),
+                MaybeTimeout::Result(()) => {
+                    if game_entry.metadata.settings.time_settings.is_realtime() {
+                        Err(SetPremovesResult::NotAsyncGame)
+                    } else if game_entry.game.current_player() == current_player {
+                        Err(SetPremovesResult::PlayersTurn)
+                    } else {
+                        premoves
+                            .validate(game_entry.game.base_game())
+                            .map(|()| MaybeTimeout::Result(premoves))
+                            .map_err(SetPremovesResult::InvalidPremoves)
+                    }
+                }
+            },
+            |game_entry, current_player, premoves| {
+                *game_entry.premoves.get_mut(current_player) = premoves;
+                (GameControl::Keep, SetPremovesResult::PremovesSet)
+            },
+        )
+    }
+
+    fn take_premove(&self, game_id: GameId) -> Option<(PlayerId, TakAction)> {
+        let mut entry = self.games.get_mut(&game_id)?;
+        let game_entry = &mut *entry;
+        let player = game_entry.game.current_player();
+        let last_action = game_entry.game.action_history().last()?;
+        let response = game_entry
+            .premoves
+            .get_mut(player)
+            .take_response(last_action)?;
+        Some((game_entry.metadata.get_player_id(player), response))
+    }
+
+    fn set_clock_paused(
+        &self,
+        game_id: GameId,
+        player: PlayerId,
+        paused: bool,
+        now: Instant,
+    ) -> bool {
+        let Some(mut game_entry) = self.games.get_mut(&game_id) else {
+            return false;
+        };
+        let Some(player) = game_entry.metadata.get_player(player) else {
+            return false;
+        };
+        game_entry.game.set_clock_paused(player, paused, now)
+    }
+
     fn accept_draw_request(
         &self,
         game_id: GameId,
@@ -635,6 +791,8 @@ impl GameService for GameServiceImpl {
                     let time_info = game_entry.game.get_time_info(now);
 
                     let undo_record = if did_undo {
+                        // the premoves were prepared for a different position
+                        game_entry.premoves.clear();
                         let ply_index = game_entry.game.action_history().len();
                         game_entry.events.push(GameEvent::new(
                             GameEventType::ActionUndone,
@@ -729,8 +887,41 @@ impl GameService for GameServiceImpl {
                     )
                 }
                 MaybeTimeout::Result(()) => {
-                    let time_info = game_entry.game.get_time_info(now);
-                    (GameControl::Keep, CheckTimeoutResult::NoTimeout(time_info))
+                    let first_ply_wait = if game_entry.metadata.settings.time_settings.is_realtime()
+                    {
+                        game_entry.game.first_ply_wait(now)
+                    } else {
+                        None
+                    };
+                    match first_ply_wait {
+                        Some(wait) if wait >= self.first_ply_timeout => {
+                            // the clocks were checked just before, so this can't time out
+                            let (MaybeTimeout::Result(finished_game)
+                            | MaybeTimeout::Timeout(finished_game)) =
+                                game_entry.game.abort_forced(now);
+                            let time_info = finished_game.get_time_info();
+                            game_entry.events.push(GameEvent::new(
+                                GameEventType::GameOver(GameOverEventType::Aborted),
+                                time_info,
+                            ));
+                            let finished_game = FinishedGame::new(game_entry, finished_game);
+                            (
+                                GameControl::Remove,
+                                CheckTimeoutResult::Aborted(finished_game),
+                            )
+                        }
+                        _ => {
+                            let time_info = game_entry.game.get_time_info(now);
+                            (
+                                GameControl::Keep,
+                                CheckTimeoutResult::NoTimeout {
+                                    time_info,
+                                    first_ply_remaining: first_ply_wait
+                                        .map(|wait| self.first_ply_timeout - wait),
+                                },
+                            )
+                        }
+                    }
                 }
             }
         })
@@ -760,7 +951,7 @@ impl GameService for GameServiceImpl {
                         timeout_duration - disconnected_duration,
                     ));
                 }
-                Ok(game_entry.game.resign_or_abandon(current_player, now))
+                Ok(game_entry.game.abandon(current_player, now))
             },
             |game_entry, _, finished_game| {
                 let time_info = finished_game.get_time_info();
@@ -0,0 +1,309 @@
+use tak_core::{InvalidActionReason, TakAction, TakOngoingBaseGame, TakPlayer, ptn::action_to_ptn};
+
+/// Upper bound for the number of branches in a premove tree.
+pub const MAX_PREMOVES: usize = 256;
+
+/// Conditional responses a player prepared for the next moves of the opponent.
+#[derive(Clone, Debug, Default)]
+pub struct PremoveTree {
+    pub branches: Vec<PremoveBranch>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PremoveBranch {
+    /// The opponent action this branch responds to.
+    pub condition: TakAction,
+    pub response: TakAction,
+    /// Responses to the opponent's action after `response`.
+    pub next: PremoveTree,
+}
+
+#[derive(Clone, Debug)]
+pub enum InvalidPremoveReason {
+    TooManyPremoves,
+    DuplicateCondition(TakAction),
+    InvalidCondition(TakAction, InvalidActionReason),
+    InvalidResponse(TakAction, InvalidActionReason),
+    /// Premoves can't follow an action that ends the game.
+    FollowsGameEnd(TakAction),
+}
+
+impl std::fmt::Display for InvalidPremoveReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidPremoveReason::TooManyPremoves => {
+                write!(f, "At most {} premoves are allowed", MAX_PREMOVES)
+            }
+            InvalidPremoveReason::DuplicateCondition(action) => {
+                write!(f, "Multiple responses to {}", action_to_ptn(action))
+            }
+            InvalidPremoveReason::InvalidCondition(action, reason) => {
+                write!(f, "Invalid condition {}: {}", action_to_ptn(action), reason)
+            }
+            InvalidPremoveReason::InvalidResponse(action, reason) => {
+                write!(f, "Invalid response {}: {}", action_to_ptn(action), reason)
+            }
+            InvalidPremoveReason::FollowsGameEnd(action) => {
+                write!(
+                    f,
+                    "{} ends the game, nothing can follow it",
+                    action_to_ptn(action)
+                )
+            }
+        }
+    }
+}
+
+impl PremoveTree {
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.branches
+            .iter()
+            .map(|branch| 1 + branch.next.len())
+            .sum()
+    }
+
+    /// Returns the prepared response to `action` and keeps only the premoves following it.
+    /// The whole tree is dropped if the opponent played something else.
+    pub fn take_response(&mut self, action: &TakAction) -> Option<TakAction> {
+        let branches = std::mem::take(&mut self.branches);
+        let branch = branches
+            .into_iter()
+            .find(|branch| branch.condition == *action)?;
+        *self = branch.next;
+        Some(branch.response)
+    }
+
+    /// Checks that every branch can be played, starting from `game` with the opponent to move.
+    pub fn validate(&self, game: &TakOngoingBaseGame) -> Result<(), InvalidPremoveReason> {
+        if self.len() > MAX_PREMOVES {
+            return Err(InvalidPremoveReason::TooManyPremoves);
+        }
+        let mut game = game.clone();
+        self.validate_branches(&mut game)
+    }
+
+    fn validate_branches(&self, game: &mut TakOngoingBaseGame) -> Result<(), InvalidPremoveReason> {
+        for (i, branch) in self.branches.iter().enumerate() {
+            if self.branches[..i]
+                .iter()
+                .any(|other| other.condition == branch.condition)
+            {
+                return Err(InvalidPremoveReason::DuplicateCondition(
+                    branch.condition.clone(),
+                ));
+            }
+            branch.validate(game)?;
+        }
+        Ok(())
+    }
+}
+
+impl PremoveBranch {
+    fn validate(&self, game: &mut TakOngoingBaseGame) -> Result<(), InvalidPremoveReason> {
+        game.can_do_action(&self.condition)
+            .map_err(|e| InvalidPremoveReason::InvalidCondition(self.condition.clone(), e))?;
+        let res = if matches!(game.make_action(self.condition.clone()), Ok(Some(_))) {
+            Err(InvalidPremoveReason::FollowsGameEnd(self.condition.clone()))
+        } else {
+            self.validate_response(game)
+        };
+        game.unmake_action();
+        res
+    }
+
+    fn validate_response(&self, game: &mut TakOngoingBaseGame) -> Result<(), InvalidPremoveReason> {
+        game.can_do_action(&self.response)
+            .map_err(|e| InvalidPremoveReason::InvalidResponse(self.response.clone(), e))?;
+        let ends_game = matches!(game.make_action(self.response.clone()), Ok(Some(_)));
+        let res = if ends_game && !self.next.is_empty() {
+            Err(InvalidPremoveReason::FollowsGameEnd(self.response.clone()))
+        } else {
+            self.next.validate_branches(game)
+        };
+        game.unmake_action();
+        res
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GamePremoves {
+    pub white_premoves: PremoveTree,
+    pub black_premoves: PremoveTree,
+}
+
+impl GamePremoves {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, player: TakPlayer) -> &PremoveTree {
+        match player {
+            TakPlayer::White => &self.white_premoves,
+            TakPlayer::Black => &self.black_premoves,
+        }
+    }
+
+    pub fn get_mut(&mut self, player: TakPlayer) -> &mut PremoveTree {
+        match player {
+            TakPlayer::White => &mut self.white_premoves,
+            TakPlayer::Black => &mut self.black_premoves,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.white_premoves = PremoveTree::default();
+        self.black_premoves = PremoveTree::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tak_core::{
+        TakAsyncTimeControl, TakBaseGameSettings, TakGameSettings, TakOpening, TakReserve,
+        TakRules, TakTimeSettings, ptn::action_from_ptn,
+    };
+
+    use super::*;
+    use crate::domain::{
+        GameId, PlayerId,
+        game::{
+            DoActionResult, GamePlayerActionResult, GameService, GameServiceImpl,
+            SetPremovesResult, request::GameRequest,
+        },
+    };
+
+    fn action(ptn: &str) -> TakAction {
+        action_from_ptn(ptn).unwrap()
+    }
+
+    fn branch(condition: &str, response: &str, next: Vec<PremoveBranch>) -> PremoveBranch {
+        PremoveBranch {
+            condition: action(condition),
+            response: action(response),
+            next: PremoveTree { branches: next },
+        }
+    }
+
+    fn tree(branches: Vec<PremoveBranch>) -> PremoveTree {
+        PremoveTree { branches }
+    }
+
+    fn settings() -> TakGameSettings {
+        TakGameSettings {
+            base: TakBaseGameSettings {
+                board_size: 5,
+                half_komi: 0,
+                reserve: TakReserve::from_size(5).unwrap(),
+                black_reserve: None,
+                opening: TakOpening::Swap,
+                rules: TakRules::default(),
+            },
+            time_settings: TakTimeSettings::Async(TakAsyncTimeControl {
+                contingent: Duration::from_secs(86400),
+            }),
+        }
+    }
+
+    /// A game after white's first ply, with black to move.
+    fn game() -> TakOngoingBaseGame {
+        let mut game = TakOngoingBaseGame::new(settings().base);
+        game.do_action(action("a1")).unwrap();
+        game
+    }
+
+    #[test]
+    fn test_validate() {
+        let game = game();
+        let valid = tree(vec![
+            branch("e5", "e4", vec![branch("d5", "d4", vec![])]),
+            branch("a2", "b2", vec![]),
+        ]);
+        assert_eq!(valid.len(), 3);
+        assert!(valid.validate(&game).is_ok());
+
+        assert!(matches!(
+            tree(vec![branch("a1", "e5", vec![])]).validate(&game),
+            Err(InvalidPremoveReason::InvalidCondition(..))
+        ));
+        assert!(matches!(
+            tree(vec![branch("e5", "e5", vec![])]).validate(&game),
+            Err(InvalidPremoveReason::InvalidResponse(..))
+        ));
+        // illegal actions deeper in the tree are found as well
+        assert!(matches!(
+            tree(vec![branch("e5", "e4", vec![branch("e4", "d4", vec![])])]).validate(&game),
+            Err(InvalidPremoveReason::InvalidCondition(..))
+        ));
+        assert!(matches!(
+            tree(vec![branch("e5", "e4", vec![]), branch("e5", "d4", vec![])]).validate(&game),
+            Err(InvalidPremoveReason::DuplicateCondition(..))
+        ));
+
+        let too_many = tree(vec![branch("e5", "e4", vec![]); MAX_PREMOVES + 1]);
+        assert!(matches!(
+            too_many.validate(&game),
+            Err(InvalidPremoveReason::TooManyPremoves)
+        ));
+    }
+
+    #[test]
+    fn test_take_response() {
+        let mut premoves = tree(vec![
+            branch("e5", "e4", vec![branch("d5", "d4", vec![])]),
+            branch("a2", "b2", vec![]),
+        ]);
+        assert_eq!(premoves.take_response(&action("e5")), Some(action("e4")));
+        assert_eq!(premoves.len(), 1);
+        assert_eq!(premoves.branches[0].condition, action("d5"));
+        assert_eq!(premoves.take_response(&action("d5")), Some(action("d4")));
+        assert!(premoves.is_empty());
+
+        let mut premoves = tree(vec![
+            branch("e5", "e4", vec![branch("d5", "d4", vec![])]),
+            branch("a2", "b2", vec![]),
+        ]);
+        assert_eq!(premoves.take_response(&action("c3")), None);
+        assert!(premoves.is_empty());
+    }
+
+    #[test]
+    fn test_premoves_cleared_on_undo() {
+        let service = GameServiceImpl::new(Duration::from_secs(60));
+        let white = PlayerId(uuid::Uuid::new_v4());
+        let black = PlayerId(uuid::Uuid::new_v4());
+        let game_id = GameId::new(1);
+        let metadata =
+            service.create_game_metadata(chrono::Utc::now(), white, black, false, settings(), None);
+        service.create_game(game_id, metadata);
+        let now = Instant::now();
+        for (player, ptn) in [(white, "a1"), (black, "e5")] {
+            assert!(matches!(
+                service.do_action(game_id, player, action(ptn), now),
+                GamePlayerActionResult::Result(DoActionResult::ActionPerformed(_))
+            ));
+        }
+        assert!(matches!(
+            service.set_premoves(game_id, black, tree(vec![branch("e4", "d5", vec![])]), now),
+            GamePlayerActionResult::Result(SetPremovesResult::PremovesSet)
+        ));
+
+        // black takes back e5, the premoves were prepared for the position after it
+        assert!(matches!(
+            service.set_request(game_id, black, GameRequest::Undo(true), now),
+            GamePlayerActionResult::Result(Some(_))
+        ));
+        assert!(matches!(
+            service.accept_undo_request(game_id, white, now),
+            GamePlayerActionResult::Result(Some((_, _, Some(_))))
+        ));
+        let game = service.get_game_by_id(game_id).unwrap();
+        assert_eq!(game.game.action_history().len(), 1);
+        assert!(game.premoves.get(TakPlayer::Black).is_empty());
+    }
+}
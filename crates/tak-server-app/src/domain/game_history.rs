@@ -4,7 +4,7 @@ use tak_core::{TakGameResult, TakPlayer};
 use crate::domain::{
     GameId, MatchId, PaginatedResponse, Pagination, PlayerId, RepoError, RepoRetrieveError,
     SortOrder,
-    game::{FinishedGame, GameEvent, GameMetadata},
+    game::{FinishedGame, GameEvent, GameMetadata, OngoingGameSnapshot},
 };
 
 pub struct GameRecord {
@@ -89,6 +89,17 @@ pub trait GameRepository {
         game_id: GameId,
         update: GameFinishedUpdate,
     ) -> Result<(), RepoRetrieveError>;
+    /// Stores the current state of a game that hasn't finished yet. Does nothing once the game
+    /// is finished.
+    async fn update_ongoing_game(
+        &self,
+        game_id: GameId,
+        snapshot: OngoingGameSnapshot,
+    ) -> Result<(), RepoRetrieveError>;
+    /// Games that haven't finished yet together with their last stored state.
+    async fn get_ongoing_games(
+        &self,
+    ) -> Result<Vec<(GameId, GameMetadata, OngoingGameSnapshot)>, RepoError>;
     async fn get_game_record(&self, game_id: GameId) -> Result<GameRecord, RepoRetrieveError>;
     async fn query_games(
         &self,
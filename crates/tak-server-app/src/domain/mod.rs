@@ -1,4 +1,6 @@
 pub mod chat;
+pub mod correspondence;
+pub mod drain;
 pub mod event;
 pub mod game;
 pub mod game_history;
@@ -1,7 +1,9 @@
 use country_code_enum::CountryCode;
 use image::DynamicImage;
 
-use crate::domain::{AccountId, RepoError, RepoRetrieveError};
+use crate::domain::{
+    AccountId, RepoError, RepoRetrieveError, correspondence::CorrespondenceSettings,
+};
 
 #[async_trait::async_trait]
 pub trait AccountProfileRepository {
@@ -32,6 +34,8 @@ impl ProfilePictureVersion {
 pub struct AccountProfile {
     pub country: Option<CountryCode>,
     pub profile_picture_version: Option<ProfilePictureVersion>,
+    /// Private to the account, not part of the public profile.
+    pub correspondence: CorrespondenceSettings,
 }
 
 impl AccountProfile {
@@ -42,6 +46,7 @@ impl AccountProfile {
         Self {
             country,
             profile_picture_version,
+            correspondence: CorrespondenceSettings::default(),
         }
     }
 }
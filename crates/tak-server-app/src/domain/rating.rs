@@ -102,6 +102,9 @@ impl RatingServiceImpl {
         if ply_count <= 6 {
             return false;
         }
+        if !settings.is_rateable() {
+            return false;
+        }
         let TakTimeSettings::Realtime(time_control) = &settings.time_settings else {
             return false;
         };
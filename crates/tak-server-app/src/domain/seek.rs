@@ -17,6 +17,7 @@ pub struct Seek {
 
 pub enum CreateSeekError {
     InvalidGameSettings,
+    ServerDraining,
 }
 
 pub trait SeekService {
@@ -164,8 +165,8 @@ impl SeekService for SeekServiceImpl {
                 id: seek_id,
                 creator_id: player,
                 color,
+                is_rated: is_rated && game_settings.is_rateable(),
                 game_settings,
-                is_rated,
             }))
     }
 
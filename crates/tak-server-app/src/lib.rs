@@ -1,8 +1,10 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use crate::{
     domain::{
         chat::{ChatRepository, ChatRoomServiceImpl, RustrictContentPolicy},
+        correspondence::CorrespondenceServiceImpl,
+        drain::DrainServiceImpl,
         event::EventRepository,
         game::GameServiceImpl,
         game_history::{GameHistoryServiceImpl, GameRepository},
@@ -24,6 +26,7 @@ use crate::{
         player_mapping::PlayerAccountMappingRepository,
     },
     processes::{
+        correspondence_runner::{CorrespondenceRunner, CorrespondenceRunnerImpl},
         disconnect_timeout_runner::DisconnectTimeoutRunnerImpl,
         game_timeout_runner::GameTimeoutRunnerImpl,
     },
@@ -44,11 +47,14 @@ use crate::{
         },
         events::list::{ListEventsUseCase, ListEventsUseCaseImpl},
         gameplay::{
+            correspondence::CorrespondenceWorkflowImpl,
             do_action::{DoActionUseCase, DoActionUseCaseImpl},
             finalize_game::FinalizeGameWorkflowImpl,
             get::{GetOngoingGameUseCase, GetOngoingGameUseCaseImpl},
             list::{ListOngoingGameUseCase, ListOngoingGameUseCaseImpl},
             observe::{ObserveGameUseCase, ObserveGameUseCaseImpl},
+            persist::PersistGameWorkflowImpl,
+            restore::{RestoreGamesWorkflow, RestoreGamesWorkflowImpl},
             timeout::ObserveGameTimeoutUseCaseImpl,
         },
         history::query::{GameHistoryQueryUseCase, GameHistoryQueryUseCaseImpl},
@@ -179,15 +185,18 @@ pub async fn build_application<
     tournament_player_registration_repository: Arc<TPR>,
     match_repository: Arc<MR>,
     tournament_round_repository: Arc<TRR>,
+    first_ply_timeout: Duration,
 ) -> Application {
     let seek_service = Arc::new(SeekServiceImpl::new());
-    let game_service = Arc::new(GameServiceImpl::new());
+    let game_service = Arc::new(GameServiceImpl::new(first_ply_timeout));
     let spectator_service = Arc::new(SpectatorServiceImpl::new());
     let chat_room_service = Arc::new(ChatRoomServiceImpl::new());
     let game_history_service = Arc::new(GameHistoryServiceImpl::new());
     let rating_service = Arc::new(RatingServiceImpl::new());
     let chat_content_policy = Arc::new(RustrictContentPolicy::new());
     let match_readiness_service = Arc::new(MatchReadinessServiceImpl::new());
+    let drain_service = Arc::new(DrainServiceImpl::new());
+    let correspondence_service = Arc::new(CorrespondenceServiceImpl::new());
 
     let policies = ModerationPolicies {
         ban_policy: Arc::new(AdminAccountPolicy),
@@ -249,6 +258,11 @@ pub async fn build_application<
         observe_game_timeout_use_case.clone(),
     ));
 
+    let persist_game_workflow = Arc::new(PersistGameWorkflowImpl::new(
+        game_service.clone(),
+        game_repository.clone(),
+    ));
+
     let create_game_from_match_workflow = Arc::new(CreateGameFromMatchWorkflowImpl::new(
         match_repository.clone(),
         game_history_service.clone(),
@@ -257,19 +271,46 @@ pub async fn build_application<
         game_timeout_scheduler.clone(),
         listener_notification_port.clone(),
         get_snapshot_workflow.clone(),
+        drain_service.clone(),
+        persist_game_workflow.clone(),
     ));
 
     let shutdown_workflow = Arc::new(ShutdownWorkflowImpl::new(
-        finalize_game_workflow.clone(),
+        drain_service.clone(),
+        game_service.clone(),
+        listener_notification_port.clone(),
+        persist_game_workflow.clone(),
+    ));
+
+    let correspondence_workflow = Arc::new(CorrespondenceWorkflowImpl::new(
         game_service.clone(),
+        correspondence_service.clone(),
+        email_port.clone(),
+        authentication_service.clone(),
+        player_resolver_service.clone(),
+        profile_repository.clone(),
+        persist_game_workflow.clone(),
     ));
 
+    RestoreGamesWorkflowImpl::new(
+        game_service.clone(),
+        game_repository.clone(),
+        game_timeout_scheduler.clone(),
+    )
+    .restore_games()
+    .await;
+
+    CorrespondenceRunner::start(Arc::new(CorrespondenceRunnerImpl::new(
+        correspondence_workflow.clone(),
+    )));
+
     let application = Application {
         seek_accept_use_case: Arc::new(AcceptSeekUseCaseImpl::new(
             seek_service.clone(),
             match_repository.clone(),
             listener_notification_port.clone(),
             create_game_from_match_workflow.clone(),
+            drain_service.clone(),
         )),
         seek_cancel_use_case: Arc::new(CancelSeekUseCaseImpl::new(
             seek_service.clone(),
@@ -278,6 +319,7 @@ pub async fn build_application<
         seek_create_use_case: Arc::new(CreateSeekUseCaseImpl::new(
             seek_service.clone(),
             listener_notification_port.clone(),
+            drain_service.clone(),
         )),
         seek_get_use_case: Arc::new(GetSeekUseCaseImpl::new(seek_service.clone())),
         seek_list_use_case: Arc::new(ListSeeksUseCaseImpl::new(seek_service.clone())),
@@ -319,6 +361,8 @@ pub async fn build_application<
             game_service.clone(),
             notify_player_workflow.clone(),
             finalize_game_workflow.clone(),
+            persist_game_workflow.clone(),
+            correspondence_workflow.clone(),
         )),
         game_get_ongoing_use_case: Arc::new(GetOngoingGameUseCaseImpl::new(game_service.clone())),
         game_list_ongoing_use_case: Arc::new(ListOngoingGameUseCaseImpl::new(game_service.clone())),
@@ -385,6 +429,7 @@ pub async fn build_application<
             tournament_player_registration_repository.clone(),
             tournament_round_repository.clone(),
             rating_repository.clone(),
+            drain_service.clone(),
         )),
 
         match_get_use_case: Arc::new(GetMatchUseCaseImpl::new(match_repository.clone())),
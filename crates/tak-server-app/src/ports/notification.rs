@@ -1,4 +1,4 @@
-use tak_core::{TakAction, TakGameResult, TakTimeInfo};
+use tak_core::{TakAction, TakGameResult, TakPos, TakTimeInfo};
 
 use crate::{
     domain::{
@@ -69,6 +69,7 @@ pub enum ListenerMatchEventType {
 pub enum ListenerGameMessageType {
     GameOver {
         game_result: TakGameResult,
+        winning_road: Vec<TakPos>,
     },
     GameAction {
         player_id: PlayerId,
@@ -85,6 +86,10 @@ pub enum ListenerGameMessageType {
 
 #[derive(Clone, Debug)]
 pub enum ServerAlertMessage {
-    Shutdown,
+    /// The server stops at `deadline`, no new games can be started until then.
+    Shutdown {
+        deadline: chrono::DateTime<chrono::Utc>,
+    },
+    ShutdownCancelled,
     Custom(String),
 }
@@ -0,0 +1,32 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::workflow::gameplay::correspondence::CorrespondenceWorkflow;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+pub trait CorrespondenceRunner {
+    fn start(this: Arc<Self>);
+}
+
+pub struct CorrespondenceRunnerImpl<W: CorrespondenceWorkflow + Send + Sync + 'static> {
+    workflow: Arc<W>,
+}
+
+impl<W: CorrespondenceWorkflow + Send + Sync + 'static> CorrespondenceRunner
+    for CorrespondenceRunnerImpl<W>
+{
+    fn start(this: Arc<Self>) {
+        tokio::spawn(async move {
+            loop {
+                this.workflow.run_checks().await;
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+}
+
+impl<W: CorrespondenceWorkflow + Send + Sync + 'static> CorrespondenceRunnerImpl<W> {
+    pub fn new(workflow: Arc<W>) -> Self {
+        Self { workflow }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod correspondence_runner;
 pub mod disconnect_timeout_runner;
 pub mod game_timeout_runner;
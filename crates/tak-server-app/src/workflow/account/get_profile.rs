@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::{
     domain::{
         AccountId, RepoRetrieveError,
+        correspondence::CorrespondenceSettings,
         profile::{AccountProfileRepository, ProfilePicture, ProfilePictureRepository},
     },
     workflow::account::AccountProfileView,
@@ -18,6 +19,10 @@ pub trait GetProfileUseCase {
         &self,
         account_id: &AccountId,
     ) -> Result<Option<ProfilePicture>, GetProfileError>;
+    async fn get_correspondence_settings(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<CorrespondenceSettings, GetProfileError>;
 }
 
 pub enum GetProfileError {
@@ -86,4 +91,26 @@ impl<
             }
         }
     }
+
+    async fn get_correspondence_settings(
+        &self,
+        account_id: &AccountId,
+    ) -> Result<CorrespondenceSettings, GetProfileError> {
+        match self
+            .profile_information_repo
+            .get_profile_information(account_id)
+            .await
+        {
+            Ok(profile_information) => Ok(profile_information.correspondence),
+            Err(RepoRetrieveError::NotFound) => Ok(CorrespondenceSettings::default()),
+            Err(RepoRetrieveError::StorageError(e)) => {
+                tracing::error!(
+                    "Failed to retrieve correspondence settings for account {}: {}",
+                    account_id,
+                    e
+                );
+                Err(GetProfileError::RepositoryError)
+            }
+        }
+    }
 }
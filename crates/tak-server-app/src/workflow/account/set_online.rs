@@ -1,7 +1,5 @@
 use std::sync::Arc;
 
-use tak_core::TakTimeSettings;
-
 use crate::{
     domain::{
         AccountId,
@@ -139,10 +137,7 @@ impl<
             }
         };
         let cancelled_seeks = self.seek_service.cancel_player_seeks(player_id, |seek| {
-            matches!(
-                seek.game_settings.time_settings,
-                TakTimeSettings::Realtime(_)
-            )
+            seek.game_settings.time_settings.is_realtime()
         });
         for cancelled_seek in cancelled_seeks {
             let message = ListenerMessage::SeekCancelled {
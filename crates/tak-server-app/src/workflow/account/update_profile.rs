@@ -5,6 +5,7 @@ use country_code_enum::CountryCode;
 use crate::{
     domain::{
         AccountId, RepoError, RepoRetrieveError,
+        correspondence::{CorrespondenceSettings, InvalidVacationReason},
         profile::{
             AccountProfile, AccountProfileRepository, ProfilePicture, ProfilePictureRepository,
             ProfilePictureVersion,
@@ -26,11 +27,18 @@ pub trait UpdateProfileUseCase {
         account_id: &AccountId,
         picture_data: DynamicImage,
     ) -> Result<ProfilePictureVersion, UpdateProfileError>;
+    /// Vacations take effect on the account's async games within a minute.
+    async fn update_correspondence_settings(
+        &self,
+        account_id: &AccountId,
+        settings: CorrespondenceSettings,
+    ) -> Result<(), UpdateProfileError>;
 }
 
 pub enum UpdateProfileError {
     AccountNotFound,
     RepositoryError,
+    InvalidVacation(InvalidVacationReason),
 }
 
 pub struct UpdateProfileUseCaseImpl<
@@ -154,8 +162,8 @@ impl<
             .insert_profile_information(
                 account_id,
                 AccountProfile {
-                    country: profile_data.country,
                     profile_picture_version: Some(new_version),
+                    ..profile_data
                 },
             )
             .await
@@ -192,4 +200,53 @@ impl<
             }
         }
     }
+
+    async fn update_correspondence_settings(
+        &self,
+        account_id: &AccountId,
+        mut settings: CorrespondenceSettings,
+    ) -> Result<(), UpdateProfileError> {
+        let account = match self.authentication_port.get_account(account_id).await {
+            Some(acc) => acc,
+            None => return Err(UpdateProfileError::AccountNotFound),
+        };
+        if account.is_guest() {
+            return Err(UpdateProfileError::AccountNotFound);
+        }
+        settings
+            .validate(chrono::Utc::now())
+            .map_err(UpdateProfileError::InvalidVacation)?;
+        let mut profile_data = match self
+            .profile_information_repo
+            .get_profile_information(account_id)
+            .await
+        {
+            Ok(data) => data,
+            Err(RepoRetrieveError::NotFound) => AccountProfile::new(None, None),
+            Err(RepoRetrieveError::StorageError(e)) => {
+                tracing::error!(
+                    "Failed to retrieve profile information for account {}: {}",
+                    account_id,
+                    e
+                );
+                return Err(UpdateProfileError::RepositoryError);
+            }
+        };
+        profile_data.correspondence = settings;
+        match self
+            .profile_information_repo
+            .insert_profile_information(account_id, profile_data)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(RepoError::StorageError(e)) => {
+                tracing::error!(
+                    "Failed to update correspondence settings for account {}: {}",
+                    account_id,
+                    e
+                );
+                Err(UpdateProfileError::RepositoryError)
+            }
+        }
+    }
 }
@@ -0,0 +1,307 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use tak_core::{TakPlayer, TakTimeSettings};
+
+use crate::{
+    domain::{
+        AccountId, GameId, PlayerId, RepoRetrieveError,
+        correspondence::{CorrespondenceService, CorrespondenceSettings},
+        game::{GameService, OngoingGame},
+        profile::AccountProfileRepository,
+    },
+    ports::{authentication::AuthenticationPort, email::EmailPort},
+    services::player_resolver::PlayerResolverService,
+    workflow::gameplay::persist::PersistGameWorkflow,
+};
+
+/// How long turn notifications are collected before they are sent, players who move in the
+/// meantime don't get one.
+const TURN_NOTIFICATION_DELAY: Duration = Duration::from_secs(10 * 60);
+const MAX_TIMEOUT_WARNING_THRESHOLD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[async_trait::async_trait]
+pub trait CorrespondenceWorkflow {
+    /// Queues a notification for the player to move if the game is async.
+    fn notify_turn(&self, game_id: GameId);
+    /// Pauses the clocks of players on vacation and sends due turn notifications and warnings
+    /// for clocks that are about to run out.
+    async fn run_checks(&self);
+}
+
+pub struct CorrespondenceWorkflowImpl<
+    G: GameService,
+    C: CorrespondenceService,
+    E: EmailPort,
+    A: AuthenticationPort,
+    R: PlayerResolverService,
+    PF: AccountProfileRepository,
+    P: PersistGameWorkflow,
+> {
+    game_service: Arc<G>,
+    correspondence_service: Arc<C>,
+    email_port: Arc<E>,
+    authentication_service: Arc<A>,
+    player_resolver_service: Arc<R>,
+    profile_repository: Arc<PF>,
+    persist_game_workflow: Arc<P>,
+}
+
+type SettingsCache = HashMap<PlayerId, Option<(AccountId, CorrespondenceSettings)>>;
+
+impl<
+    G: GameService,
+    C: CorrespondenceService,
+    E: EmailPort,
+    A: AuthenticationPort,
+    R: PlayerResolverService,
+    PF: AccountProfileRepository,
+    P: PersistGameWorkflow,
+> CorrespondenceWorkflowImpl<G, C, E, A, R, PF, P>
+{
+    pub fn new(
+        game_service: Arc<G>,
+        correspondence_service: Arc<C>,
+        email_port: Arc<E>,
+        authentication_service: Arc<A>,
+        player_resolver_service: Arc<R>,
+        profile_repository: Arc<PF>,
+        persist_game_workflow: Arc<P>,
+    ) -> Self {
+        Self {
+            game_service,
+            correspondence_service,
+            email_port,
+            authentication_service,
+            player_resolver_service,
+            profile_repository,
+            persist_game_workflow,
+        }
+    }
+
+    async fn get_settings(
+        &self,
+        player_id: PlayerId,
+        cache: &mut SettingsCache,
+    ) -> Option<(AccountId, CorrespondenceSettings)> {
+        if let Some(settings) = cache.get(&player_id) {
+            return settings.clone();
+        }
+        let settings = match self
+            .player_resolver_service
+            .resolve_account_id_by_player_id(player_id)
+            .await
+        {
+            Ok(account_id) => match self
+                .profile_repository
+                .get_profile_information(&account_id)
+                .await
+            {
+                Ok(profile) => Some((account_id, profile.correspondence)),
+                Err(RepoRetrieveError::NotFound) => {
+                    Some((account_id, CorrespondenceSettings::default()))
+                }
+                Err(RepoRetrieveError::StorageError(e)) => {
+                    tracing::error!(
+                        "Failed to retrieve correspondence settings of player {}: {}",
+                        player_id,
+                        e
+                    );
+                    None
+                }
+            },
+            Err(_) => {
+                tracing::error!("Failed to resolve account of player {}", player_id);
+                None
+            }
+        };
+        cache.insert(player_id, settings.clone());
+        settings
+    }
+
+    async fn send_email(&self, account_id: &AccountId, subject: &str, content: &str) {
+        let Some(account) = self.authentication_service.get_account(account_id).await else {
+            return;
+        };
+        let Some(email) = &account.email else {
+            return;
+        };
+        let body = format!(
+            "Hello {},\n\n\
+        {}\n\n\
+        You can turn off these emails in your account settings.\n\n\
+        Best regards,\n\
+        The Playtak Team",
+            account.username, content
+        );
+        if let Err(e) = self.email_port.send_email(email, subject, &body) {
+            tracing::error!("Failed to send correspondence email to {}: {:?}", email, e);
+        }
+    }
+
+    async fn apply_vacations(&self, games: &[OngoingGame], cache: &mut SettingsCache) {
+        let now = Instant::now();
+        let date = Utc::now();
+        for game in games {
+            for player in [TakPlayer::White, TakPlayer::Black] {
+                let player_id = game.metadata.get_player_id(player);
+                let on_vacation = self
+                    .get_settings(player_id, cache)
+                    .await
+                    .is_some_and(|(_, settings)| settings.is_on_vacation(date));
+                if self
+                    .game_service
+                    .set_clock_paused(game.game_id, player_id, on_vacation, now)
+                {
+                    tracing::info!(
+                        "Clock of player {} in game {} is {}",
+                        player_id,
+                        game.game_id,
+                        if on_vacation { "paused" } else { "resumed" }
+                    );
+                    self.persist_game_workflow.persist_game(game.game_id).await;
+                }
+            }
+        }
+    }
+
+    async fn send_turn_notifications(&self, games: &[OngoingGame], cache: &mut SettingsCache) {
+        let Some(since) = Instant::now().checked_sub(TURN_NOTIFICATION_DELAY) else {
+            return;
+        };
+        let date = Utc::now();
+        for (player_id, game_ids) in self
+            .correspondence_service
+            .take_due_turn_notifications(since)
+        {
+            // the player might have moved or the game might have ended in the meantime
+            let game_ids = game_ids
+                .into_iter()
+                .filter(|game_id| {
+                    games.iter().any(|game| {
+                        game.game_id == *game_id
+                            && game.metadata.get_player_id(game.game.current_player()) == player_id
+                    })
+                })
+                .collect::<Vec<_>>();
+            if game_ids.is_empty() {
+                continue;
+            }
+            let Some((account_id, settings)) = self.get_settings(player_id, cache).await else {
+                continue;
+            };
+            if !settings.notifications.turn_emails || settings.is_on_vacation(date) {
+                continue;
+            }
+            let game_list = game_ids
+                .iter()
+                .map(|game_id| format!("- Game {}", game_id))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let content = format!("It's your turn in the following games:\n{}", game_list);
+            self.send_email(&account_id, "Playtak: It's your turn", &content)
+                .await;
+        }
+    }
+
+    async fn send_timeout_warnings(&self, games: &[OngoingGame], cache: &mut SettingsCache) {
+        let now = Instant::now();
+        for game in games {
+            let TakTimeSettings::Async(time_control) = &game.metadata.settings.time_settings else {
+                continue;
+            };
+            let player = game.game.current_player();
+            if game.game.is_clock_paused(player) {
+                continue;
+            }
+            let time_info = game.get_time_info(now);
+            let remaining = match player {
+                TakPlayer::White => time_info.white_remaining,
+                TakPlayer::Black => time_info.black_remaining,
+            };
+            if remaining > (time_control.contingent / 4).min(MAX_TIMEOUT_WARNING_THRESHOLD) {
+                continue;
+            }
+            let ply_index = game.game.action_history().len();
+            if !self
+                .correspondence_service
+                .mark_timeout_warning(game.game_id, ply_index)
+            {
+                continue;
+            }
+            let player_id = game.metadata.get_player_id(player);
+            let Some((account_id, settings)) = self.get_settings(player_id, cache).await else {
+                continue;
+            };
+            if !settings.notifications.timeout_warning_emails {
+                continue;
+            }
+            let minutes = remaining.as_secs() / 60;
+            let content = format!(
+                "Your clock in game {} runs out in {}h {}m, make your move before you lose on time.",
+                game.game_id,
+                minutes / 60,
+                minutes % 60
+            );
+            self.send_email(&account_id, "Playtak: Your time is running out", &content)
+                .await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<
+    G: GameService + Send + Sync + 'static,
+    C: CorrespondenceService + Send + Sync + 'static,
+    E: EmailPort + Send + Sync + 'static,
+    A: AuthenticationPort + Send + Sync + 'static,
+    R: PlayerResolverService + Send + Sync + 'static,
+    PF: AccountProfileRepository + Send + Sync + 'static,
+    P: PersistGameWorkflow + Send + Sync + 'static,
+> CorrespondenceWorkflow for CorrespondenceWorkflowImpl<G, C, E, A, R, PF, P>
+{
+    fn notify_turn(&self, game_id: GameId) {
+        let Some(game) = self.game_service.get_game_by_id(game_id) else {
+            return;
+        };
+        if game.metadata.settings.time_settings.is_realtime() {
+            return;
+        }
+        let player = game.game.current_player();
+        self.correspondence_service
+            .cancel_turn_notification(game.metadata.get_player_id(player.opponent()), game_id);
+        self.correspondence_service.queue_turn_notification(
+            game.metadata.get_player_id(player),
+            game_id,
+            Instant::now(),
+        );
+    }
+
+    async fn run_checks(&self) {
+        let games = self
+            .game_service
+            .get_games()
+            .filter(|game| !game.metadata.settings.time_settings.is_realtime())
+            .collect::<Vec<_>>();
+        self.correspondence_service.retain_games(
+            &games
+                .iter()
+                .map(|game| game.game_id)
+                .collect::<HashSet<_>>(),
+        );
+
+        let mut cache = SettingsCache::new();
+        self.apply_vacations(&games, &mut cache).await;
+        self.send_turn_notifications(&games, &mut cache).await;
+        // re-read the games so paused clocks are taken into account
+        let games = games
+            .iter()
+            .filter_map(|game| self.game_service.get_game_by_id(game.game_id))
+            .collect::<Vec<_>>();
+        self.send_timeout_warnings(&games, &mut cache).await;
+    }
+}
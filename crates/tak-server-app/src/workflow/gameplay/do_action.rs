@@ -6,13 +6,18 @@ use crate::{
     domain::{
         GameId, PlayerId,
         game::{
-            DoActionResult, FinishedGame, GamePlayerActionResult, GameService,
+            DoActionResult, FinishedGame, GamePlayerActionResult, GameService, SetPremovesResult,
+            premove::{InvalidPremoveReason, PremoveTree},
             request::{GameRequest, GameRequestType},
         },
     },
     ports::notification::{ListenerGameMessageType, ListenerMessage},
     workflow::{
-        gameplay::finalize_game::FinalizeGameWorkflow, player::notify_player::NotifyPlayerWorkflow,
+        gameplay::{
+            correspondence::CorrespondenceWorkflow, finalize_game::FinalizeGameWorkflow,
+            persist::PersistGameWorkflow,
+        },
+        player::notify_player::NotifyPlayerWorkflow,
     },
 };
 
@@ -36,6 +41,18 @@ pub trait DoActionUseCase {
         player_id: PlayerId,
         request_type: GameRequestType,
     ) -> ActionResult<HandleRequestError>;
+    /// Registers conditional responses that are played automatically once the opponent moves.
+    async fn set_premoves(
+        &self,
+        game_id: GameId,
+        player_id: PlayerId,
+        premoves: PremoveTree,
+    ) -> ActionResult<SetPremovesError>;
+    fn get_premoves(
+        &self,
+        game_id: GameId,
+        player_id: PlayerId,
+    ) -> Result<PremoveTree, PlayerActionError>;
     async fn resign(&self, game_id: GameId, player_id: PlayerId) -> Result<(), PlayerActionError>;
     async fn abort(&self, game_id: GameId, player_id: PlayerId) -> ActionResult<AbortError>;
 }
@@ -59,33 +76,97 @@ pub enum DoActionError {
     NotPlayersTurn,
 }
 
+impl DoActionError {
+    /// A stable identifier of the error, see [`tak_core::InvalidActionReason::code`].
+    pub fn code(&self) -> &'static str {
+        match self {
+            DoActionError::InvalidAction(reason) => reason.code(),
+            DoActionError::NotPlayersTurn => "not_players_turn",
+        }
+    }
+}
+
+impl std::fmt::Display for DoActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DoActionError::InvalidAction(reason) => write!(f, "Invalid action: {}", reason),
+            DoActionError::NotPlayersTurn => write!(f, "Not player's turn"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SetPremovesError {
+    NotAsyncGame,
+    PlayersTurn,
+    InvalidPremoves(InvalidPremoveReason),
+}
+
+impl std::fmt::Display for SetPremovesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SetPremovesError::NotAsyncGame => write!(f, "Premoves are only allowed in async games"),
+            SetPremovesError::PlayersTurn => write!(f, "Premoves can't be set on your own turn"),
+            SetPremovesError::InvalidPremoves(reason) => write!(f, "Invalid premoves: {}", reason),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum HandleRequestError {
     RequestNotFound,
 }
 
+#[derive(Debug)]
 pub enum AbortError {
     GameAlreadyStarted,
 }
 
-pub struct DoActionUseCaseImpl<G: GameService, NP: NotifyPlayerWorkflow, F: FinalizeGameWorkflow> {
+impl std::fmt::Display for AbortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbortError::GameAlreadyStarted => {
+                write!(f, "Games can only be aborted before your first move")
+            }
+        }
+    }
+}
+
+pub struct DoActionUseCaseImpl<
+    G: GameService,
+    NP: NotifyPlayerWorkflow,
+    F: FinalizeGameWorkflow,
+    P: PersistGameWorkflow,
+    C: CorrespondenceWorkflow,
+> {
     game_service: Arc<G>,
     notify_player_workflow: Arc<NP>,
     finalize_game_workflow: Arc<F>,
+    persist_game_workflow: Arc<P>,
+    correspondence_workflow: Arc<C>,
 }
 
-impl<G: GameService, NP: NotifyPlayerWorkflow, F: FinalizeGameWorkflow>
-    DoActionUseCaseImpl<G, NP, F>
+impl<
+    G: GameService,
+    NP: NotifyPlayerWorkflow,
+    F: FinalizeGameWorkflow,
+    P: PersistGameWorkflow,
+    C: CorrespondenceWorkflow,
+> DoActionUseCaseImpl<G, NP, F, P, C>
 {
     pub fn new(
         game_service: Arc<G>,
         notify_player_workflow: Arc<NP>,
         finalize_game_workflow: Arc<F>,
+        persist_game_workflow: Arc<P>,
+        correspondence_workflow: Arc<C>,
     ) -> Self {
         Self {
             game_service,
             notify_player_workflow,
             finalize_game_workflow,
+            persist_game_workflow,
+            correspondence_workflow,
         }
     }
 
@@ -116,7 +197,9 @@ impl<
     G: GameService + Send + Sync + 'static,
     NP: NotifyPlayerWorkflow + Send + Sync + 'static,
     F: FinalizeGameWorkflow + Send + Sync + 'static,
-> DoActionUseCase for DoActionUseCaseImpl<G, NP, F>
+    P: PersistGameWorkflow + Send + Sync + 'static,
+    C: CorrespondenceWorkflow + Send + Sync + 'static,
+> DoActionUseCase for DoActionUseCaseImpl<G, NP, F, P, C>
 {
     async fn do_action(
         &self,
@@ -173,11 +256,72 @@ impl<
             self.notify_player_workflow
                 .notify_players_and_observers(game_id, &msg)
                 .await;
+            self.correspondence_workflow.notify_turn(game_id);
+            let premove = self.game_service.take_premove(game_id);
+            self.persist_game_workflow.persist_game(game_id).await;
+            if let Some((premove_player_id, response)) = premove {
+                tracing::debug!(
+                    "Playing premove of player {} in game {}",
+                    premove_player_id,
+                    game_id
+                );
+                let res = self.do_action(game_id, premove_player_id, response).await;
+                if !matches!(res, ActionResult::Success) {
+                    tracing::warn!("Failed to play premove in game {}: {:?}", game_id, res);
+                }
+            }
         }
 
         ActionResult::Success
     }
 
+    async fn set_premoves(
+        &self,
+        game_id: GameId,
+        player_id: PlayerId,
+        premoves: PremoveTree,
+    ) -> ActionResult<SetPremovesError> {
+        let now = Instant::now();
+        match self
+            .handle_game_action_result(
+                self.game_service
+                    .set_premoves(game_id, player_id, premoves, now),
+            )
+            .await
+        {
+            Err(e) => ActionResult::NotPossible(e),
+            Ok(SetPremovesResult::PremovesSet) => {
+                self.persist_game_workflow.persist_game(game_id).await;
+                ActionResult::Success
+            }
+            Ok(SetPremovesResult::NotAsyncGame) => {
+                ActionResult::ActionError(SetPremovesError::NotAsyncGame)
+            }
+            Ok(SetPremovesResult::PlayersTurn) => {
+                ActionResult::ActionError(SetPremovesError::PlayersTurn)
+            }
+            Ok(SetPremovesResult::InvalidPremoves(reason)) => {
+                ActionResult::ActionError(SetPremovesError::InvalidPremoves(reason))
+            }
+        }
+    }
+
+    fn get_premoves(
+        &self,
+        game_id: GameId,
+        player_id: PlayerId,
+    ) -> Result<PremoveTree, PlayerActionError> {
+        let game = self
+            .game_service
+            .get_game_by_id(game_id)
+            .ok_or(PlayerActionError::GameNotFound)?;
+        let player = game
+            .metadata
+            .get_player(player_id)
+            .ok_or(PlayerActionError::NotAPlayerInGame)?;
+        Ok(game.premoves.get(player).clone())
+    }
+
     async fn set_request(
         &self,
         game_id: GameId,
@@ -205,6 +349,7 @@ impl<
                 self.notify_player_workflow
                     .notify_players_and_observers(game_id, &msg)
                     .await;
+                self.persist_game_workflow.persist_game(game_id).await;
                 Ok(())
             }
         }
@@ -281,7 +426,9 @@ impl<
                             self.notify_player_workflow
                                 .notify_players_and_observers(game_id, &msg)
                                 .await;
+                            self.correspondence_workflow.notify_turn(game_id);
                         }
+                        self.persist_game_workflow.persist_game(game_id).await;
                         ActionResult::Success
                     }
                     Ok(None) => ActionResult::ActionError(HandleRequestError::RequestNotFound),
@@ -305,6 +452,7 @@ impl<
                         self.notify_player_workflow
                             .notify_players_and_observers(game_id, &request_msg)
                             .await;
+                        self.persist_game_workflow.persist_game(game_id).await;
                         ActionResult::Success
                     }
                     Ok(None) => ActionResult::ActionError(HandleRequestError::RequestNotFound),
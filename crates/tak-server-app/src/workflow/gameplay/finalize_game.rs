@@ -173,6 +173,7 @@ impl<
             game_id,
             event_type: ListenerGameMessageType::GameOver {
                 game_result: ended_game.game.game_result().clone(),
+                winning_road: ended_game.game.winning_road().clone(),
             },
             time_info: ended_game.game.get_time_info(),
         };
@@ -8,11 +8,14 @@ use crate::domain::{
     game::{FinishedGame, GameMetadata, OngoingGame, request::GameRequests},
 };
 
+pub mod correspondence;
 pub mod do_action;
 pub mod finalize_game;
 pub mod get;
 pub mod list;
 pub mod observe;
+pub mod persist;
+pub mod restore;
 pub mod timeout;
 
 #[derive(Clone, Debug)]
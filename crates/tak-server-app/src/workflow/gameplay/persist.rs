@@ -0,0 +1,67 @@
+use std::{sync::Arc, time::Instant};
+
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+
+use crate::domain::{GameId, game::GameService, game_history::GameRepository};
+
+#[async_trait::async_trait]
+pub trait PersistGameWorkflow {
+    async fn persist_game(&self, game_id: GameId);
+    async fn persist_all_games(&self);
+}
+
+pub struct PersistGameWorkflowImpl<G: GameService, GR: GameRepository> {
+    game_service: Arc<G>,
+    game_repository: Arc<GR>,
+    /// Serializes the writes of each game, so an older snapshot can't overwrite a newer one.
+    game_locks: DashMap<GameId, Arc<Mutex<()>>>,
+}
+
+impl<G: GameService, GR: GameRepository> PersistGameWorkflowImpl<G, GR> {
+    pub fn new(game_service: Arc<G>, game_repository: Arc<GR>) -> Self {
+        Self {
+            game_service,
+            game_repository,
+            game_locks: DashMap::new(),
+        }
+    }
+
+    async fn save(&self, game_id: GameId) {
+        let lock = self.game_locks.entry(game_id).or_default().clone();
+        let _guard = lock.lock().await;
+        // taking the snapshot while holding the lock makes the writes land in snapshot order
+        let Some(game) = self.game_service.get_game_by_id(game_id) else {
+            // the game has ended in the meantime, which is persisted when finalizing it
+            self.game_locks.remove(&game_id);
+            return;
+        };
+        if let Err(e) = self
+            .game_repository
+            .update_ongoing_game(game_id, game.snapshot(Instant::now()))
+            .await
+        {
+            tracing::error!("Failed to persist ongoing game {}: {}", game_id, e);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<G: GameService + Send + Sync + 'static, GR: GameRepository + Send + Sync + 'static>
+    PersistGameWorkflow for PersistGameWorkflowImpl<G, GR>
+{
+    async fn persist_game(&self, game_id: GameId) {
+        self.save(game_id).await;
+    }
+
+    async fn persist_all_games(&self) {
+        let game_ids = self
+            .game_service
+            .get_games()
+            .map(|game| game.game_id)
+            .collect::<Vec<_>>();
+        tracing::info!("Persisting {} ongoing games", game_ids.len());
+        let save_futures = game_ids.into_iter().map(|game_id| self.save(game_id));
+        futures::future::join_all(save_futures).await;
+    }
+}
@@ -0,0 +1,70 @@
+use std::{sync::Arc, time::Instant};
+
+use crate::{
+    domain::{game::GameService, game_history::GameRepository},
+    processes::game_timeout_runner::GameTimeoutRunner,
+};
+
+#[async_trait::async_trait]
+pub trait RestoreGamesWorkflow {
+    /// Resumes the games that were still ongoing when the server stopped.
+    async fn restore_games(&self);
+}
+
+pub struct RestoreGamesWorkflowImpl<G: GameService, GR: GameRepository, GT: GameTimeoutRunner> {
+    game_service: Arc<G>,
+    game_repository: Arc<GR>,
+    game_timeout_runner: Arc<GT>,
+}
+
+impl<G: GameService, GR: GameRepository, GT: GameTimeoutRunner>
+    RestoreGamesWorkflowImpl<G, GR, GT>
+{
+    pub fn new(
+        game_service: Arc<G>,
+        game_repository: Arc<GR>,
+        game_timeout_runner: Arc<GT>,
+    ) -> Self {
+        Self {
+            game_service,
+            game_repository,
+            game_timeout_runner,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<
+    G: GameService + Send + Sync + 'static,
+    GR: GameRepository + Send + Sync + 'static,
+    GT: GameTimeoutRunner + Send + Sync + 'static,
+> RestoreGamesWorkflow for RestoreGamesWorkflowImpl<G, GR, GT>
+{
+    async fn restore_games(&self) {
+        let games = match self.game_repository.get_ongoing_games().await {
+            Ok(games) => games,
+            Err(e) => {
+                tracing::error!("Failed to load ongoing games: {}", e);
+                return;
+            }
+        };
+        let now = Instant::now();
+        let mut restored = 0;
+        for (game_id, metadata, snapshot) in games {
+            if self
+                .game_service
+                .restore_game(game_id, metadata, snapshot, now)
+                .is_none()
+            {
+                tracing::error!("Stored state of game {} can't be resumed", game_id);
+                continue;
+            }
+            GameTimeoutRunner::schedule_game_timeout_check(
+                self.game_timeout_runner.clone(),
+                game_id,
+            );
+            restored += 1;
+        }
+        tracing::info!("Restored {} ongoing games", restored);
+    }
+}
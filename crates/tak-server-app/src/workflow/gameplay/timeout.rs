@@ -51,10 +51,24 @@ impl<G: GameService + Send + Sync + 'static, F: FinalizeGameWorkflow + Send + Sy
                 self.finalize_game_workflow.finalize_game(game).await;
                 ObserveOutcome::Finished
             }
-            CheckTimeoutResult::NoTimeout(time_info) => ObserveOutcome::Continue(
-                time_info.white_remaining.min(time_info.black_remaining)
-                    + std::time::Duration::from_millis(100),
-            ),
+            CheckTimeoutResult::Aborted(game) => {
+                tracing::info!(
+                    "Game {:?} aborted because the first ply wasn't made in time",
+                    game_id
+                );
+                self.finalize_game_workflow.finalize_game(game).await;
+                ObserveOutcome::Finished
+            }
+            CheckTimeoutResult::NoTimeout {
+                time_info,
+                first_ply_remaining,
+            } => {
+                let remaining = time_info.white_remaining.min(time_info.black_remaining);
+                ObserveOutcome::Continue(
+                    first_ply_remaining.map_or(remaining, |first_ply| first_ply.min(remaining))
+                        + std::time::Duration::from_millis(100),
+                )
+            }
 
             CheckTimeoutResult::GameNotFound => ObserveOutcome::Finished,
         }
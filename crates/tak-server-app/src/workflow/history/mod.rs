@@ -1,4 +1,8 @@
-use tak_core::{TakAction, TakGameResult, TakTimeInfo, TakTimeSettings};
+use tak_core::{
+    TakAction, TakGameResult, TakOngoingBaseGame, TakPlyTime, TakPos, TakTimeInfo, TakTimeSettings,
+    TakWinReason,
+    ptn::{Ptn, game_to_ptn},
+};
 
 use crate::{
     domain::{
@@ -35,10 +39,18 @@ impl GameRecordView {
     }
 
     pub fn reconstruct_action_history(&self) -> Vec<TakAction> {
+        self.reconstruct_timed_actions()
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect()
+    }
+
+    /// The actions that are still part of the game, each with the clocks right after it.
+    fn reconstruct_timed_actions(&self) -> Vec<(TakAction, TakTimeInfo)> {
         let mut actions = Vec::new();
         for event in &self.events {
             if let GameEventType::Action { action, .. } = &event.event_type {
-                actions.push(action.clone());
+                actions.push((action.clone(), event.time_info.clone()));
             } else if let GameEventType::ActionUndone { .. } = &event.event_type {
                 actions.pop();
             }
@@ -46,6 +58,63 @@ impl GameRecordView {
         actions
     }
 
+    /// The clock usage of every ply still part of the game, `None` where it wasn't recorded.
+    pub fn reconstruct_ply_times(&self) -> Vec<Option<TakPlyTime>> {
+        let mut ply_times = Vec::new();
+        for event in &self.events {
+            if let GameEventType::Action { ply_time, .. } = &event.event_type {
+                ply_times.push(ply_time.clone());
+            } else if let GameEventType::ActionUndone = &event.event_type {
+                ply_times.pop();
+            }
+        }
+        ply_times
+    }
+
+    /// Exports the game as PTN with a clock comment after every move.
+    pub fn to_ptn(&self) -> Ptn {
+        let player = |snapshot: &PlayerSnapshot| {
+            (
+                snapshot.username.clone().unwrap_or_default(),
+                snapshot.rating,
+            )
+        };
+        let (actions, clocks): (Vec<_>, Vec<_>) = self
+            .reconstruct_timed_actions()
+            .into_iter()
+            .map(|(action, time_info)| (action, Some(time_info)))
+            .unzip();
+        game_to_ptn(
+            &self.metadata.settings,
+            self.result.clone(),
+            actions,
+            player(&self.white),
+            player(&self.black),
+            self.metadata.date,
+        )
+        .with_clocks(clocks)
+    }
+
+    /// Replays the game to find the squares of the winning road, empty unless it was won by road.
+    pub fn reconstruct_winning_road(&self) -> Vec<TakPos> {
+        let Some(TakGameResult::Win {
+            reason: TakWinReason::Road,
+            ..
+        }) = &self.result
+        else {
+            return Vec::new();
+        };
+        let mut game = TakOngoingBaseGame::new(self.metadata.settings.base.clone());
+        for action in self.reconstruct_action_history() {
+            match game.do_action(action) {
+                Ok(None) => {}
+                Ok(Some(finished)) => return finished.winning_road,
+                Err(_) => break,
+            }
+        }
+        Vec::new()
+    }
+
     pub fn reconstruct_time_info(&self) -> TakTimeInfo {
         let maybe_time_info = self.events.last().map(|event| event.time_info.clone());
         match maybe_time_info {
@@ -55,6 +124,10 @@ impl GameRecordView {
                     white_remaining: s.contingent,
                     black_remaining: s.contingent,
                 },
+                TakTimeSettings::RealtimeOdds(white, black) => TakTimeInfo {
+                    white_remaining: white.contingent,
+                    black_remaining: black.contingent,
+                },
                 TakTimeSettings::Async(s) => TakTimeInfo {
                     white_remaining: s.contingent,
                     black_remaining: s.contingent,
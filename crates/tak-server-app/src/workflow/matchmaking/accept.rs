@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
-use tak_core::{TakPlayer, TakTimeSettings};
+use tak_core::TakPlayer;
 
 use crate::{
     domain::{
         PlayerId, SeekId,
+        drain::DrainService,
         matches::{Match, MatchMode, MatchRepository, MatchSettings},
         seek::SeekService,
     },
@@ -22,11 +23,13 @@ pub struct AcceptSeekUseCaseImpl<
     M: MatchRepository,
     L: ListenerNotificationPort,
     C: CreateGameFromMatchWorkflow,
+    D: DrainService,
 > {
     seek_service: Arc<S>,
     match_repo: Arc<M>,
     notification_port: Arc<L>,
     create_game_workflow: Arc<C>,
+    drain_service: Arc<D>,
 }
 
 impl<
@@ -34,19 +37,22 @@ impl<
     M: MatchRepository,
     L: ListenerNotificationPort,
     C: CreateGameFromMatchWorkflow,
-> AcceptSeekUseCaseImpl<S, M, L, C>
+    D: DrainService,
+> AcceptSeekUseCaseImpl<S, M, L, C, D>
 {
     pub fn new(
         seek_service: Arc<S>,
         match_repo: Arc<M>,
         notification_port: Arc<L>,
         create_game_workflow: Arc<C>,
+        drain_service: Arc<D>,
     ) -> Self {
         Self {
             seek_service,
             match_repo,
             notification_port,
             create_game_workflow,
+            drain_service,
         }
     }
 }
@@ -54,6 +60,7 @@ impl<
 pub enum AcceptSeekError {
     SeekNotFound,
     FailedToCreateGame,
+    ServerDraining,
 }
 
 #[async_trait::async_trait]
@@ -62,28 +69,26 @@ impl<
     M: MatchRepository + Send + Sync + 'static,
     L: ListenerNotificationPort + Send + Sync + 'static,
     C: CreateGameFromMatchWorkflow + Send + Sync + 'static,
-> AcceptSeekUseCase for AcceptSeekUseCaseImpl<S, M, L, C>
+    D: DrainService + Send + Sync + 'static,
+> AcceptSeekUseCase for AcceptSeekUseCaseImpl<S, M, L, C, D>
 {
     #[tracing::instrument(skip(self))]
     async fn accept_seek(&self, player: PlayerId, seek_id: SeekId) -> Result<(), AcceptSeekError> {
+        if self.drain_service.is_draining() {
+            return Err(AcceptSeekError::ServerDraining);
+        }
         let seek = self
             .seek_service
             .remove_seek_if(seek_id, |s| s.creator_id != player)
             .ok_or(AcceptSeekError::SeekNotFound)?;
 
         let cancelled_seeks = self.seek_service.cancel_player_seeks(player, |seek| {
-            matches!(
-                seek.game_settings.time_settings,
-                TakTimeSettings::Realtime(_)
-            )
+            seek.game_settings.time_settings.is_realtime()
         });
         let cancelled_seeks2 = self
             .seek_service
             .cancel_player_seeks(seek.creator_id, |seek| {
-                matches!(
-                    seek.game_settings.time_settings,
-                    TakTimeSettings::Realtime(_)
-                )
+                seek.game_settings.time_settings.is_realtime()
             });
 
         for cancelled_seek in cancelled_seeks
@@ -162,6 +167,14 @@ impl<
                 );
                 Err(AcceptSeekError::FailedToCreateGame)
             }
+            Err(CreateGameFromMatchError::InvalidGameSettings) => {
+                tracing::error!(
+                    "Failed to create game from match {}: invalid game settings",
+                    match_id
+                );
+                Err(AcceptSeekError::FailedToCreateGame)
+            }
+            Err(CreateGameFromMatchError::ServerDraining) => Err(AcceptSeekError::ServerDraining),
         }
     }
 }
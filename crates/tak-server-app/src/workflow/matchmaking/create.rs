@@ -5,6 +5,7 @@ use tak_core::{TakGameSettings, TakPlayer};
 use crate::{
     domain::{
         PlayerId,
+        drain::DrainService,
         seek::{CreateSeekError, SeekService},
     },
     ports::notification::{ListenerMessage, ListenerNotificationPort},
@@ -21,22 +22,27 @@ pub trait CreateSeekUseCase {
     ) -> Result<SeekView, CreateSeekError>;
 }
 
-pub struct CreateSeekUseCaseImpl<S: SeekService, L: ListenerNotificationPort> {
+pub struct CreateSeekUseCaseImpl<S: SeekService, L: ListenerNotificationPort, D: DrainService> {
     seek_service: Arc<S>,
     notification_port: Arc<L>,
+    drain_service: Arc<D>,
 }
 
-impl<S: SeekService, L: ListenerNotificationPort> CreateSeekUseCaseImpl<S, L> {
-    pub fn new(seek_service: Arc<S>, notification_port: Arc<L>) -> Self {
+impl<S: SeekService, L: ListenerNotificationPort, D: DrainService> CreateSeekUseCaseImpl<S, L, D> {
+    pub fn new(seek_service: Arc<S>, notification_port: Arc<L>, drain_service: Arc<D>) -> Self {
         Self {
             seek_service,
             notification_port,
+            drain_service,
         }
     }
 }
 
-impl<S: SeekService + Send + Sync + 'static, L: ListenerNotificationPort + Send + Sync + 'static>
-    CreateSeekUseCase for CreateSeekUseCaseImpl<S, L>
+impl<
+    S: SeekService + Send + Sync + 'static,
+    L: ListenerNotificationPort + Send + Sync + 'static,
+    D: DrainService + Send + Sync + 'static,
+> CreateSeekUseCase for CreateSeekUseCaseImpl<S, L, D>
 {
     fn create_seek(
         &self,
@@ -45,6 +51,9 @@ impl<S: SeekService + Send + Sync + 'static, L: ListenerNotificationPort + Send
         game_settings: TakGameSettings,
         is_rated: bool,
     ) -> Result<SeekView, CreateSeekError> {
+        if self.drain_service.is_draining() {
+            return Err(CreateSeekError::ServerDraining);
+        }
         let created_seek = self
             .seek_service
             .create_seek(player, color, game_settings, is_rated)?;
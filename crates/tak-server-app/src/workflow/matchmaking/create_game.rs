@@ -5,13 +5,17 @@ use tak_core::TakPlayer;
 use crate::{
     domain::{
         MatchId, RepoRetrieveError,
+        drain::DrainService,
         game::GameService,
         game_history::{GameHistoryService, GameRepository},
         matches::MatchRepository,
     },
     ports::notification::{ListenerMessage, ListenerNotificationPort},
     processes::game_timeout_runner::GameTimeoutRunner,
-    workflow::{account::get_snapshot::GetSnapshotWorkflow, gameplay::OngoingGameView},
+    workflow::{
+        account::get_snapshot::GetSnapshotWorkflow,
+        gameplay::{OngoingGameView, persist::PersistGameWorkflow},
+    },
 };
 
 #[async_trait::async_trait]
@@ -27,6 +31,8 @@ pub enum CreateGameFromMatchError {
     MatchNotFound,
     RepositoryError,
     AlreadyInProgress,
+    ServerDraining,
+    InvalidGameSettings,
 }
 
 pub struct CreateGameFromMatchWorkflowImpl<
@@ -37,6 +43,8 @@ pub struct CreateGameFromMatchWorkflowImpl<
     GT: GameTimeoutRunner,
     L: ListenerNotificationPort,
     S: GetSnapshotWorkflow,
+    D: DrainService,
+    P: PersistGameWorkflow,
 > {
     match_repo: Arc<M>,
     game_history_service: Arc<GH>,
@@ -45,6 +53,8 @@ pub struct CreateGameFromMatchWorkflowImpl<
     game_timeout_runner: Arc<GT>,
     listener_notification_port: Arc<L>,
     get_snapshot_workflow: Arc<S>,
+    drain_service: Arc<D>,
+    persist_game_workflow: Arc<P>,
 }
 impl<
     M: MatchRepository,
@@ -54,7 +64,9 @@ impl<
     GT: GameTimeoutRunner,
     L: ListenerNotificationPort,
     S: GetSnapshotWorkflow,
-> CreateGameFromMatchWorkflowImpl<M, GH, GR, G, GT, L, S>
+    D: DrainService,
+    P: PersistGameWorkflow,
+> CreateGameFromMatchWorkflowImpl<M, GH, GR, G, GT, L, S, D, P>
 {
     pub fn new(
         match_repo: Arc<M>,
@@ -64,6 +76,8 @@ impl<
         game_timeout_runner: Arc<GT>,
         listener_notification_port: Arc<L>,
         get_snapshot_workflow: Arc<S>,
+        drain_service: Arc<D>,
+        persist_game_workflow: Arc<P>,
     ) -> Self {
         Self {
             match_repo,
@@ -73,6 +87,8 @@ impl<
             game_timeout_runner,
             listener_notification_port,
             get_snapshot_workflow,
+            drain_service,
+            persist_game_workflow,
         }
     }
 }
@@ -86,13 +102,18 @@ impl<
     GT: GameTimeoutRunner + Send + Sync,
     L: ListenerNotificationPort + Send + Sync,
     S: GetSnapshotWorkflow + Send + Sync,
-> CreateGameFromMatchWorkflow for CreateGameFromMatchWorkflowImpl<M, GH, GR, G, GT, L, S>
+    D: DrainService + Send + Sync,
+    P: PersistGameWorkflow + Send + Sync,
+> CreateGameFromMatchWorkflow for CreateGameFromMatchWorkflowImpl<M, GH, GR, G, GT, L, S, D, P>
 {
     #[tracing::instrument(skip(self))]
     async fn create_game_from_match(
         &self,
         match_id: MatchId,
     ) -> Result<(), CreateGameFromMatchError> {
+        if self.drain_service.is_draining() {
+            return Err(CreateGameFromMatchError::ServerDraining);
+        }
         let date = chrono::Utc::now();
 
         let mut match_entry = self
@@ -106,6 +127,11 @@ impl<
                     CreateGameFromMatchError::RepositoryError
                 }
             })?;
+        // checked before anything is written, the game itself can't be created from these
+        if !match_entry.settings.game_settings.base.is_valid() {
+            tracing::error!("Invalid game settings in match {}", match_id);
+            return Err(CreateGameFromMatchError::InvalidGameSettings);
+        }
         let player1_color = match_entry.try_begin_game().map_err(|e| {
             tracing::error!("Failed to start game for match {}: {}", match_id, e);
             CreateGameFromMatchError::AlreadyInProgress
@@ -159,6 +185,7 @@ impl<
         }
 
         let game = self.game_service.create_game(game_id, metadata);
+        self.persist_game_workflow.persist_game(game_id).await;
 
         GameTimeoutRunner::schedule_game_timeout_check(self.game_timeout_runner.clone(), game_id);
 
@@ -7,7 +7,10 @@ use crate::{
     },
     ports::notification::{ListenerMatchEventType, ListenerMessage},
     workflow::{
-        matchmaking::{MatchReadinessStatus, create_game::CreateGameFromMatchWorkflow},
+        matchmaking::{
+            MatchReadinessStatus,
+            create_game::{CreateGameFromMatchError, CreateGameFromMatchWorkflow},
+        },
         player::notify_player::NotifyPlayerWorkflow,
     },
 };
@@ -86,6 +89,7 @@ impl<
 #[derive(Debug)]
 pub enum MatchReadinessError {
     MatchNotFound,
+    ServerDraining,
     Internal,
 }
 
@@ -131,13 +135,19 @@ impl<
             .match_readiness_service
             .set_player_ready(match_id, player_id);
         if should_create_game {
-            if let Err(e) = self
+            match self
                 .create_game_workflow
                 .create_game_from_match(match_id)
                 .await
             {
-                tracing::error!("Failed to create game from match {}: {:?}", match_id, e);
-                return Err(MatchReadinessError::Internal);
+                Ok(()) => {}
+                Err(CreateGameFromMatchError::ServerDraining) => {
+                    return Err(MatchReadinessError::ServerDraining);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to create game from match {}: {:?}", match_id, e);
+                    return Err(MatchReadinessError::Internal);
+                }
             }
         } else {
             let msg = ListenerMessage::MatchEvent {
@@ -1,35 +1,120 @@
-use std::{sync::Arc, time::Instant};
+use std::{sync::Arc, time::Duration};
 
-use crate::{domain::game::GameService, workflow::gameplay::finalize_game::FinalizeGameWorkflow};
+use chrono::{DateTime, Utc};
+use tokio::sync::watch;
+
+use crate::{
+    domain::{drain::DrainService, game::GameService},
+    ports::notification::{ListenerMessage, ListenerNotificationPort, ServerAlertMessage},
+    workflow::gameplay::persist::PersistGameWorkflow,
+};
+
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 #[async_trait::async_trait]
 pub trait ShutdownWorkflow {
+    /// Announces a shutdown at `deadline` and stops new games from being started, so running
+    /// realtime games can be finished before the server goes down.
+    fn start_draining(&self, deadline: DateTime<Utc>);
+    /// Returns whether the server was draining.
+    fn cancel_draining(&self) -> bool;
+    fn get_drain_deadline(&self) -> Option<DateTime<Utc>>;
+    /// Resolves once draining reached its deadline or no realtime games are left.
+    async fn wait_until_drained(&self);
     async fn shutdown(&self);
 }
 
-pub struct ShutdownWorkflowImpl<F: FinalizeGameWorkflow, G: GameService> {
-    finalize_game_workflow: Arc<F>,
+pub struct ShutdownWorkflowImpl<
+    D: DrainService,
+    G: GameService,
+    L: ListenerNotificationPort,
+    P: PersistGameWorkflow,
+> {
+    drain_service: Arc<D>,
     game_service: Arc<G>,
+    listener_notification_port: Arc<L>,
+    persist_game_workflow: Arc<P>,
+    drained: Arc<watch::Sender<bool>>,
 }
 
-impl<F: FinalizeGameWorkflow, G: GameService> ShutdownWorkflowImpl<F, G> {
-    pub fn new(finalize_game_workflow: Arc<F>, game_service: Arc<G>) -> Self {
+impl<D: DrainService, G: GameService, L: ListenerNotificationPort, P: PersistGameWorkflow>
+    ShutdownWorkflowImpl<D, G, L, P>
+{
+    pub fn new(
+        drain_service: Arc<D>,
+        game_service: Arc<G>,
+        listener_notification_port: Arc<L>,
+        persist_game_workflow: Arc<P>,
+    ) -> Self {
         Self {
-            finalize_game_workflow,
+            drain_service,
             game_service,
+            listener_notification_port,
+            persist_game_workflow,
+            drained: Arc::new(watch::Sender::new(false)),
         }
     }
 }
 
 #[async_trait::async_trait]
-impl<F: FinalizeGameWorkflow + Send + Sync + 'static, G: GameService + Send + Sync + 'static>
-    ShutdownWorkflow for ShutdownWorkflowImpl<F, G>
+impl<
+    D: DrainService + Send + Sync + 'static,
+    G: GameService + Send + Sync + 'static,
+    L: ListenerNotificationPort + Send + Sync + 'static,
+    P: PersistGameWorkflow + Send + Sync + 'static,
+> ShutdownWorkflow for ShutdownWorkflowImpl<D, G, L, P>
 {
+    fn start_draining(&self, deadline: DateTime<Utc>) {
+        tracing::info!("Draining server until {}", deadline);
+        self.drain_service.start_draining(deadline);
+        self.listener_notification_port
+            .notify_all(&ListenerMessage::ServerAlert {
+                message: ServerAlertMessage::Shutdown { deadline },
+            });
+
+        let drain_service = self.drain_service.clone();
+        let game_service = self.game_service.clone();
+        let drained = self.drained.clone();
+        tokio::spawn(async move {
+            // stops once draining is cancelled or restarted with another deadline
+            while drain_service.get_drain_deadline() == Some(deadline) {
+                let has_realtime_games = game_service
+                    .get_games()
+                    .any(|game| game.metadata.settings.time_settings.is_realtime());
+                let remaining = (deadline - Utc::now()).to_std().unwrap_or_default();
+                if !has_realtime_games || remaining.is_zero() {
+                    tracing::info!("Draining finished");
+                    drained.send_replace(true);
+                    return;
+                }
+                tokio::time::sleep(remaining.min(DRAIN_CHECK_INTERVAL)).await;
+            }
+        });
+    }
+
+    fn cancel_draining(&self) -> bool {
+        if !self.drain_service.cancel_draining() {
+            return false;
+        }
+        tracing::info!("Draining cancelled");
+        self.listener_notification_port
+            .notify_all(&ListenerMessage::ServerAlert {
+                message: ServerAlertMessage::ShutdownCancelled,
+            });
+        true
+    }
+
+    fn get_drain_deadline(&self) -> Option<DateTime<Utc>> {
+        self.drain_service.get_drain_deadline()
+    }
+
+    async fn wait_until_drained(&self) {
+        let mut drained = self.drained.subscribe();
+        let _ = drained.wait_for(|drained| *drained).await;
+    }
+
     async fn shutdown(&self) {
-        let games = self.game_service.abort_all_games(Instant::now());
-        let finalize_futures = games
-            .into_iter()
-            .map(|game| self.finalize_game_workflow.finalize_game(game));
-        futures::future::join_all(finalize_futures).await;
+        // ongoing games are resumed with paused clocks on the next start
+        self.persist_game_workflow.persist_all_games().await;
     }
 }
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use crate::domain::{
     RepoError, RepoRetrieveError, TournamentId,
+    drain::DrainService,
     matches::{Match, MatchRepository, MatchSettings, MatchStatus, MatchTournamentInfo},
     rating::RatingRepository,
     tournament::{
@@ -34,12 +35,14 @@ pub struct HostTournamentUseCaseImpl<
     TPR: TournamentPlayerRepository,
     TRR: TournamentRoundRepository,
     R: RatingRepository,
+    D: DrainService,
 > {
     tournament_repository: Arc<TR>,
     match_repository: Arc<M>,
     tournament_player_repository: Arc<TPR>,
     tournament_round_repository: Arc<TRR>,
     rating_repository: Arc<R>,
+    drain_service: Arc<D>,
 }
 
 impl<
@@ -48,7 +51,8 @@ impl<
     TPR: TournamentPlayerRepository,
     TRR: TournamentRoundRepository,
     R: RatingRepository,
-> HostTournamentUseCaseImpl<TR, M, TPR, TRR, R>
+    D: DrainService,
+> HostTournamentUseCaseImpl<TR, M, TPR, TRR, R, D>
 {
     pub fn new(
         tournament_repository: Arc<TR>,
@@ -56,6 +60,7 @@ impl<
         tournament_player_repository: Arc<TPR>,
         tournament_round_repository: Arc<TRR>,
         rating_repository: Arc<R>,
+        drain_service: Arc<D>,
     ) -> Self {
         Self {
             tournament_repository,
@@ -63,6 +68,7 @@ impl<
             tournament_player_repository,
             tournament_round_repository,
             rating_repository,
+            drain_service,
         }
     }
 }
@@ -74,7 +80,8 @@ impl<
     TPR: TournamentPlayerRepository + Send + Sync + 'static,
     TRR: TournamentRoundRepository + Send + Sync + 'static,
     R: RatingRepository + Send + Sync + 'static,
-> HostTournamentUseCase for HostTournamentUseCaseImpl<TR, M, TPR, TRR, R>
+    D: DrainService + Send + Sync + 'static,
+> HostTournamentUseCase for HostTournamentUseCaseImpl<TR, M, TPR, TRR, R, D>
 {
     #[tracing::instrument(skip(self))]
     async fn create_tournament(
@@ -108,6 +115,13 @@ impl<
 
     #[tracing::instrument(skip(self))]
     async fn begin_tournament(&self, tournament_id: TournamentId) -> Result<(), ()> {
+        if self.drain_service.is_draining() {
+            tracing::warn!(
+                "Not beginning tournament {} while the server is draining",
+                tournament_id
+            );
+            return Err(());
+        }
         let tournament = match self
             .tournament_repository
             .get_tournament(tournament_id)
@@ -218,6 +232,13 @@ impl<
 
     #[tracing::instrument(skip(self))]
     async fn start_next_round(&self, tournament_id: TournamentId) -> Result<(), ()> {
+        if self.drain_service.is_draining() {
+            tracing::warn!(
+                "Not starting the next round of tournament {} while the server is draining",
+                tournament_id
+            );
+            return Err(());
+        }
         let tournament = match self
             .tournament_repository
             .get_tournament(tournament_id)
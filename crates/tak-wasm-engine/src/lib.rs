@@ -252,6 +252,8 @@ impl Engine {
         (settings.half_komi == 0 || settings.half_komi == 4)
             && (settings.board_size >= 4 && settings.board_size <= 6)
             && TakReserve::from_size(settings.board_size).is_some_and(|x| x == settings.reserve)
+            && !settings.is_handicap()
+            && settings.rules.is_standard()
     }
 }
 
@@ -1,5 +1,6 @@
 import { useAccount } from '@/api/auth';
 import { useSubscription, useWebSocketStore } from '@/features/websocket';
+import { ptnGameResults } from '@/tak-core/ptn';
 import { useFetch } from '@/utils/fetch';
 import { useMutation, useQuery, useQueryClient } from '@tanstack/vue-query';
 import { onUnmounted, ref, toValue, watch, type MaybeRefOrGetter } from 'vue';
@@ -14,19 +15,29 @@ export const gameBaseSettingsSchema = z.object({
   opening: z.enum(['swap', 'noSwap', 'doubleStack']),
 });
 
+export const realtimeTimeControlSchema = z.object({
+  contingentMs: z.number(),
+  incrementMs: z.number(),
+  extra: z
+    .object({
+      onMove: z.number(),
+      extraMs: z.number(),
+    })
+    .nullable(),
+  incrementMode: z.enum(['fischer', 'delay', 'bronstein']).optional(),
+});
+
 export const gameSettingsSchema = z.object({
   ...gameBaseSettingsSchema.shape,
   timeSettings: z.union([
     z.object({
       type: z.literal('realtime'),
-      contingentMs: z.number(),
-      incrementMs: z.number(),
-      extra: z
-        .object({
-          onMove: z.number(),
-          extraMs: z.number(),
-        })
-        .nullable(),
+      ...realtimeTimeControlSchema.shape,
+    }),
+    z.object({
+      type: z.literal('realtimeOdds'),
+      white: realtimeTimeControlSchema,
+      black: realtimeTimeControlSchema,
     }),
     z.object({
       type: z.literal('async'),
@@ -63,7 +74,7 @@ export const gameStatusSchema = z.object({
   status: z.union([
     z.object({
       type: z.literal('ended'),
-      result: z.enum(['1-0', '0-1', '1/2-1/2', '0-F', 'F-0', '0-R', 'R-0', '0-0']),
+      result: z.enum(ptnGameResults),
     }),
     z.object({
       type: z.literal('ongoing'),
@@ -90,6 +101,7 @@ export const gameRequestSchema = z.union([
 
 export type GameStatus = z.infer<typeof gameStatusSchema>;
 export type GameSettings = z.infer<typeof gameSettingsSchema>;
+export type RealtimeTimeControl = z.infer<typeof realtimeTimeControlSchema>;
 export type GameMetadata = z.infer<typeof gameMetadataSchema>;
 export type GameRequests = z.infer<typeof gameRequestsSchema>;
 export type GameRequest = z.infer<typeof gameRequestSchema>;
@@ -124,7 +136,7 @@ export const gameEvent = z.union([
   }),
   z.object({
     eventType: z.literal('gameEnded'),
-    result: z.enum(['1-0', '0-1', '1/2-1/2', '0-F', 'F-0', '0-R', 'R-0']),
+    result: z.enum(ptnGameResults),
     ...gameEventBase.shape,
   }),
   z.object({
@@ -6,7 +6,8 @@ import {
   type TakGameResult,
   type TakGameSettings,
   type TakPlayer,
-  type TakRealtimeTimeControl,
+  type TakRealtimeClockSettings,
+  type TakTimeControl,
 } from '.';
 import { TakBaseGame } from './base';
 
@@ -19,7 +20,7 @@ export interface TakClock {
 export type TakClockUpdatePolicy =
   | {
       type: 'realtime';
-      timeControl: TakRealtimeTimeControl;
+      timeControls: Record<TakPlayer, TakRealtimeClockSettings>;
       hasGainedExtraTime: Record<TakPlayer, boolean>;
     }
   | {
@@ -27,6 +28,25 @@ export type TakClockUpdatePolicy =
       timeControl: TakAsyncTimeControl;
     };
 
+function clockUpdatePolicyFor(timeControl: TakTimeControl): TakClockUpdatePolicy {
+  switch (timeControl.type) {
+    case 'async':
+      return { type: 'async', timeControl };
+    case 'realtime':
+      return {
+        type: 'realtime',
+        timeControls: { white: timeControl, black: timeControl },
+        hasGainedExtraTime: { white: false, black: false },
+      };
+    case 'realtimeOdds':
+      return {
+        type: 'realtime',
+        timeControls: { white: timeControl.white, black: timeControl.black },
+        hasGainedExtraTime: { white: false, black: false },
+      };
+  }
+}
+
 export class TakGame {
   [immerable] = true;
 
@@ -36,28 +56,29 @@ export class TakGame {
 
   constructor(settings: TakGameSettings) {
     this.base = new TakBaseGame(settings.base);
+    this.clockUpdatePolicy = clockUpdatePolicyFor(settings.timeControl);
     this.clock = {
       remainingTimeMs: {
-        white: settings.timeControl.contingentMs,
-        black: settings.timeControl.contingentMs,
+        white: this.contingentMs('white'),
+        black: this.contingentMs('black'),
       },
       lastUpdateTimestamp: Date.now(),
       isTicking: false,
     };
-    this.clockUpdatePolicy =
-      settings.timeControl.type === 'realtime'
-        ? {
-            type: settings.timeControl.type,
-            timeControl: settings.timeControl,
-            hasGainedExtraTime: {
-              white: false,
-              black: false,
-            },
-          }
-        : {
-            type: settings.timeControl.type,
-            timeControl: settings.timeControl,
-          };
+  }
+
+  private contingentMs(player: TakPlayer): number {
+    return this.clockUpdatePolicy.type === 'realtime'
+      ? this.clockUpdatePolicy.timeControls[player].contingentMs
+      : this.clockUpdatePolicy.timeControl.contingentMs;
+  }
+
+  private delayMs(player: TakPlayer): number {
+    if (this.clockUpdatePolicy.type !== 'realtime') {
+      return 0;
+    }
+    const timeControl = this.clockUpdatePolicy.timeControls[player];
+    return timeControl.incrementMode === 'delay' ? timeControl.incrementMs : 0;
   }
 
   setGameOver(gameResult: TakGameResult, now: number) {
@@ -70,15 +91,17 @@ export class TakGame {
     this.clock.isTicking = false;
   }
 
-  private maybeApplyElapsed(player: TakPlayer, now: number) {
+  private maybeApplyElapsed(player: TakPlayer, now: number): number {
+    let elapsed = 0;
     if (this.clock.isTicking) {
-      const elapsed = now - this.clock.lastUpdateTimestamp;
+      elapsed = now - this.clock.lastUpdateTimestamp;
       this.clock.remainingTimeMs[player] = Math.max(
-        this.clock.remainingTimeMs[player] - elapsed,
+        this.clock.remainingTimeMs[player] - Math.max(elapsed - this.delayMs(player), 0),
         0,
       );
     }
     this.clock.lastUpdateTimestamp = now;
+    return elapsed;
   }
 
   getTimeRemaining(player: TakPlayer, now: number): number {
@@ -87,22 +110,29 @@ export class TakGame {
       return baseRemaining;
     }
     const elapsed = now - this.clock.lastUpdateTimestamp;
-    return Math.max(baseRemaining - elapsed, 0);
+    return Math.max(baseRemaining - Math.max(elapsed - this.delayMs(player), 0), 0);
   }
 
   private startOrUpdateClock(player: TakPlayer, now: number) {
-    this.maybeApplyElapsed(player, now);
+    const elapsed = this.maybeApplyElapsed(player, now);
     switch (this.clockUpdatePolicy.type) {
       case 'realtime': {
-        this.clock.remainingTimeMs[player] += this.clockUpdatePolicy.timeControl.incrementMs;
-        if (
-          this.clockUpdatePolicy.timeControl.extra !== null &&
-          !this.clockUpdatePolicy.hasGainedExtraTime[player]
-        ) {
+        const timeControl = this.clockUpdatePolicy.timeControls[player];
+        switch (timeControl.incrementMode ?? 'fischer') {
+          case 'fischer':
+            this.clock.remainingTimeMs[player] += timeControl.incrementMs;
+            break;
+          case 'delay':
+            break;
+          case 'bronstein':
+            this.clock.remainingTimeMs[player] += Math.min(elapsed, timeControl.incrementMs);
+            break;
+        }
+        if (timeControl.extra !== null && !this.clockUpdatePolicy.hasGainedExtraTime[player]) {
           const moveIndex = (this.base.actionHistory.length + 1) / 2;
 
-          if (moveIndex === this.clockUpdatePolicy.timeControl.extra.onMove) {
-            this.clock.remainingTimeMs[player] += this.clockUpdatePolicy.timeControl.extra.extraMs;
+          if (moveIndex === timeControl.extra.onMove) {
+            this.clock.remainingTimeMs[player] += timeControl.extra.extraMs;
             this.clockUpdatePolicy.hasGainedExtraTime[player] = true;
           }
         }
@@ -32,17 +32,32 @@ export interface TakAsyncTimeControl {
   contingentMs: number;
 }
 
-export interface TakRealtimeTimeControl {
-  type: 'realtime';
+export type TakIncrementMode = 'fischer' | 'delay' | 'bronstein';
+
+export interface TakRealtimeClockSettings {
   contingentMs: number;
   incrementMs: number;
   extra: {
     onMove: number;
     extraMs: number;
   } | null;
+  incrementMode?: TakIncrementMode;
 }
 
-export type TakTimeControl = TakAsyncTimeControl | TakRealtimeTimeControl;
+export interface TakRealtimeTimeControl extends TakRealtimeClockSettings {
+  type: 'realtime';
+}
+
+export interface TakRealtimeOddsTimeControl {
+  type: 'realtimeOdds';
+  white: TakRealtimeClockSettings;
+  black: TakRealtimeClockSettings;
+}
+
+export type TakTimeControl =
+  | TakAsyncTimeControl
+  | TakRealtimeTimeControl
+  | TakRealtimeOddsTimeControl;
 
 export interface TakPieceId {
   type: 'flat' | 'capstone';
@@ -82,7 +97,11 @@ export const allDirections = ['up', 'down', 'left', 'right'] as const;
 export type TakDir = (typeof allDirections)[number];
 
 export type TakGameResult =
-  | { type: 'win'; winner: TakPlayer; reason: 'default' | 'timeout' | 'resignation' }
+  | {
+      type: 'win';
+      winner: TakPlayer;
+      reason: 'default' | 'timeout' | 'resignation' | 'abandonment' | 'forfeit';
+    }
   | {
       type: 'win';
       winner: TakPlayer;
@@ -197,17 +216,31 @@ export function gameSettingsEquals(
     return a.timeControl.contingentMs === b.timeControl.contingentMs;
   }
   if (a.timeControl.type === 'realtime' && b.timeControl.type === 'realtime') {
-    const extraEqual =
-      (a.timeControl.extra === null && b.timeControl.extra === null) ||
-      (a.timeControl.extra !== null &&
-        b.timeControl.extra !== null &&
-        a.timeControl.extra.onMove === b.timeControl.extra.onMove &&
-        a.timeControl.extra.extraMs === b.timeControl.extra.extraMs);
+    return realtimeClockSettingsEquals(a.timeControl, b.timeControl);
+  }
+  if (a.timeControl.type === 'realtimeOdds' && b.timeControl.type === 'realtimeOdds') {
     return (
-      a.timeControl.contingentMs === b.timeControl.contingentMs &&
-      a.timeControl.incrementMs === b.timeControl.incrementMs &&
-      extraEqual
+      realtimeClockSettingsEquals(a.timeControl.white, b.timeControl.white) &&
+      realtimeClockSettingsEquals(a.timeControl.black, b.timeControl.black)
     );
   }
   return false;
 }
+
+function realtimeClockSettingsEquals(
+  a: TakRealtimeClockSettings,
+  b: TakRealtimeClockSettings,
+): boolean {
+  const extraEqual =
+    (a.extra === null && b.extra === null) ||
+    (a.extra !== null &&
+      b.extra !== null &&
+      a.extra.onMove === b.extra.onMove &&
+      a.extra.extraMs === b.extra.extraMs);
+  return (
+    a.contingentMs === b.contingentMs &&
+    a.incrementMs === b.incrementMs &&
+    (a.incrementMode ?? 'fischer') === (b.incrementMode ?? 'fischer') &&
+    extraEqual
+  );
+}
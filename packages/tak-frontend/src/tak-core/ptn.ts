@@ -159,7 +159,7 @@ export function gameToPTN(
 const PTN_ATTRIBUTES_REGEX = /(?:\[(\w*)\s"([^"]*)"\])\s*/g;
 const PTN_MOVES_REGEX = /(?!\d*\.)[^\s]+/g;
 
-const PTN_GAME_OVER_REGEX = /^(1\/2-1\/2|0-1|1-0|0-F|F-0|0-R|R-0|0-0)/;
+const PTN_GAME_OVER_REGEX = /^(1\/2-1\/2|0-0|[1RFTSAX]-0|0-[1RFTSAX])/;
 const INTEGER_REGEX = /^\d+$/;
 
 export function PTNToGame(ptn: string): {
@@ -240,10 +240,20 @@ export function PTNToGame(ptn: string): {
   return { game, playerInfo };
 }
 
+const winReasonLetters = {
+  road: 'R',
+  flats: 'F',
+  timeout: 'T',
+  resignation: 'S',
+  abandonment: 'A',
+  forfeit: 'X',
+  default: '1',
+} as const;
+
 export function gameResultToString(gameResult: TakGameResult): string {
   switch (gameResult.type) {
     case 'win': {
-      const letter = gameResult.reason === 'flats' ? 'F' : gameResult.reason === 'road' ? 'R' : '1';
+      const letter = winReasonLetters[gameResult.reason];
       return gameResult.winner === 'white' ? `${letter}-0` : `0-${letter}`;
     }
     case 'draw':
@@ -253,7 +263,25 @@ export function gameResultToString(gameResult: TakGameResult): string {
   }
 }
 
-export type TakPTNGameResult = '1-0' | '0-1' | '1/2-1/2' | '0-F' | 'F-0' | '0-R' | 'R-0' | '0-0';
+export const ptnGameResults = [
+  '1-0',
+  '0-1',
+  '1/2-1/2',
+  '0-F',
+  'F-0',
+  '0-R',
+  'R-0',
+  '0-T',
+  'T-0',
+  '0-S',
+  'S-0',
+  '0-A',
+  'A-0',
+  '0-X',
+  'X-0',
+  '0-0',
+] as const;
+export type TakPTNGameResult = (typeof ptnGameResults)[number];
 
 export function gameResultFromString<T extends string>(
   gameOverStr: T,
@@ -273,6 +301,22 @@ export function gameResultFromString<T extends string>(
       return { type: 'win', winner: 'black', reason: 'road' };
     case 'R-0':
       return { type: 'win', winner: 'white', reason: 'road' };
+    case '0-T':
+      return { type: 'win', winner: 'black', reason: 'timeout' };
+    case 'T-0':
+      return { type: 'win', winner: 'white', reason: 'timeout' };
+    case '0-S':
+      return { type: 'win', winner: 'black', reason: 'resignation' };
+    case 'S-0':
+      return { type: 'win', winner: 'white', reason: 'resignation' };
+    case '0-A':
+      return { type: 'win', winner: 'black', reason: 'abandonment' };
+    case 'A-0':
+      return { type: 'win', winner: 'white', reason: 'abandonment' };
+    case '0-X':
+      return { type: 'win', winner: 'black', reason: 'forfeit' };
+    case 'X-0':
+      return { type: 'win', winner: 'white', reason: 'forfeit' };
     case '0-0':
       return { type: 'aborted' };
     default:
@@ -1,4 +1,4 @@
-import type { GameSettings } from '@/api/game';
+import type { GameSettings, RealtimeTimeControl } from '@/api/game';
 import { differenceInMinutes, isAfter, startOfDay } from 'date-fns';
 
 export function timeFormat(milliseconds: number): string {
@@ -27,15 +27,28 @@ export function timeFormat(milliseconds: number): string {
 }
 
 export function timeControlToString(settings: GameSettings['timeSettings']): string {
-  if (settings.type === 'realtime') {
-    let res = `${timeFormat(settings.contingentMs)} + ${timeFormat(settings.incrementMs)}`;
-    if (settings.extra) {
-      res += ` (+ ${timeFormat(settings.extra.extraMs)} @ move ${settings.extra.onMove.toString()})`;
+  switch (settings.type) {
+    case 'realtime':
+      return realtimeTimeControlToString(settings);
+    case 'realtimeOdds': {
+      const white = realtimeTimeControlToString(settings.white);
+      const black = realtimeTimeControlToString(settings.black);
+      return `White: ${white}, Black: ${black}`;
     }
-    return res;
-  } else {
-    return `${timeFormat(settings.contingentMs)} per move`;
+    case 'async':
+      return `${timeFormat(settings.contingentMs)} per move`;
+  }
+}
+
+function realtimeTimeControlToString(settings: RealtimeTimeControl): string {
+  let res = `${timeFormat(settings.contingentMs)} + ${timeFormat(settings.incrementMs)}`;
+  if (settings.incrementMode === 'delay' || settings.incrementMode === 'bronstein') {
+    res += ` ${settings.incrementMode}`;
+  }
+  if (settings.extra) {
+    res += ` (+ ${timeFormat(settings.extra.extraMs)} @ move ${settings.extra.onMove.toString()})`;
   }
+  return res;
 }
 
 export function clockFormat(milliseconds: number): string {